@@ -41,8 +41,9 @@ impl Stage {
 
 		let bindings = Bindings {
 			vertex_buffers: vec![vertex_buffer],
-			index_buffer,
+			index_buffer: Some(index_buffer),
 			images: vec![],
+			samplers: vec![],
 		};
 
 		let shader = ctx.new_shader(ShaderSource::new(shader::VERTEX, shader::FRAGMENT), shader::meta()).unwrap();
@@ -232,6 +233,7 @@ mod shader {
 					UniformDesc::new("blobs_positions", UniformType::Float2).array(32),
 				],
 			},
+			uniform_blocks: vec![],
 		}
 	}
 
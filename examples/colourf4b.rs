@@ -29,8 +29,9 @@ impl Stage {
 
 		let bindings = Bindings {
 			vertex_buffers: vec![vertex_buffer],
-			index_buffer,
+			index_buffer: Some(index_buffer),
 			images: vec![],
+			samplers: vec![],
 		};
 
 		let shader = backend.new_shader(ShaderSource::new(shader::VERTEX, shader::FRAGMENT), shader::meta()).unwrap();
@@ -90,6 +91,7 @@ mod shader {
 		ShaderMeta {
 			images: vec![],
 			uniforms: UniformBlockLayout { uniforms: vec![] },
+			uniform_blocks: vec![],
 		}
 	}
 }
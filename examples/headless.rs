@@ -0,0 +1,118 @@
+use miniquad_wasm_bindgen::*;
+
+// Renders a single frame without a window and reads it back,
+// the way a golden-image test would.
+
+#[repr(C)]
+struct Vertex {
+	pos: [f32; 2],
+	color: [f32; 4],
+}
+
+struct Stage {
+	pipeline: Pipeline,
+	bindings: Bindings,
+	backend: Box<dyn RenderingBackend>,
+}
+
+impl Stage {
+	pub fn new() -> Stage {
+		let mut backend: Box<dyn RenderingBackend> = window::new_rendering_backend();
+
+		#[rustfmt::skip]
+        let vertices: [Vertex; 3] = [
+            Vertex { pos : [ -1.0, -1.0 ], color: [1., 0., 0., 1.] },
+            Vertex { pos : [  3.0, -1.0 ], color: [1., 0., 0., 1.] },
+            Vertex { pos : [ -1.0,  3.0 ], color: [1., 0., 0., 1.] },
+        ];
+		let vertex_buffer = backend.new_buffer(BufferType::VertexBuffer, BufferUsage::Immutable, BufferSource::slice(&vertices));
+
+		let indices: [u16; 3] = [0, 1, 2];
+		let index_buffer = backend.new_buffer(BufferType::IndexBuffer, BufferUsage::Immutable, BufferSource::slice(&indices));
+
+		let bindings = Bindings {
+			vertex_buffers: vec![vertex_buffer],
+			index_buffer: Some(index_buffer),
+			images: vec![],
+			samplers: vec![],
+		};
+
+		let shader = backend.new_shader(ShaderSource::new(shader::VERTEX, shader::FRAGMENT), shader::meta()).unwrap();
+
+		let pipeline = backend.new_pipeline(
+			&[BufferLayout::default()],
+			&[VertexAttribute::new("in_pos", VertexFormat::Float2), VertexAttribute::new("in_color", VertexFormat::Float4)],
+			shader,
+			PipelineParams::default(),
+		);
+
+		Stage { pipeline, bindings, backend }
+	}
+}
+
+impl EventHandler for Stage {
+	fn update(&mut self) {}
+
+	fn draw(&mut self) {
+		self.backend.begin_default_pass(Default::default());
+
+		self.backend.apply_pipeline(&self.pipeline);
+		self.backend.apply_bindings(&self.bindings);
+		self.backend.draw(0, 3, 1);
+		self.backend.end_render_pass();
+
+		self.backend.commit_frame();
+
+		let (w, h) = window::screen_size();
+		let mut pixels = vec![0u8; (w * h * 4) as usize];
+		self.backend.default_pass_read_pixels(&mut pixels);
+
+		let center = ((h / 2 * w + w / 2) * 4) as usize;
+		println!("{}x{} frame, center pixel: {:?}", w, h, &pixels[center..center + 4]);
+
+		window::quit();
+	}
+}
+
+fn main() {
+	let conf = conf::Conf {
+		window_width: 64,
+		window_height: 64,
+		platform: conf::PlatformSettings {
+			linux_backend: conf::LinuxBackend::Headless,
+			..Default::default()
+		},
+		..Default::default()
+	};
+	miniquad_wasm_bindgen::start(conf, move || Box::new(Stage::new()));
+}
+
+mod shader {
+	use miniquad_wasm_bindgen::*;
+
+	pub const VERTEX: &str = r#"#version 100
+    attribute vec2 in_pos;
+    attribute vec4 in_color;
+
+    varying lowp vec4 color;
+
+    void main() {
+        gl_Position = vec4(in_pos, 0, 1);
+        color = in_color;
+    }"#;
+
+	pub const FRAGMENT: &str = r#"#version 100
+    varying lowp vec4 color;
+
+    void main() {
+        gl_FragColor = color;
+    }"#;
+
+	pub fn meta() -> ShaderMeta {
+		ShaderMeta {
+			images: vec![],
+			uniforms: UniformBlockLayout { uniforms: vec![] },
+			uniform_blocks: vec![],
+		}
+	}
+}
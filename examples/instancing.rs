@@ -46,8 +46,9 @@ impl Stage {
 
 		let bindings = Bindings {
 			vertex_buffers: vec![geometry_vertex_buffer, positions_vertex_buffer],
-			index_buffer,
+			index_buffer: Some(index_buffer),
 			images: vec![],
+			samplers: vec![],
 		};
 
 		let shader = ctx.new_shader(ShaderSource::new(shader::VERTEX, shader::FRAGMENT), shader::meta()).unwrap();
@@ -174,6 +175,7 @@ mod shader {
 			uniforms: UniformBlockLayout {
 				uniforms: vec![UniformDesc::new("mvp", UniformType::Mat4)],
 			},
+			uniform_blocks: vec![],
 		}
 	}
 
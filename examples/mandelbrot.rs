@@ -54,8 +54,9 @@ impl Mandelbrot {
 
 		let bindings = Bindings {
 			vertex_buffers: vec![vertex_buffer],
-			index_buffer,
+			index_buffer: Some(index_buffer),
 			images: Vec::new(),
+			samplers: vec![],
 		};
 
 		let shader = backend.new_shader(ShaderSource::new(shader::VERTEX, shader::FRAGMENT), shader::meta()).unwrap();
@@ -258,6 +259,7 @@ void main() {
 			uniforms: UniformBlockLayout {
 				uniforms: vec![UniformDesc::new("transform", UniformType::Mat4)],
 			},
+			uniform_blocks: vec![],
 		}
 	}
 }
@@ -89,8 +89,9 @@ impl Stage {
 
 		let offscreen_bind = Bindings {
 			vertex_buffers: vec![vertex_buffer.clone()],
-			index_buffer: index_buffer.clone(),
+			index_buffer: Some(index_buffer),
 			images: vec![],
+			samplers: vec![],
 		};
 
 		let display_bind = {
@@ -107,8 +108,9 @@ impl Stage {
 
 			Bindings {
 				vertex_buffers: vec![vertex_buffer],
-				index_buffer: index_buffer,
+				index_buffer: Some(index_buffer),
 				images: vec![color_resolve_img],
+				samplers: vec![],
 			}
 		};
 
@@ -230,10 +232,11 @@ mod display_shader {
 
 	pub fn meta() -> ShaderMeta {
 		ShaderMeta {
-			images: vec!["tex".to_string()],
+			images: vec![ImageDesc::new("tex", ImageType::Float)],
 			uniforms: UniformBlockLayout {
 				uniforms: vec![UniformDesc::new("mvp", UniformType::Mat4)],
 			},
+			uniform_blocks: vec![],
 		}
 	}
 
@@ -274,6 +277,7 @@ mod offscreen_shader {
 			uniforms: UniformBlockLayout {
 				uniforms: vec![UniformDesc::new("mvp", UniformType::Mat4)],
 			},
+			uniform_blocks: vec![],
 		}
 	}
 }
@@ -83,14 +83,16 @@ impl Stage {
 
 		let offscreen_bind = Bindings {
 			vertex_buffers: vec![vertex_buffer],
-			index_buffer: index_buffer,
+			index_buffer: Some(index_buffer),
 			images: vec![],
+			samplers: vec![],
 		};
 
 		let display_bind = Bindings {
 			vertex_buffers: vec![vertex_buffer],
-			index_buffer,
+			index_buffer: Some(index_buffer),
 			images: vec![color_img],
+			samplers: vec![],
 		};
 
 		let default_shader = ctx.new_shader(ShaderSource::new(display_shader::VERTEX, display_shader::FRAGMENT), display_shader::meta()).unwrap();
@@ -212,10 +214,11 @@ mod display_shader {
 
 	pub fn meta() -> ShaderMeta {
 		ShaderMeta {
-			images: vec!["tex".to_string()],
+			images: vec![ImageDesc::new("tex", ImageType::Float)],
 			uniforms: UniformBlockLayout {
 				uniforms: vec![UniformDesc::new("mvp", UniformType::Mat4)],
 			},
+			uniform_blocks: vec![],
 		}
 	}
 
@@ -256,6 +259,7 @@ mod offscreen_shader {
 			uniforms: UniformBlockLayout {
 				uniforms: vec![UniformDesc::new("mvp", UniformType::Mat4)],
 			},
+			uniform_blocks: vec![],
 		}
 	}
 }
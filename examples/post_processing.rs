@@ -84,8 +84,9 @@ impl Stage {
 
 		let offscreen_bind = Bindings {
 			vertex_buffers: vec![vertex_buffer],
-			index_buffer: index_buffer,
+			index_buffer: Some(index_buffer),
 			images: vec![],
+			samplers: vec![],
 		};
 
 		#[rustfmt::skip]
@@ -105,8 +106,9 @@ impl Stage {
 
 		let post_processing_bind = Bindings {
 			vertex_buffers: vec![vertex_buffer],
-			index_buffer,
+			index_buffer: Some(index_buffer),
 			images: vec![color_img],
+			samplers: vec![],
 		};
 
 		let default_shader = ctx
@@ -254,10 +256,11 @@ mod post_processing_shader {
 
 	pub fn meta() -> ShaderMeta {
 		ShaderMeta {
-			images: vec!["tex".to_string()],
+			images: vec![ImageDesc::new("tex", ImageType::Float)],
 			uniforms: UniformBlockLayout {
 				uniforms: vec![UniformDesc::new("resolution", UniformType::Float2)],
 			},
+			uniform_blocks: vec![],
 		}
 	}
 
@@ -299,6 +302,7 @@ mod offscreen_shader {
 			uniforms: UniformBlockLayout {
 				uniforms: vec![UniformDesc::new("mvp", UniformType::Mat4)],
 			},
+			uniform_blocks: vec![],
 		}
 	}
 
@@ -42,8 +42,9 @@ impl Stage {
 
 		let bindings = Bindings {
 			vertex_buffers: vec![vertex_buffer],
-			index_buffer,
+			index_buffer: Some(index_buffer),
 			images: vec![texture],
+			samplers: vec![],
 		};
 
 		let shader = ctx.new_shader(ShaderSource::new(shader::VERTEX, shader::FRAGMENT), shader::meta()).unwrap();
@@ -114,10 +115,11 @@ mod shader {
 
 	pub fn meta() -> ShaderMeta {
 		ShaderMeta {
-			images: vec!["tex".to_string()],
+			images: vec![ImageDesc::new("tex", ImageType::Float)],
 			uniforms: UniformBlockLayout {
 				uniforms: vec![UniformDesc::new("offset", UniformType::Float2)],
 			},
+			uniform_blocks: vec![],
 		}
 	}
 
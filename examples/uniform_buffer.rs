@@ -0,0 +1,153 @@
+use miniquad_wasm_bindgen::*;
+
+// Uniforms of every quad live in one uniform buffer,
+// each draw binds its own range of it instead of uploading uniforms.
+
+const QUADS: usize = 4;
+
+#[repr(C)]
+struct Vertex {
+	pos: [f32; 2],
+}
+
+struct Stage {
+	pipeline: Pipeline,
+	bindings: Bindings,
+	uniform_buffer: BufferId,
+	// distance between the uniforms of two quads, respecting uniform_buffer_offset_alignment
+	uniforms_stride: usize,
+	backend: Box<dyn RenderingBackend>,
+}
+
+impl Stage {
+	pub fn new() -> Stage {
+		let mut backend: Box<dyn RenderingBackend> = window::new_rendering_backend();
+
+		#[rustfmt::skip]
+        let vertices: [Vertex; 4] = [
+            Vertex { pos : [ -0.5, -0.5 ] },
+            Vertex { pos : [  0.5, -0.5 ] },
+            Vertex { pos : [  0.5,  0.5 ] },
+            Vertex { pos : [ -0.5,  0.5 ] },
+        ];
+		let vertex_buffer = backend.new_buffer(BufferType::VertexBuffer, BufferUsage::Immutable, BufferSource::slice(&vertices));
+
+		let indices: [u16; 6] = [0, 1, 2, 0, 2, 3];
+		let index_buffer = backend.new_buffer(BufferType::IndexBuffer, BufferUsage::Immutable, BufferSource::slice(&indices));
+
+		let alignment = backend.info().uniform_buffer_offset_alignment;
+		assert!(alignment != 0, "Uniform buffers are not supported");
+		let uniforms_stride = std::mem::size_of::<shader::Uniforms>().div_ceil(alignment) * alignment;
+
+		let mut uniforms = vec![0u8; uniforms_stride * QUADS];
+		for i in 0..QUADS {
+			let x = i as f32 / QUADS as f32 * 2. - 0.75;
+			let block = shader::Uniforms {
+				offset: [x, 0.],
+				scale: 0.4,
+				_pad: 0.,
+				color: [1. - i as f32 / QUADS as f32, i as f32 / QUADS as f32, 0.5, 1.],
+			};
+			let bytes = unsafe { std::slice::from_raw_parts(&block as *const _ as *const u8, std::mem::size_of::<shader::Uniforms>()) };
+			uniforms[i * uniforms_stride..][..bytes.len()].copy_from_slice(bytes);
+		}
+		let uniform_buffer = backend.new_buffer(BufferType::UniformBuffer, BufferUsage::Immutable, BufferSource::slice(&uniforms));
+
+		let bindings = Bindings {
+			vertex_buffers: vec![vertex_buffer],
+			index_buffer: Some(index_buffer),
+			images: vec![],
+			samplers: vec![],
+		};
+
+		let shader = backend.new_shader(ShaderSource::new(shader::VERTEX, shader::FRAGMENT), shader::meta()).unwrap();
+
+		let pipeline = backend.new_pipeline(&[BufferLayout::default()], &[VertexAttribute::new("in_pos", VertexFormat::Float2)], shader, PipelineParams::default());
+
+		Stage {
+			pipeline,
+			bindings,
+			uniform_buffer,
+			uniforms_stride,
+			backend,
+		}
+	}
+}
+
+impl EventHandler for Stage {
+	fn update(&mut self) {}
+
+	fn draw(&mut self) {
+		self.backend.begin_default_pass(Default::default());
+
+		self.backend.apply_pipeline(&self.pipeline);
+		self.backend.apply_bindings(&self.bindings);
+		for i in 0..QUADS {
+			self.backend
+				.apply_uniform_buffer(shader::TRANSFORM_BINDING, self.uniform_buffer, i * self.uniforms_stride, std::mem::size_of::<shader::Uniforms>());
+			self.backend.draw(0, 6, 1);
+		}
+		self.backend.end_render_pass();
+
+		self.backend.commit_frame();
+	}
+}
+
+fn main() {
+	miniquad_wasm_bindgen::start(conf::Conf::default(), move || Box::new(Stage::new()));
+}
+
+mod shader {
+	use miniquad_wasm_bindgen::*;
+
+	pub const VERTEX: &str = r#"#version 300 es
+    in vec2 in_pos;
+
+    layout(std140) uniform Transform {
+        vec2 offset;
+        float scale;
+        vec4 color;
+    };
+
+    out lowp vec4 v_color;
+
+    void main() {
+        gl_Position = vec4(in_pos * scale + offset, 0, 1);
+        v_color = color;
+    }"#;
+
+	pub const FRAGMENT: &str = r#"#version 300 es
+    in lowp vec4 v_color;
+    out lowp vec4 FragColor;
+
+    void main() {
+        FragColor = v_color;
+    }"#;
+
+	pub const TRANSFORM_BINDING: u32 = 0;
+
+	pub fn meta() -> ShaderMeta {
+		ShaderMeta {
+			images: vec![],
+			uniforms: UniformBlockLayout { uniforms: vec![] },
+			uniform_blocks: vec![UniformBlockDesc::new(
+				"Transform",
+				TRANSFORM_BINDING,
+				vec![
+					UniformDesc::new("offset", UniformType::Float2),
+					UniformDesc::new("scale", UniformType::Float1),
+					UniformDesc::new("color", UniformType::Float4),
+				],
+			)],
+		}
+	}
+
+	// std140: `color` is aligned to 16 bytes
+	#[repr(C)]
+	pub struct Uniforms {
+		pub offset: [f32; 2],
+		pub scale: f32,
+		pub _pad: f32,
+		pub color: [f32; 4],
+	}
+}
@@ -61,6 +61,12 @@ pub enum LinuxBackend {
 	WaylandOnly,
 	X11WithWaylandFallback,
 	WaylandWithX11Fallback,
+	/// No window at all: render into an offscreen EGL pbuffer of
+	/// `window_width`x`window_height`, works without a running X11 or Wayland server.
+	/// There are no input events, update()/draw() are called back to back
+	/// until `window::quit()`.
+	/// Useful for CI and golden-image tests, see `RenderingBackend::default_pass_read_pixels`.
+	Headless,
 }
 
 /// Platform specific settings.
@@ -75,6 +81,7 @@ pub struct PlatformSettings {
 	/// to load "libX11.so", but if there is no - will try to initialize
 	/// through wayland natively. If both  fails (no graphics server at
 	/// all, like KMS) - will panic.
+	/// `Headless` skips the display server entirely and renders offscreen.
 	///
 	/// Defaults to X11Only. Wayland implementation is way too unstable right now.
 	pub linux_backend: LinuxBackend,
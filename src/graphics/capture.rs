@@ -0,0 +1,1614 @@
+//! Frame capture: a `RenderingBackend` wrapper recording every call into a
+//! `FrameCapture`, which can be saved with `to_bytes`, loaded back with `from_bytes`
+//! and replayed into any other backend.
+//!
+//! Think of it as a very small RenderDoc, working the same way on desktop and on the web.
+
+use std::{
+	cell::RefCell,
+	collections::{HashMap, HashSet},
+};
+
+use super::*;
+
+/// Backend-agnostic copy of `TextureSource`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CapturedTextureSource {
+	Empty,
+	Bytes(Vec<u8>),
+	/// Array of `[cubemap_face][mipmap_level][bytes]`
+	Array(Vec<Vec<Vec<u8>>>),
+}
+
+/// Backend-agnostic copy of `BufferSource`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CapturedBufferSource {
+	Slice { bytes: Vec<u8>, element_size: usize },
+	Empty { size: usize, element_size: usize },
+}
+
+/// Backend-agnostic copy of `VertexAttribute`, owning the name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapturedVertexAttribute {
+	pub name: String,
+	pub format: VertexFormat,
+	pub buffer_index: usize,
+}
+
+/// Error returned by `FrameCapture::from_bytes`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CaptureError {
+	/// Not a capture made by `FrameCapture::to_bytes`
+	BadMagic,
+	UnsupportedVersion(u32),
+	UnexpectedEnd,
+	/// Unknown enum variant, (type name, tag)
+	InvalidTag(&'static str, u8),
+	InvalidUtf8,
+}
+
+impl Display for CaptureError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{:?}", self) // Display the same way as Debug
+	}
+}
+
+impl Error for CaptureError {}
+
+/// Error returned by `FrameCapture::replay`.
+#[derive(Debug)]
+pub enum ReplayError {
+	/// A captured shader failed to compile on the replaying context
+	Shader(ShaderError),
+	/// A call uses a resource the capture does not create, for example one made with `CaptureContext::inner`
+	UnknownResource(String),
+}
+
+impl From<ShaderError> for ReplayError {
+	fn from(e: ShaderError) -> ReplayError {
+		ReplayError::Shader(e)
+	}
+}
+
+impl Display for ReplayError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{:?}", self) // Display the same way as Debug
+	}
+}
+
+impl Error for ReplayError {}
+
+const MAGIC: &[u8; 8] = b"MQCAPTUR";
+const VERSION: u32 = 1;
+
+struct Reader<'a> {
+	bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+	fn take(&mut self, n: usize) -> Result<&'a [u8], CaptureError> {
+		if self.bytes.len() < n {
+			return Err(CaptureError::UnexpectedEnd);
+		}
+		let (head, tail) = self.bytes.split_at(n);
+		self.bytes = tail;
+		Ok(head)
+	}
+}
+
+trait Encode {
+	fn encode(&self, out: &mut Vec<u8>);
+}
+
+trait Decode: Sized {
+	fn decode(input: &mut Reader) -> Result<Self, CaptureError>;
+}
+
+macro_rules! codec_number {
+	($($t:ty),*) => {$(
+		impl Encode for $t {
+			fn encode(&self, out: &mut Vec<u8>) {
+				out.extend_from_slice(&self.to_le_bytes());
+			}
+		}
+		impl Decode for $t {
+			fn decode(input: &mut Reader) -> Result<Self, CaptureError> {
+				Ok(<$t>::from_le_bytes(input.take(std::mem::size_of::<$t>())?.try_into().unwrap()))
+			}
+		}
+	)*};
+}
+codec_number!(u8, u32, i32, u64, f32);
+
+impl Encode for usize {
+	fn encode(&self, out: &mut Vec<u8>) {
+		(*self as u64).encode(out)
+	}
+}
+impl Decode for usize {
+	fn decode(input: &mut Reader) -> Result<Self, CaptureError> {
+		Ok(u64::decode(input)? as usize)
+	}
+}
+
+impl Encode for bool {
+	fn encode(&self, out: &mut Vec<u8>) {
+		(*self as u8).encode(out)
+	}
+}
+impl Decode for bool {
+	fn decode(input: &mut Reader) -> Result<Self, CaptureError> {
+		Ok(u8::decode(input)? != 0)
+	}
+}
+
+impl Encode for String {
+	fn encode(&self, out: &mut Vec<u8>) {
+		self.len().encode(out);
+		out.extend_from_slice(self.as_bytes());
+	}
+}
+impl Decode for String {
+	fn decode(input: &mut Reader) -> Result<Self, CaptureError> {
+		let len = usize::decode(input)?;
+		String::from_utf8(input.take(len)?.to_vec()).map_err(|_| CaptureError::InvalidUtf8)
+	}
+}
+
+impl<T: Encode> Encode for Vec<T> {
+	fn encode(&self, out: &mut Vec<u8>) {
+		self.len().encode(out);
+		for item in self {
+			item.encode(out);
+		}
+	}
+}
+impl<T: Decode> Decode for Vec<T> {
+	fn decode(input: &mut Reader) -> Result<Self, CaptureError> {
+		let len = usize::decode(input)?;
+		// do not trust the length for the allocation, every element takes at least a byte
+		let mut items = Vec::with_capacity(len.min(input.bytes.len()));
+		for _ in 0..len {
+			items.push(T::decode(input)?);
+		}
+		Ok(items)
+	}
+}
+
+impl<T: Encode> Encode for Option<T> {
+	fn encode(&self, out: &mut Vec<u8>) {
+		self.is_some().encode(out);
+		if let Some(value) = self {
+			value.encode(out);
+		}
+	}
+}
+impl<T: Decode> Decode for Option<T> {
+	fn decode(input: &mut Reader) -> Result<Self, CaptureError> {
+		Ok(match bool::decode(input)? {
+			true => Some(T::decode(input)?),
+			false => None,
+		})
+	}
+}
+
+impl<A: Encode, B: Encode> Encode for (A, B) {
+	fn encode(&self, out: &mut Vec<u8>) {
+		self.0.encode(out);
+		self.1.encode(out);
+	}
+}
+impl<A: Decode, B: Decode> Decode for (A, B) {
+	fn decode(input: &mut Reader) -> Result<Self, CaptureError> {
+		Ok((A::decode(input)?, B::decode(input)?))
+	}
+}
+
+impl<T: Encode> Encode for (T, T, T, T) {
+	fn encode(&self, out: &mut Vec<u8>) {
+		self.0.encode(out);
+		self.1.encode(out);
+		self.2.encode(out);
+		self.3.encode(out);
+	}
+}
+impl<T: Decode> Decode for (T, T, T, T) {
+	fn decode(input: &mut Reader) -> Result<Self, CaptureError> {
+		Ok((T::decode(input)?, T::decode(input)?, T::decode(input)?, T::decode(input)?))
+	}
+}
+
+impl<T: Encode, const N: usize> Encode for [T; N] {
+	fn encode(&self, out: &mut Vec<u8>) {
+		self.iter().for_each(|value| value.encode(out));
+	}
+}
+impl<T: Decode, const N: usize> Decode for [T; N] {
+	fn decode(input: &mut Reader) -> Result<Self, CaptureError> {
+		let items = (0..N).map(|_| T::decode(input)).collect::<Result<Vec<T>, _>>()?;
+		Ok(items.try_into().unwrap_or_else(|_| unreachable!()))
+	}
+}
+
+/// Fieldless enums, encoded as the index in the list.
+/// The match makes sure a newly added variant can not be forgotten here.
+macro_rules! codec_enum {
+	($($t:ident { $($variant:ident),* $(,)? })*) => {$(
+		impl Encode for $t {
+			fn encode(&self, out: &mut Vec<u8>) {
+				match self {
+					$($t::$variant)|* => {}
+				}
+				([$($t::$variant),*].iter().position(|v| v == self).unwrap() as u8).encode(out)
+			}
+		}
+		impl Decode for $t {
+			fn decode(input: &mut Reader) -> Result<Self, CaptureError> {
+				let tag = u8::decode(input)?;
+				[$($t::$variant),*].get(tag as usize).copied().ok_or(CaptureError::InvalidTag(stringify!($t), tag))
+			}
+		}
+	)*};
+}
+
+codec_enum! {
+	TextureAccess { Static, RenderTarget }
+	TextureKind { Texture2D, CubeMap, Texture2DArray, Texture3D }
+	TextureFormat { RGB8, RGBA8, RGBA16F, Depth, Depth32, Alpha, R8, RG8, R16F, RG16F, R32F, RGBA32F, SRGB8A8, Depth24Stencil8, ETC2RGB8, ETC2RGBA8, ASTC4x4, ASTC6x6, ASTC8x8, BC1, BC3 }
+	TextureWrap { Repeat, Mirror, Clamp, ClampToBorder }
+	FilterMode { Linear, Nearest }
+	MipmapFilterMode { None, Linear, Nearest }
+	BufferType { VertexBuffer, IndexBuffer, UniformBuffer }
+	BufferUsage { Immutable, Dynamic, Stream }
+	VertexStep { PerVertex, PerInstance }
+	VertexFormat { Float1, Float2, Float3, Float4, Byte1, Byte2, Byte3, Byte4, Short1, Short2, Short3, Short4, Int1, Int2, Int3, Int4, Mat4 }
+	UniformType { Float1, Float2, Float3, Float4, Int1, Int2, Int3, Int4, Mat4 }
+	CullFace { Nothing, Front, Back }
+	FrontFaceOrder { Clockwise, CounterClockwise }
+	Comparison { Never, Less, LessOrEqual, Greater, GreaterOrEqual, Equal, NotEqual, Always }
+	Equation { Add, Subtract, ReverseSubtract }
+	BlendValue { SourceColor, SourceAlpha, DestinationColor, DestinationAlpha }
+	StencilOp { Keep, Zero, Replace, IncrementClamp, DecrementClamp, Invert, IncrementWrap, DecrementWrap }
+	CompareFunc { Always, Never, Less, Equal, LessOrEqual, Greater, NotEqual, GreaterOrEqual }
+	PrimitiveType { Triangles, TriangleStrip, TriangleFan, Lines, LineStrip, LineLoop, Points }
+	ImageType { Float, Shadow }
+	StoreOp { Store, StoreUnresolved, Discard }
+}
+
+/// Structs, field by field. Destructuring without `..` makes a new field a compile error.
+macro_rules! codec_struct {
+	($($t:ident { $($field:ident),* $(,)? })*) => {$(
+		impl Encode for $t {
+			fn encode(&self, out: &mut Vec<u8>) {
+				let $t { $($field),* } = self;
+				$($field.encode(out);)*
+			}
+		}
+		impl Decode for $t {
+			fn decode(input: &mut Reader) -> Result<Self, CaptureError> {
+				Ok($t { $($field: Decode::decode(input)?),* })
+			}
+		}
+	)*};
+}
+
+codec_struct! {
+	TextureParams { kind, format, wrap, min_filter, mag_filter, mipmap_filter, width, height, depth, allocate_mipmaps, sample_count, compare }
+	SamplerParams { min_filter, mag_filter, mipmap_filter, wrap_x, wrap_y, wrap_z, border_color, compare, max_anisotropy }
+	BufferLayout { stride, step_func, step_rate }
+	UniformDesc { name, uniform_type, array_count }
+	UniformBlockLayout { uniforms }
+	UniformBlockDesc { name, binding, layout }
+	ImageDesc { name, image_type }
+	ShaderMeta { uniforms, images, uniform_blocks }
+	BlendState { equation, sfactor, dfactor }
+	StencilState { front, back }
+	StencilFaceState { fail_op, depth_fail_op, pass_op, test_func, test_ref, test_mask, write_mask }
+	ColorTargetState { color_blend, alpha_blend, color_write }
+	PipelineParams { cull_face, front_face_order, depth_test, depth_write, depth_write_offset, color_blend, alpha_blend, stencil_test, color_write, color_targets, primitive_type, program_point_size }
+	DrawRange { base_element, num_elements }
+	CapturedVertexAttribute { name, format, buffer_index }
+	ResourceId { index, generation }
+}
+
+macro_rules! codec_id {
+	($($t:ident),*) => {$(
+		impl Encode for $t {
+			fn encode(&self, out: &mut Vec<u8>) {
+				self.0.encode(out)
+			}
+		}
+		impl Decode for $t {
+			fn decode(input: &mut Reader) -> Result<Self, CaptureError> {
+				Ok($t(Decode::decode(input)?))
+			}
+		}
+	)*};
+}
+
+codec_id!(ShaderId, BufferId, Pipeline, RenderPass, SamplerId);
+
+impl Encode for TextureId {
+	fn encode(&self, out: &mut Vec<u8>) {
+		match self.0 {
+			TextureIdInner::Managed(id) => {
+				0u8.encode(out);
+				id.encode(out);
+			}
+			TextureIdInner::Raw(RawId(raw)) => {
+				1u8.encode(out);
+				raw.encode(out);
+			}
+		}
+	}
+}
+impl Decode for TextureId {
+	fn decode(input: &mut Reader) -> Result<Self, CaptureError> {
+		match u8::decode(input)? {
+			0 => Ok(TextureId(TextureIdInner::Managed(Decode::decode(input)?))),
+			1 => Ok(TextureId(TextureIdInner::Raw(RawId(Decode::decode(input)?)))),
+			tag => Err(CaptureError::InvalidTag("TextureId", tag)),
+		}
+	}
+}
+
+impl Encode for BlendFactor {
+	fn encode(&self, out: &mut Vec<u8>) {
+		match self {
+			BlendFactor::Zero => 0u8.encode(out),
+			BlendFactor::One => 1u8.encode(out),
+			BlendFactor::Value(value) => {
+				2u8.encode(out);
+				value.encode(out);
+			}
+			BlendFactor::OneMinusValue(value) => {
+				3u8.encode(out);
+				value.encode(out);
+			}
+			BlendFactor::SourceAlphaSaturate => 4u8.encode(out),
+		}
+	}
+}
+impl Decode for BlendFactor {
+	fn decode(input: &mut Reader) -> Result<Self, CaptureError> {
+		match u8::decode(input)? {
+			0 => Ok(BlendFactor::Zero),
+			1 => Ok(BlendFactor::One),
+			2 => Ok(BlendFactor::Value(Decode::decode(input)?)),
+			3 => Ok(BlendFactor::OneMinusValue(Decode::decode(input)?)),
+			4 => Ok(BlendFactor::SourceAlphaSaturate),
+			tag => Err(CaptureError::InvalidTag("BlendFactor", tag)),
+		}
+	}
+}
+
+impl Encode for PassAction {
+	fn encode(&self, out: &mut Vec<u8>) {
+		match self {
+			PassAction::Nothing => 0u8.encode(out),
+			PassAction::Clear { color, depth, stencil } => {
+				1u8.encode(out);
+				color.encode(out);
+				depth.encode(out);
+				stencil.encode(out);
+			}
+			PassAction::ClearAttachments { colors, depth, stencil } => {
+				2u8.encode(out);
+				colors.encode(out);
+				depth.encode(out);
+				stencil.encode(out);
+			}
+			PassAction::Attachments { colors, depth, stencil } => {
+				3u8.encode(out);
+				colors.encode(out);
+				depth.encode(out);
+				stencil.encode(out);
+			}
+		}
+	}
+}
+impl Decode for PassAction {
+	fn decode(input: &mut Reader) -> Result<Self, CaptureError> {
+		match u8::decode(input)? {
+			0 => Ok(PassAction::Nothing),
+			1 => Ok(PassAction::Clear {
+				color: Decode::decode(input)?,
+				depth: Decode::decode(input)?,
+				stencil: Decode::decode(input)?,
+			}),
+			2 => Ok(PassAction::ClearAttachments {
+				colors: Decode::decode(input)?,
+				depth: Decode::decode(input)?,
+				stencil: Decode::decode(input)?,
+			}),
+			3 => Ok(PassAction::Attachments {
+				colors: Decode::decode(input)?,
+				depth: Decode::decode(input)?,
+				stencil: Decode::decode(input)?,
+			}),
+			tag => Err(CaptureError::InvalidTag("PassAction", tag)),
+		}
+	}
+}
+
+impl<T: Encode> Encode for LoadOp<T> {
+	fn encode(&self, out: &mut Vec<u8>) {
+		match self {
+			LoadOp::Load => 0u8.encode(out),
+			LoadOp::Clear(value) => {
+				1u8.encode(out);
+				value.encode(out);
+			}
+			LoadOp::DontCare => 2u8.encode(out),
+		}
+	}
+}
+impl<T: Decode> Decode for LoadOp<T> {
+	fn decode(input: &mut Reader) -> Result<Self, CaptureError> {
+		match u8::decode(input)? {
+			0 => Ok(LoadOp::Load),
+			1 => Ok(LoadOp::Clear(T::decode(input)?)),
+			2 => Ok(LoadOp::DontCare),
+			tag => Err(CaptureError::InvalidTag("LoadOp", tag)),
+		}
+	}
+}
+
+impl<T: Encode> Encode for AttachmentAction<T> {
+	fn encode(&self, out: &mut Vec<u8>) {
+		let AttachmentAction { load, store } = self;
+		load.encode(out);
+		store.encode(out);
+	}
+}
+impl<T: Decode> Decode for AttachmentAction<T> {
+	fn decode(input: &mut Reader) -> Result<Self, CaptureError> {
+		Ok(AttachmentAction {
+			load: Decode::decode(input)?,
+			store: Decode::decode(input)?,
+		})
+	}
+}
+
+impl Encode for CapturedTextureSource {
+	fn encode(&self, out: &mut Vec<u8>) {
+		match self {
+			CapturedTextureSource::Empty => 0u8.encode(out),
+			CapturedTextureSource::Bytes(bytes) => {
+				1u8.encode(out);
+				bytes.encode(out);
+			}
+			CapturedTextureSource::Array(array) => {
+				2u8.encode(out);
+				array.encode(out);
+			}
+		}
+	}
+}
+impl Decode for CapturedTextureSource {
+	fn decode(input: &mut Reader) -> Result<Self, CaptureError> {
+		match u8::decode(input)? {
+			0 => Ok(CapturedTextureSource::Empty),
+			1 => Ok(CapturedTextureSource::Bytes(Decode::decode(input)?)),
+			2 => Ok(CapturedTextureSource::Array(Decode::decode(input)?)),
+			tag => Err(CaptureError::InvalidTag("CapturedTextureSource", tag)),
+		}
+	}
+}
+
+impl Encode for CapturedBufferSource {
+	fn encode(&self, out: &mut Vec<u8>) {
+		match self {
+			CapturedBufferSource::Slice { bytes, element_size } => {
+				0u8.encode(out);
+				bytes.encode(out);
+				element_size.encode(out);
+			}
+			CapturedBufferSource::Empty { size, element_size } => {
+				1u8.encode(out);
+				size.encode(out);
+				element_size.encode(out);
+			}
+		}
+	}
+}
+impl Decode for CapturedBufferSource {
+	fn decode(input: &mut Reader) -> Result<Self, CaptureError> {
+		match u8::decode(input)? {
+			0 => Ok(CapturedBufferSource::Slice {
+				bytes: Decode::decode(input)?,
+				element_size: Decode::decode(input)?,
+			}),
+			1 => Ok(CapturedBufferSource::Empty {
+				size: Decode::decode(input)?,
+				element_size: Decode::decode(input)?,
+			}),
+			tag => Err(CaptureError::InvalidTag("CapturedBufferSource", tag)),
+		}
+	}
+}
+
+impl CapturedBufferSource {
+	fn new(source: &BufferSource) -> CapturedBufferSource {
+		match source {
+			BufferSource::Slice(arg) => CapturedBufferSource::Slice {
+				bytes: unsafe { std::slice::from_raw_parts(arg.ptr as *const u8, arg.size) }.to_vec(),
+				element_size: arg.element_size,
+			},
+			BufferSource::Empty { size, element_size } => CapturedBufferSource::Empty {
+				size: *size,
+				element_size: *element_size,
+			},
+		}
+	}
+
+	/// Overwrite the start of the buffer, like `buffer_update`
+	fn update(&mut self, update: &[u8]) {
+		if let CapturedBufferSource::Empty { size, element_size } = *self {
+			*self = CapturedBufferSource::Slice { bytes: vec![0; size], element_size };
+		}
+		if let CapturedBufferSource::Slice { bytes, .. } = self {
+			let len = update.len().min(bytes.len());
+			bytes[..len].copy_from_slice(&update[..len]);
+		}
+	}
+
+	fn source(&self) -> BufferSource<'_> {
+		match self {
+			CapturedBufferSource::Slice { bytes, element_size } => unsafe { BufferSource::pointer(bytes.as_ptr(), bytes.len(), *element_size) },
+			CapturedBufferSource::Empty { size, element_size } => BufferSource::Empty {
+				size: *size,
+				element_size: *element_size,
+			},
+		}
+	}
+}
+
+impl CapturedVertexAttribute {
+	fn new(attribute: &VertexAttribute) -> CapturedVertexAttribute {
+		CapturedVertexAttribute {
+			name: attribute.name.to_string(),
+			format: attribute.format,
+			buffer_index: attribute.buffer_index,
+		}
+	}
+
+	fn attribute(&self) -> VertexAttribute<'_> {
+		VertexAttribute::with_buffer(&self.name, self.format, self.buffer_index)
+	}
+}
+
+/// Defines `CapturedCall` together with its (de)serialization.
+/// Tags are a part of the file format and should never be reused.
+macro_rules! captured_calls {
+	($($(#[$attr:meta])* $variant:ident { $($field:ident: $ty:ty),* $(,)? } = $tag:literal,)*) => {
+		/// One recorded `RenderingBackend` call.
+		/// Resource ids are the ones returned by the recorded backend.
+		#[derive(Debug, Clone)]
+		pub enum CapturedCall {
+			$($(#[$attr])* $variant { $($field: $ty),* },)*
+		}
+
+		impl Encode for CapturedCall {
+			fn encode(&self, out: &mut Vec<u8>) {
+				match self {
+					$(CapturedCall::$variant { $($field),* } => {
+						($tag as u8).encode(out);
+						$($field.encode(out);)*
+					})*
+				}
+			}
+		}
+
+		impl Decode for CapturedCall {
+			fn decode(input: &mut Reader) -> Result<Self, CaptureError> {
+				match u8::decode(input)? {
+					$($tag => Ok(CapturedCall::$variant { $($field: Decode::decode(input)?),* }),)*
+					tag => Err(CaptureError::InvalidTag("CapturedCall", tag)),
+				}
+			}
+		}
+	};
+}
+
+captured_calls! {
+	NewShader { shader: ShaderId, vertex: String, fragment: String, meta: ShaderMeta } = 0,
+	NewTexture { texture: TextureId, access: TextureAccess, source: CapturedTextureSource, params: TextureParams } = 1,
+	TextureSetMinFilter { texture: TextureId, filter: FilterMode, mipmap_filter: MipmapFilterMode } = 2,
+	TextureSetMagFilter { texture: TextureId, filter: FilterMode } = 3,
+	TextureSetWrap { texture: TextureId, wrap_x: TextureWrap, wrap_y: TextureWrap } = 4,
+	TextureGenerateMipmaps { texture: TextureId } = 5,
+	TextureResize { texture: TextureId, width: u32, height: u32, bytes: Option<Vec<u8>> } = 6,
+	/// Not replayed, readbacks do not change the rendering
+	TextureReadPixels { texture: TextureId } = 7,
+	/// Not replayed, readbacks do not change the rendering
+	DefaultPassReadPixels {} = 8,
+	TextureUpdatePart { texture: TextureId, x_offset: i32, y_offset: i32, width: i32, height: i32, bytes: Vec<u8> } = 9,
+	NewRenderPass { pass: RenderPass, color_img: Vec<TextureId>, resolve_img: Vec<TextureId>, depth_img: Option<TextureId> } = 10,
+	DeleteRenderPass { pass: RenderPass } = 11,
+	NewPipeline { pipeline: Pipeline, buffer_layout: Vec<BufferLayout>, attributes: Vec<CapturedVertexAttribute>, shader: ShaderId, params: PipelineParams } = 12,
+	ApplyPipeline { pipeline: Pipeline } = 13,
+	DeletePipeline { pipeline: Pipeline } = 14,
+	NewBuffer { buffer: BufferId, type_: BufferType, usage: BufferUsage, data: CapturedBufferSource } = 15,
+	BufferUpdate { buffer: BufferId, data: CapturedBufferSource } = 16,
+	DeleteBuffer { buffer: BufferId } = 17,
+	DeleteTexture { texture: TextureId } = 18,
+	DeleteShader { shader: ShaderId } = 19,
+	ApplyViewport { x: i32, y: i32, w: i32, h: i32 } = 20,
+	ApplyScissorRect { x: i32, y: i32, w: i32, h: i32 } = 21,
+	ApplyBindings { vertex_buffers: Vec<BufferId>, index_buffer: Option<BufferId>, images: Vec<TextureId>, samplers: Vec<Option<SamplerId>> } = 22,
+	/// Raw bytes of the uniforms struct
+	ApplyUniforms { bytes: Vec<u8> } = 23,
+	Clear { color: Option<(f32, f32, f32, f32)>, depth: Option<f32>, stencil: Option<i32> } = 24,
+	BeginPass { pass: Option<RenderPass>, action: PassAction } = 25,
+	EndRenderPass {} = 26,
+	CommitFrame {} = 27,
+	Draw { base_element: i32, num_elements: i32, num_instances: i32 } = 28,
+	ApplyUniformBuffer { binding: u32, buffer: BufferId, offset: usize, size: usize } = 29,
+	/// Not replayed, readbacks do not change the rendering
+	TextureReadPixelsAsync { texture: TextureId } = 30,
+	/// Not replayed, readbacks do not change the rendering
+	DefaultPassReadPixelsAsync {} = 31,
+	TextureUpdateLayerPart { texture: TextureId, layer: i32, x_offset: i32, y_offset: i32, width: i32, height: i32, bytes: Vec<u8> } = 32,
+	NewSampler { sampler: SamplerId, params: SamplerParams } = 33,
+	DeleteSampler { sampler: SamplerId } = 34,
+	CopyTextureRegion { src: TextureId, dst: TextureId, src_rect: (i32, i32, i32, i32), dst_offset: (i32, i32) } = 35,
+	BlitPass { src: Option<RenderPass>, dst: Option<RenderPass>, src_rect: (i32, i32, i32, i32), dst_rect: (i32, i32, i32, i32), filter: FilterMode } = 36,
+	DrawBaseVertex { base_element: i32, num_elements: i32, num_instances: i32, base_vertex: i32 } = 37,
+	DrawArrays { first_vertex: i32, num_vertices: i32, num_instances: i32 } = 38,
+	DrawMulti { ranges: Vec<DrawRange> } = 39,
+	ReloadShader { shader: ShaderId, vertex: String, fragment: String, meta: ShaderMeta } = 40,
+}
+
+/// A resource a call uses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Resource {
+	Shader(ShaderId),
+	Texture(TextureId),
+	RenderPass(RenderPass),
+	Pipeline(Pipeline),
+	Buffer(BufferId),
+	Sampler(SamplerId),
+}
+
+impl CapturedCall {
+	/// Every resource the call creates, changes or uses.
+	fn resources(&self, f: &mut dyn FnMut(Resource)) {
+		use Resource::*;
+		match self {
+			CapturedCall::NewShader { shader, .. } | CapturedCall::ReloadShader { shader, .. } | CapturedCall::DeleteShader { shader } => f(Shader(*shader)),
+			CapturedCall::NewTexture { texture, .. }
+			| CapturedCall::TextureSetMinFilter { texture, .. }
+			| CapturedCall::TextureSetMagFilter { texture, .. }
+			| CapturedCall::TextureSetWrap { texture, .. }
+			| CapturedCall::TextureGenerateMipmaps { texture }
+			| CapturedCall::TextureResize { texture, .. }
+			| CapturedCall::TextureReadPixels { texture }
+			| CapturedCall::TextureUpdatePart { texture, .. }
+			| CapturedCall::TextureReadPixelsAsync { texture }
+			| CapturedCall::TextureUpdateLayerPart { texture, .. }
+			| CapturedCall::DeleteTexture { texture } => f(Texture(*texture)),
+			CapturedCall::CopyTextureRegion { src, dst, .. } => {
+				f(Texture(*src));
+				f(Texture(*dst));
+			}
+			CapturedCall::NewRenderPass {
+				pass,
+				color_img,
+				resolve_img,
+				depth_img,
+			} => {
+				f(RenderPass(*pass));
+				color_img.iter().chain(resolve_img).chain(depth_img).for_each(|texture| f(Texture(*texture)));
+			}
+			CapturedCall::DeleteRenderPass { pass } => f(RenderPass(*pass)),
+			CapturedCall::BeginPass { pass, .. } => pass.iter().for_each(|pass| f(RenderPass(*pass))),
+			CapturedCall::BlitPass { src, dst, .. } => src.iter().chain(dst).for_each(|pass| f(RenderPass(*pass))),
+			CapturedCall::NewPipeline { pipeline, shader, .. } => {
+				f(Pipeline(*pipeline));
+				f(Shader(*shader));
+			}
+			CapturedCall::ApplyPipeline { pipeline } | CapturedCall::DeletePipeline { pipeline } => f(Pipeline(*pipeline)),
+			CapturedCall::NewBuffer { buffer, .. } | CapturedCall::BufferUpdate { buffer, .. } | CapturedCall::DeleteBuffer { buffer } | CapturedCall::ApplyUniformBuffer { buffer, .. } => f(Buffer(*buffer)),
+			CapturedCall::NewSampler { sampler, .. } | CapturedCall::DeleteSampler { sampler } => f(Sampler(*sampler)),
+			CapturedCall::ApplyBindings {
+				vertex_buffers,
+				index_buffer,
+				images,
+				samplers,
+			} => {
+				vertex_buffers.iter().chain(index_buffer).for_each(|buffer| f(Buffer(*buffer)));
+				images.iter().for_each(|texture| f(Texture(*texture)));
+				samplers.iter().flatten().for_each(|sampler| f(Sampler(*sampler)));
+			}
+			CapturedCall::DefaultPassReadPixels {}
+			| CapturedCall::DefaultPassReadPixelsAsync {}
+			| CapturedCall::ApplyViewport { .. }
+			| CapturedCall::ApplyScissorRect { .. }
+			| CapturedCall::ApplyUniforms { .. }
+			| CapturedCall::Clear { .. }
+			| CapturedCall::EndRenderPass {}
+			| CapturedCall::CommitFrame {}
+			| CapturedCall::Draw { .. }
+			| CapturedCall::DrawBaseVertex { .. }
+			| CapturedCall::DrawArrays { .. }
+			| CapturedCall::DrawMulti { .. } => {}
+		}
+	}
+}
+
+/// The calls recreating a resource in its current state, kept by `CaptureContext` for every live resource.
+#[derive(Clone)]
+struct LiveResource {
+	/// Creation order, a resource only depends on older ones
+	created: u64,
+	calls: Vec<CapturedCall>,
+}
+
+/// The calls recreating the resources of `live` used by `calls`, and the resources those depend on.
+fn setup_calls(live: &HashMap<Resource, LiveResource>, calls: &[CapturedCall]) -> Vec<CapturedCall> {
+	let mut pending = vec![];
+	calls.iter().for_each(|call| call.resources(&mut |resource| pending.push(resource)));
+	let mut used = HashSet::new();
+	while let Some(resource) = pending.pop() {
+		if used.insert(resource) {
+			if let Some(live) = live.get(&resource) {
+				live.calls.iter().for_each(|call| call.resources(&mut |resource| pending.push(resource)));
+			}
+		}
+	}
+
+	let mut resources: Vec<&LiveResource> = used.iter().filter_map(|resource| live.get(resource)).collect();
+	resources.sort_by_key(|live| live.created);
+	resources.into_iter().flat_map(|live| live.calls.iter().cloned()).collect()
+}
+
+/// A recorded sequence of `RenderingBackend` calls, see `CaptureContext`.
+#[derive(Debug, Clone, Default)]
+pub struct FrameCapture {
+	/// Calls recreating the resources that existed before the capture started and are used by `calls`,
+	/// so the capture can be replayed into a fresh context.
+	pub setup: Vec<CapturedCall>,
+	/// Calls in the order they were made.
+	/// Truncate the list (for example right after the suspicious `Draw`) to replay a part of the frame.
+	pub calls: Vec<CapturedCall>,
+	/// Textures of `setup` the GPU rendered or copied into before the capture started.
+	/// Only their uploaded data is kept, so the replayed frame reads different contents from them.
+	pub gpu_written: Vec<TextureId>,
+}
+
+impl FrameCapture {
+	pub fn to_bytes(&self) -> Vec<u8> {
+		let mut out = MAGIC.to_vec();
+		VERSION.encode(&mut out);
+		self.setup.encode(&mut out);
+		self.calls.encode(&mut out);
+		self.gpu_written.encode(&mut out);
+		out
+	}
+
+	pub fn from_bytes(bytes: &[u8]) -> Result<FrameCapture, CaptureError> {
+		let mut input = Reader { bytes };
+		if input.take(MAGIC.len()).map_err(|_| CaptureError::BadMagic)? != MAGIC {
+			return Err(CaptureError::BadMagic);
+		}
+		let version = u32::decode(&mut input)?;
+		if version != VERSION {
+			return Err(CaptureError::UnsupportedVersion(version));
+		}
+		Ok(FrameCapture {
+			setup: Decode::decode(&mut input)?,
+			calls: Decode::decode(&mut input)?,
+			gpu_written: Decode::decode(&mut input)?,
+		})
+	}
+
+	/// Feed the `setup` and the recorded calls to `ctx`.
+	///
+	/// Resources are created again and the ids are remapped, only raw textures
+	/// (`TextureId::from_raw_id`) are used as is. Stops at the first shader failing to compile
+	/// or at the first call using a resource the capture does not create.
+	///
+	/// ```
+	/// # use miniquad_wasm_bindgen::*;
+	/// let mut ctx = CaptureContext::new(Box::new(NullContext::new()));
+	/// let target = ctx.new_render_texture(TextureParams { width: 4, height: 4, ..Default::default() });
+	/// let pass = ctx.new_render_pass(target, None);
+	/// ctx.begin_pass(Some(pass), PassAction::clear_color(1.0, 0.0, 0.0, 1.0));
+	/// ctx.end_render_pass();
+	/// let buffer = ctx.inner().new_buffer(BufferType::VertexBuffer, BufferUsage::Immutable, BufferSource::slice(&[0.0f32; 6]));
+	///
+	/// ctx.start_capture();
+	/// ctx.copy_texture_region(target, target, (0, 0, 2, 2), (2, 2));
+	/// ctx.delete_buffer(buffer);
+	/// let capture = ctx.stop_capture();
+	///
+	/// // the clear is not part of the capture, the replayed texture is empty
+	/// assert_eq!(capture.gpu_written, [target]);
+	/// // the buffer was created behind the capture's back
+	/// let error = capture.replay(&mut NullContext::new()).unwrap_err();
+	/// assert!(matches!(error, ReplayError::UnknownResource(id) if id == format!("{:?}", buffer)));
+	/// ```
+	pub fn replay(&self, ctx: &mut dyn RenderingBackend) -> Result<(), ReplayError> {
+		let mut shaders: HashMap<ShaderId, ShaderId> = HashMap::new();
+		let mut textures: HashMap<TextureId, TextureId> = HashMap::new();
+		let mut passes: HashMap<RenderPass, RenderPass> = HashMap::new();
+		let mut pipelines: HashMap<Pipeline, Pipeline> = HashMap::new();
+		let mut buffers: HashMap<BufferId, BufferId> = HashMap::new();
+		let mut samplers: HashMap<SamplerId, SamplerId> = HashMap::new();
+
+		fn map<T: Copy + Eq + std::hash::Hash + std::fmt::Debug>(ids: &HashMap<T, T>, id: T) -> Result<T, ReplayError> {
+			ids.get(&id).copied().ok_or_else(|| ReplayError::UnknownResource(format!("{:?}", id)))
+		}
+		fn map_texture(textures: &HashMap<TextureId, TextureId>, id: TextureId) -> Result<TextureId, ReplayError> {
+			match id.0 {
+				TextureIdInner::Raw(_) => Ok(id),
+				TextureIdInner::Managed(_) => map(textures, id),
+			}
+		}
+		let map_textures = |textures: &HashMap<TextureId, TextureId>, ids: &[TextureId]| ids.iter().map(|id| map_texture(textures, *id)).collect::<Result<Vec<_>, _>>();
+
+		for call in self.setup.iter().chain(&self.calls) {
+			match call {
+				CapturedCall::NewShader { shader, vertex, fragment, meta } => {
+					let new = ctx.new_shader(ShaderSource::new(vertex, fragment), meta.clone())?;
+					shaders.insert(*shader, new);
+				}
+				CapturedCall::ReloadShader { shader, vertex, fragment, meta } => {
+					ctx.reload_shader(map(&shaders, *shader)?, ShaderSource::new(vertex, fragment), meta.clone())?;
+				}
+				CapturedCall::NewTexture { texture, access, source, params } => {
+					let faces: Vec<Vec<&[u8]>>;
+					let array: Vec<&[&[u8]]>;
+					let source = match source {
+						CapturedTextureSource::Empty => TextureSource::Empty,
+						CapturedTextureSource::Bytes(bytes) => TextureSource::Bytes(bytes),
+						CapturedTextureSource::Array(data) => {
+							faces = data.iter().map(|mips| mips.iter().map(|mip| &mip[..]).collect()).collect();
+							array = faces.iter().map(|mips| &mips[..]).collect();
+							TextureSource::Array(&array)
+						}
+					};
+					let new = ctx.new_texture(*access, source, *params);
+					textures.insert(*texture, new);
+				}
+				CapturedCall::TextureSetMinFilter { texture, filter, mipmap_filter } => ctx.texture_set_min_filter(map_texture(&textures, *texture)?, *filter, *mipmap_filter),
+				CapturedCall::TextureSetMagFilter { texture, filter } => ctx.texture_set_mag_filter(map_texture(&textures, *texture)?, *filter),
+				CapturedCall::TextureSetWrap { texture, wrap_x, wrap_y } => ctx.texture_set_wrap(map_texture(&textures, *texture)?, *wrap_x, *wrap_y),
+				CapturedCall::TextureGenerateMipmaps { texture } => ctx.texture_generate_mipmaps(map_texture(&textures, *texture)?),
+				CapturedCall::TextureResize { texture, width, height, bytes } => ctx.texture_resize(map_texture(&textures, *texture)?, *width, *height, bytes.as_deref()),
+				CapturedCall::TextureReadPixels { .. } | CapturedCall::DefaultPassReadPixels {} | CapturedCall::TextureReadPixelsAsync { .. } | CapturedCall::DefaultPassReadPixelsAsync {} => {}
+				CapturedCall::TextureUpdatePart {
+					texture,
+					x_offset,
+					y_offset,
+					width,
+					height,
+					bytes,
+				} => ctx.texture_update_part(map_texture(&textures, *texture)?, *x_offset, *y_offset, *width, *height, bytes),
+				CapturedCall::TextureUpdateLayerPart {
+					texture,
+					layer,
+					x_offset,
+					y_offset,
+					width,
+					height,
+					bytes,
+				} => ctx.texture_update_layer_part(map_texture(&textures, *texture)?, *layer, *x_offset, *y_offset, *width, *height, bytes),
+				CapturedCall::CopyTextureRegion { src, dst, src_rect, dst_offset } => ctx.copy_texture_region(map_texture(&textures, *src)?, map_texture(&textures, *dst)?, *src_rect, *dst_offset),
+				CapturedCall::NewRenderPass {
+					pass,
+					color_img,
+					resolve_img,
+					depth_img,
+				} => {
+					let new = ctx.new_render_pass_mrt(
+						&map_textures(&textures, color_img)?,
+						&map_textures(&textures, resolve_img)?,
+						depth_img.map(|id| map_texture(&textures, id)).transpose()?,
+					);
+					passes.insert(*pass, new);
+				}
+				CapturedCall::DeleteRenderPass { pass } => ctx.delete_render_pass(map(&passes, *pass)?),
+				CapturedCall::BlitPass { src, dst, src_rect, dst_rect, filter } => ctx.blit_pass(
+					src.map(|pass| map(&passes, pass)).transpose()?,
+					dst.map(|pass| map(&passes, pass)).transpose()?,
+					*src_rect,
+					*dst_rect,
+					*filter,
+				),
+				CapturedCall::NewPipeline {
+					pipeline,
+					buffer_layout,
+					attributes,
+					shader,
+					params,
+				} => {
+					let attributes: Vec<_> = attributes.iter().map(CapturedVertexAttribute::attribute).collect();
+					let new = ctx.new_pipeline(buffer_layout, &attributes, map(&shaders, *shader)?, *params);
+					pipelines.insert(*pipeline, new);
+				}
+				CapturedCall::ApplyPipeline { pipeline } => ctx.apply_pipeline(&map(&pipelines, *pipeline)?),
+				CapturedCall::DeletePipeline { pipeline } => ctx.delete_pipeline(map(&pipelines, *pipeline)?),
+				CapturedCall::NewBuffer { buffer, type_, usage, data } => {
+					let new = ctx.new_buffer(*type_, *usage, data.source());
+					buffers.insert(*buffer, new);
+				}
+				CapturedCall::BufferUpdate { buffer, data } => ctx.buffer_update(map(&buffers, *buffer)?, data.source()),
+				CapturedCall::DeleteBuffer { buffer } => ctx.delete_buffer(map(&buffers, *buffer)?),
+				CapturedCall::DeleteTexture { texture } => ctx.delete_texture(map_texture(&textures, *texture)?),
+				CapturedCall::DeleteShader { shader } => ctx.delete_shader(map(&shaders, *shader)?),
+				CapturedCall::NewSampler { sampler, params } => {
+					let new = ctx.new_sampler(*params);
+					samplers.insert(*sampler, new);
+				}
+				CapturedCall::DeleteSampler { sampler } => ctx.delete_sampler(map(&samplers, *sampler)?),
+				CapturedCall::ApplyViewport { x, y, w, h } => ctx.apply_viewport(*x, *y, *w, *h),
+				CapturedCall::ApplyScissorRect { x, y, w, h } => ctx.apply_scissor_rect(*x, *y, *w, *h),
+				CapturedCall::ApplyBindings {
+					vertex_buffers,
+					index_buffer,
+					images,
+					samplers: image_samplers,
+				} => {
+					let vertex_buffers = vertex_buffers.iter().map(|id| map(&buffers, *id)).collect::<Result<Vec<_>, _>>()?;
+					let image_samplers = image_samplers.iter().map(|id| id.map(|id| map(&samplers, id)).transpose()).collect::<Result<Vec<_>, _>>()?;
+					ctx.apply_bindings_from_slice(
+						&vertex_buffers,
+						index_buffer.map(|buffer| map(&buffers, buffer)).transpose()?,
+						&map_textures(&textures, images)?,
+						&image_samplers,
+					);
+				}
+				CapturedCall::ApplyUniforms { bytes } => {
+					// uniforms are read as f32/i32, the captured bytes are not aligned for that
+					let mut aligned = vec![0u32; bytes.len().div_ceil(4)];
+					unsafe { std::ptr::copy_nonoverlapping(bytes.as_ptr(), aligned.as_mut_ptr() as *mut u8, bytes.len()) };
+					ctx.apply_uniforms_from_bytes(aligned.as_ptr() as *const u8, bytes.len());
+				}
+				CapturedCall::ApplyUniformBuffer { binding, buffer, offset, size } => ctx.apply_uniform_buffer(*binding, map(&buffers, *buffer)?, *offset, *size),
+				CapturedCall::Clear { color, depth, stencil } => ctx.clear(*color, *depth, *stencil),
+				CapturedCall::BeginPass { pass, action } => ctx.begin_pass(pass.map(|pass| map(&passes, pass)).transpose()?, action.clone()),
+				CapturedCall::EndRenderPass {} => ctx.end_render_pass(),
+				CapturedCall::CommitFrame {} => ctx.commit_frame(),
+				CapturedCall::Draw {
+					base_element,
+					num_elements,
+					num_instances,
+				} => ctx.draw(*base_element, *num_elements, *num_instances),
+				CapturedCall::DrawBaseVertex {
+					base_element,
+					num_elements,
+					num_instances,
+					base_vertex,
+				} => ctx.draw_base_vertex(*base_element, *num_elements, *num_instances, *base_vertex),
+				CapturedCall::DrawArrays {
+					first_vertex,
+					num_vertices,
+					num_instances,
+				} => ctx.draw_arrays(*first_vertex, *num_vertices, *num_instances),
+				CapturedCall::DrawMulti { ranges } => ctx.draw_multi(ranges),
+			}
+		}
+		Ok(())
+	}
+}
+
+enum CaptureState {
+	Idle,
+	/// Waiting for the current frame to end
+	Armed,
+	/// Recording until the next commit_frame
+	Frame,
+	/// Recording until stop_capture
+	Manual,
+}
+
+/// `RenderingBackend` wrapper, forwarding everything to the inner backend
+/// and recording the calls on demand.
+///
+/// To make captures self-contained, it keeps a copy of the data of every live resource,
+/// so it costs as much memory again as the buffers and textures. The contents rendered or
+/// copied into textures by the GPU are not kept, only the data uploaded to them:
+/// such textures are listed in `FrameCapture::gpu_written` when the capture uses them.
+///
+/// ```
+/// # use miniquad_wasm_bindgen::*;
+/// let mut ctx = CaptureContext::new(Box::new(NullContext::new()));
+/// # let meta = ShaderMeta::default();
+/// let shader = ctx.new_shader(ShaderSource::new("", ""), meta).unwrap();
+/// let pipeline = ctx.new_pipeline(&[BufferLayout::default()], &[VertexAttribute::new("in_pos", VertexFormat::Float2)], shader, PipelineParams::default());
+/// let vertex_buffer = ctx.new_buffer(BufferType::VertexBuffer, BufferUsage::Immutable, BufferSource::slice(&[0.0f32; 6]));
+///
+/// ctx.capture_next_frame();
+/// ctx.commit_frame(); // the end of the current frame
+///
+/// ctx.begin_default_pass(PassAction::clear_color(1.0, 0.0, 0.0, 1.0));
+/// ctx.apply_pipeline(&pipeline);
+/// ctx.apply_bindings(&Bindings { vertex_buffers: vec![vertex_buffer], index_buffer: None, images: vec![], samplers: vec![] });
+/// ctx.draw_arrays(0, 3, 1);
+/// ctx.end_render_pass();
+/// ctx.commit_frame();
+///
+/// let capture = ctx.take_capture().unwrap();
+/// // the shader, the pipeline and the buffer were created before the capture
+/// assert_eq!((capture.setup.len(), capture.calls.len()), (3, 6));
+///
+/// // save it, load it back, look at it in a fresh context
+/// let capture = FrameCapture::from_bytes(&capture.to_bytes()).unwrap();
+/// assert!(matches!(&capture.setup[1], CapturedCall::NewPipeline { attributes, .. } if attributes[0].name == "in_pos"));
+/// let mut replay = NullContext::new();
+/// capture.replay(&mut replay).unwrap();
+/// assert!(replay.commands().contains(&NullCommand::DrawArrays { first_vertex: 0, num_vertices: 3, num_instances: 1 }));
+/// ```
+pub struct CaptureContext {
+	inner: Box<dyn RenderingBackend>,
+	state: CaptureState,
+	// draw takes &self
+	calls: RefCell<Vec<CapturedCall>>,
+	finished: Option<FrameCapture>,
+	live: HashMap<Resource, LiveResource>,
+	/// `live` when the recording started
+	snapshot: HashMap<Resource, LiveResource>,
+	/// Textures with contents the GPU rendered or copied, and `gpu_written` when the recording started
+	gpu_written: HashSet<TextureId>,
+	snapshot_written: HashSet<TextureId>,
+	created: u64,
+}
+
+impl CaptureContext {
+	pub fn new(inner: Box<dyn RenderingBackend>) -> CaptureContext {
+		CaptureContext {
+			inner,
+			state: CaptureState::Idle,
+			calls: RefCell::new(vec![]),
+			finished: None,
+			live: HashMap::new(),
+			snapshot: HashMap::new(),
+			gpu_written: HashSet::new(),
+			snapshot_written: HashSet::new(),
+			created: 0,
+		}
+	}
+
+	/// Record the whole next frame: from the next `commit_frame` to the one after.
+	/// The result is available from `take_capture`.
+	pub fn capture_next_frame(&mut self) {
+		self.state = CaptureState::Armed;
+	}
+
+	/// The last frame captured with `capture_next_frame`.
+	pub fn take_capture(&mut self) -> Option<FrameCapture> {
+		self.finished.take()
+	}
+
+	/// Start recording right away, until `stop_capture`.
+	pub fn start_capture(&mut self) {
+		self.begin_recording();
+		self.state = CaptureState::Manual;
+	}
+
+	pub fn stop_capture(&mut self) -> FrameCapture {
+		self.state = CaptureState::Idle;
+		self.end_recording()
+	}
+
+	/// The wrapped backend.
+	pub fn inner(&mut self) -> &mut dyn RenderingBackend {
+		&mut *self.inner
+	}
+
+	fn recording(&self) -> bool {
+		matches!(self.state, CaptureState::Frame | CaptureState::Manual)
+	}
+
+	fn record(&self, call: impl FnOnce() -> CapturedCall) {
+		if self.recording() {
+			self.calls.borrow_mut().push(call());
+		}
+	}
+
+	fn begin_recording(&mut self) {
+		self.calls.get_mut().clear();
+		self.snapshot = self.live.clone();
+		self.snapshot_written = self.gpu_written.clone();
+	}
+
+	fn end_recording(&mut self) -> FrameCapture {
+		let calls = std::mem::take(self.calls.get_mut());
+		let setup = setup_calls(&std::mem::take(&mut self.snapshot), &calls);
+		let written = std::mem::take(&mut self.snapshot_written);
+		let gpu_written = setup
+			.iter()
+			.filter_map(|call| match call {
+				CapturedCall::NewTexture { texture, .. } if written.contains(texture) => Some(*texture),
+				_ => None,
+			})
+			.collect();
+		FrameCapture { setup, calls, gpu_written }
+	}
+
+	/// Start keeping the calls recreating `resource`
+	fn track(&mut self, resource: Resource, call: CapturedCall) {
+		self.created += 1;
+		self.live.insert(
+			resource,
+			LiveResource {
+				created: self.created,
+				calls: vec![call],
+			},
+		);
+	}
+
+	fn untrack(&mut self, resource: Resource) {
+		self.live.remove(&resource);
+		if let Resource::Texture(texture) = resource {
+			self.gpu_written.remove(&texture);
+		}
+	}
+
+	/// Mark the textures `pass` renders into as written, `None` is the default framebuffer
+	fn pass_written(&mut self, pass: Option<RenderPass>) {
+		let Some(pass) = pass else {
+			return;
+		};
+		let textures: Vec<TextureId> = match self.live.get(&Resource::RenderPass(pass)).map(|live| &live.calls[0]) {
+			Some(CapturedCall::NewRenderPass { color_img, resolve_img, depth_img, .. }) => color_img.iter().chain(resolve_img).chain(depth_img).copied().collect(),
+			_ => self.inner.render_pass_color_attachments(pass).to_vec(),
+		};
+		self.gpu_written.extend(textures);
+	}
+
+	/// Whether the update covers a whole layer of the texture
+	fn covers(&self, texture: TextureId, x_offset: i32, y_offset: i32, width: i32, height: i32) -> bool {
+		let params = self.inner.texture_params(texture);
+		x_offset == 0 && y_offset == 0 && width as u32 == params.width && height as u32 == params.height
+	}
+
+	/// `None` for raw textures and resources made with `inner`
+	fn live_calls(&mut self, resource: Resource) -> Option<&mut Vec<CapturedCall>> {
+		self.live.get_mut(&resource).map(|live| &mut live.calls)
+	}
+
+	/// Replaces the previous call of the same kind, for texture settings
+	fn track_setting(&mut self, texture: TextureId, call: CapturedCall) {
+		let Some(calls) = self.live_calls(Resource::Texture(texture)) else {
+			return;
+		};
+		calls.retain(|previous| std::mem::discriminant(previous) != std::mem::discriminant(&call));
+		calls.push(call);
+	}
+
+	/// New texture contents, dropping the older contents of `layer` when `full` covers all of it,
+	/// so streaming textures do not keep every upload.
+	fn track_contents(&mut self, texture: TextureId, layer: i32, full: bool, call: CapturedCall) {
+		let Some(calls) = self.live_calls(Resource::Texture(texture)) else {
+			return;
+		};
+		if full {
+			calls.retain(|previous| match previous {
+				CapturedCall::TextureUpdatePart { .. } => layer != 0,
+				CapturedCall::TextureUpdateLayerPart { layer: previous, .. } => *previous != layer,
+				CapturedCall::TextureGenerateMipmaps { .. } => false,
+				_ => true,
+			});
+		}
+		calls.push(call);
+	}
+}
+
+impl RenderingBackend for CaptureContext {
+	fn info(&self) -> ContextInfo {
+		self.inner.info()
+	}
+
+	fn new_shader(&mut self, shader: ShaderSource, meta: ShaderMeta) -> Result<ShaderId, ShaderError> {
+		let id = self.inner.new_shader(shader, meta.clone())?;
+		let call = CapturedCall::NewShader {
+			shader: id,
+			vertex: shader.vertex.to_string(),
+			fragment: shader.fragment.to_string(),
+			meta,
+		};
+		self.record(|| call.clone());
+		self.track(Resource::Shader(id), call);
+		Ok(id)
+	}
+
+	fn reflect_shader(&mut self, shader: ShaderSource) -> Result<ShaderReflection, ShaderError> {
+		self.inner.reflect_shader(shader)
+	}
+
+	fn reload_shader(&mut self, id: ShaderId, shader: ShaderSource, meta: ShaderMeta) -> Result<(), ShaderError> {
+		self.inner.reload_shader(id, shader, meta.clone())?;
+		self.record(|| CapturedCall::ReloadShader {
+			shader: id,
+			vertex: shader.vertex.to_string(),
+			fragment: shader.fragment.to_string(),
+			meta: meta.clone(),
+		});
+		// recreated with the new source
+		if let Some(calls) = self.live_calls(Resource::Shader(id)) {
+			*calls = vec![CapturedCall::NewShader {
+				shader: id,
+				vertex: shader.vertex.to_string(),
+				fragment: shader.fragment.to_string(),
+				meta,
+			}];
+		}
+		Ok(())
+	}
+
+	fn try_new_texture(&mut self, access: TextureAccess, source: TextureSource, params: TextureParams) -> Result<TextureId, GraphicsError> {
+		let captured = match source {
+			TextureSource::Empty => CapturedTextureSource::Empty,
+			TextureSource::Bytes(bytes) => CapturedTextureSource::Bytes(bytes.to_vec()),
+			TextureSource::Array(array) => CapturedTextureSource::Array(array.iter().map(|mips| mips.iter().map(|mip| mip.to_vec()).collect()).collect()),
+		};
+		let texture = self.inner.try_new_texture(access, source, params)?;
+		let call = CapturedCall::NewTexture {
+			texture,
+			access,
+			source: captured,
+			params,
+		};
+		self.record(|| call.clone());
+		self.track(Resource::Texture(texture), call);
+		Ok(texture)
+	}
+
+	fn texture_params(&self, texture: TextureId) -> TextureParams {
+		self.inner.texture_params(texture)
+	}
+
+	unsafe fn texture_raw_id(&self, texture: TextureId) -> RawId {
+		self.inner.texture_raw_id(texture)
+	}
+
+	fn texture_set_min_filter(&mut self, texture: TextureId, filter: FilterMode, mipmap_filter: MipmapFilterMode) {
+		self.inner.texture_set_min_filter(texture, filter, mipmap_filter);
+		let call = CapturedCall::TextureSetMinFilter { texture, filter, mipmap_filter };
+		self.record(|| call.clone());
+		self.track_setting(texture, call);
+	}
+
+	fn texture_set_mag_filter(&mut self, texture: TextureId, filter: FilterMode) {
+		self.inner.texture_set_mag_filter(texture, filter);
+		let call = CapturedCall::TextureSetMagFilter { texture, filter };
+		self.record(|| call.clone());
+		self.track_setting(texture, call);
+	}
+
+	fn texture_set_wrap(&mut self, texture: TextureId, wrap_x: TextureWrap, wrap_y: TextureWrap) {
+		self.inner.texture_set_wrap(texture, wrap_x, wrap_y);
+		let call = CapturedCall::TextureSetWrap { texture, wrap_x, wrap_y };
+		self.record(|| call.clone());
+		self.track_setting(texture, call);
+	}
+
+	fn texture_generate_mipmaps(&mut self, texture: TextureId) {
+		self.inner.texture_generate_mipmaps(texture);
+		self.record(|| CapturedCall::TextureGenerateMipmaps { texture });
+		self.track_contents(texture, 0, false, CapturedCall::TextureGenerateMipmaps { texture });
+	}
+
+	fn texture_resize(&mut self, texture: TextureId, width: u32, height: u32, bytes: Option<&[u8]>) {
+		self.inner.texture_resize(texture, width, height, bytes);
+		let call = CapturedCall::TextureResize {
+			texture,
+			width,
+			height,
+			bytes: bytes.map(|bytes| bytes.to_vec()),
+		};
+		self.record(|| call.clone());
+		// the old contents are gone
+		self.gpu_written.remove(&texture);
+		if let Some(calls) = self.live_calls(Resource::Texture(texture)) {
+			calls.retain(|previous| {
+				!matches!(
+					previous,
+					CapturedCall::TextureResize { .. } | CapturedCall::TextureUpdatePart { .. } | CapturedCall::TextureUpdateLayerPart { .. } | CapturedCall::TextureGenerateMipmaps { .. }
+				)
+			});
+			calls.push(call);
+		}
+	}
+
+	fn new_sampler(&mut self, params: SamplerParams) -> SamplerId {
+		let sampler = self.inner.new_sampler(params);
+		self.record(|| CapturedCall::NewSampler { sampler, params });
+		self.track(Resource::Sampler(sampler), CapturedCall::NewSampler { sampler, params });
+		sampler
+	}
+
+	fn texture_read_pixels(&mut self, texture: TextureId, bytes: &mut [u8]) {
+		self.inner.texture_read_pixels(texture, bytes);
+		self.record(|| CapturedCall::TextureReadPixels { texture });
+	}
+
+	fn default_pass_read_pixels(&mut self, bytes: &mut [u8]) {
+		self.inner.default_pass_read_pixels(bytes);
+		self.record(|| CapturedCall::DefaultPassReadPixels {});
+	}
+
+	fn texture_read_pixels_async(&mut self, texture: TextureId) -> ReadbackId {
+		let readback = self.inner.texture_read_pixels_async(texture);
+		self.record(|| CapturedCall::TextureReadPixelsAsync { texture });
+		readback
+	}
+
+	fn default_pass_read_pixels_async(&mut self) -> ReadbackId {
+		let readback = self.inner.default_pass_read_pixels_async();
+		self.record(|| CapturedCall::DefaultPassReadPixelsAsync {});
+		readback
+	}
+
+	fn readback_size(&self, readback: ReadbackId) -> usize {
+		self.inner.readback_size(readback)
+	}
+
+	fn readback_poll(&mut self, readback: ReadbackId, bytes: &mut [u8]) -> bool {
+		self.inner.readback_poll(readback, bytes)
+	}
+
+	fn delete_readback(&mut self, readback: ReadbackId) {
+		self.inner.delete_readback(readback)
+	}
+
+	fn texture_update_part(&mut self, texture: TextureId, x_offset: i32, y_offset: i32, width: i32, height: i32, bytes: &[u8]) {
+		self.inner.texture_update_part(texture, x_offset, y_offset, width, height, bytes);
+		let call = CapturedCall::TextureUpdatePart {
+			texture,
+			x_offset,
+			y_offset,
+			width,
+			height,
+			bytes: bytes.to_vec(),
+		};
+		self.record(|| call.clone());
+		let full = self.covers(texture, x_offset, y_offset, width, height);
+		self.track_contents(texture, 0, full, call);
+	}
+
+	fn texture_update_layer_part(&mut self, texture: TextureId, layer: i32, x_offset: i32, y_offset: i32, width: i32, height: i32, bytes: &[u8]) {
+		self.inner.texture_update_layer_part(texture, layer, x_offset, y_offset, width, height, bytes);
+		let call = CapturedCall::TextureUpdateLayerPart {
+			texture,
+			layer,
+			x_offset,
+			y_offset,
+			width,
+			height,
+			bytes: bytes.to_vec(),
+		};
+		self.record(|| call.clone());
+		let full = self.covers(texture, x_offset, y_offset, width, height);
+		self.track_contents(texture, layer, full, call);
+	}
+
+	fn copy_texture_region(&mut self, src: TextureId, dst: TextureId, src_rect: (i32, i32, i32, i32), dst_offset: (i32, i32)) {
+		self.inner.copy_texture_region(src, dst, src_rect, dst_offset);
+		self.record(|| CapturedCall::CopyTextureRegion { src, dst, src_rect, dst_offset });
+		self.gpu_written.insert(dst);
+	}
+
+	fn try_new_render_pass_mrt(&mut self, color_img: &[TextureId], resolve_img: &[TextureId], depth_img: Option<TextureId>) -> Result<RenderPass, GraphicsError> {
+		let pass = self.inner.try_new_render_pass_mrt(color_img, resolve_img, depth_img)?;
+		let call = CapturedCall::NewRenderPass {
+			pass,
+			color_img: color_img.to_vec(),
+			resolve_img: resolve_img.to_vec(),
+			depth_img,
+		};
+		self.record(|| call.clone());
+		self.track(Resource::RenderPass(pass), call);
+		Ok(pass)
+	}
+
+	fn render_pass_color_attachments(&self, render_pass: RenderPass) -> &[TextureId] {
+		self.inner.render_pass_color_attachments(render_pass)
+	}
+
+	fn try_delete_render_pass(&mut self, render_pass: RenderPass) -> Result<(), GraphicsError> {
+		self.inner.try_delete_render_pass(render_pass)?;
+		self.record(|| CapturedCall::DeleteRenderPass { pass: render_pass });
+		// the backends delete the attachments with the pass
+		if let Some(CapturedCall::NewRenderPass { color_img, depth_img, .. }) = self.live.remove(&Resource::RenderPass(render_pass)).and_then(|live| live.calls.into_iter().next()) {
+			for texture in color_img.into_iter().chain(depth_img) {
+				self.untrack(Resource::Texture(texture));
+			}
+		}
+		Ok(())
+	}
+
+	fn blit_pass(&mut self, src: Option<RenderPass>, dst: Option<RenderPass>, src_rect: (i32, i32, i32, i32), dst_rect: (i32, i32, i32, i32), filter: FilterMode) {
+		self.inner.blit_pass(src, dst, src_rect, dst_rect, filter);
+		self.record(|| CapturedCall::BlitPass { src, dst, src_rect, dst_rect, filter });
+		self.pass_written(dst);
+	}
+
+	fn try_new_pipeline(&mut self, buffer_layout: &[BufferLayout], attributes: &[VertexAttribute], shader: ShaderId, params: PipelineParams) -> Result<Pipeline, GraphicsError> {
+		let pipeline = self.inner.try_new_pipeline(buffer_layout, attributes, shader, params)?;
+		let call = CapturedCall::NewPipeline {
+			pipeline,
+			buffer_layout: buffer_layout.to_vec(),
+			attributes: attributes.iter().map(CapturedVertexAttribute::new).collect(),
+			shader,
+			params,
+		};
+		self.record(|| call.clone());
+		self.track(Resource::Pipeline(pipeline), call);
+		Ok(pipeline)
+	}
+
+	fn apply_pipeline(&mut self, pipeline: &Pipeline) {
+		self.inner.apply_pipeline(pipeline);
+		self.record(|| CapturedCall::ApplyPipeline { pipeline: *pipeline });
+	}
+
+	fn try_delete_pipeline(&mut self, pipeline: Pipeline) -> Result<(), GraphicsError> {
+		self.inner.try_delete_pipeline(pipeline)?;
+		self.record(|| CapturedCall::DeletePipeline { pipeline });
+		self.untrack(Resource::Pipeline(pipeline));
+		Ok(())
+	}
+
+	fn try_new_buffer(&mut self, type_: BufferType, usage: BufferUsage, data: BufferSource) -> Result<BufferId, GraphicsError> {
+		let captured = CapturedBufferSource::new(&data);
+		let buffer = self.inner.try_new_buffer(type_, usage, data)?;
+		let call = CapturedCall::NewBuffer { buffer, type_, usage, data: captured };
+		self.record(|| call.clone());
+		self.track(Resource::Buffer(buffer), call);
+		Ok(buffer)
+	}
+
+	fn buffer_update(&mut self, buffer: BufferId, data: BufferSource) {
+		let captured = CapturedBufferSource::new(&data);
+		self.inner.buffer_update(buffer, data);
+		// the buffer is recreated with the updated data
+		if let (CapturedBufferSource::Slice { bytes: update, .. }, Some([CapturedCall::NewBuffer { data, .. }])) = (&captured, self.live_calls(Resource::Buffer(buffer)).map(|calls| &mut calls[..])) {
+			data.update(update);
+		}
+		self.record(|| CapturedCall::BufferUpdate { buffer, data: captured });
+	}
+
+	fn buffer_size(&mut self, buffer: BufferId) -> usize {
+		self.inner.buffer_size(buffer)
+	}
+
+	fn try_delete_buffer(&mut self, buffer: BufferId) -> Result<(), GraphicsError> {
+		self.inner.try_delete_buffer(buffer)?;
+		self.record(|| CapturedCall::DeleteBuffer { buffer });
+		self.untrack(Resource::Buffer(buffer));
+		Ok(())
+	}
+
+	fn try_delete_texture(&mut self, texture: TextureId) -> Result<(), GraphicsError> {
+		self.inner.try_delete_texture(texture)?;
+		self.record(|| CapturedCall::DeleteTexture { texture });
+		self.untrack(Resource::Texture(texture));
+		Ok(())
+	}
+
+	fn try_delete_shader(&mut self, program: ShaderId) -> Result<(), GraphicsError> {
+		self.inner.try_delete_shader(program)?;
+		self.record(|| CapturedCall::DeleteShader { shader: program });
+		self.untrack(Resource::Shader(program));
+		Ok(())
+	}
+
+	fn try_delete_sampler(&mut self, sampler: SamplerId) -> Result<(), GraphicsError> {
+		self.inner.try_delete_sampler(sampler)?;
+		self.record(|| CapturedCall::DeleteSampler { sampler });
+		self.untrack(Resource::Sampler(sampler));
+		Ok(())
+	}
+
+	fn apply_viewport(&mut self, x: i32, y: i32, w: i32, h: i32) {
+		self.inner.apply_viewport(x, y, w, h);
+		self.record(|| CapturedCall::ApplyViewport { x, y, w, h });
+	}
+
+	fn apply_scissor_rect(&mut self, x: i32, y: i32, w: i32, h: i32) {
+		self.inner.apply_scissor_rect(x, y, w, h);
+		self.record(|| CapturedCall::ApplyScissorRect { x, y, w, h });
+	}
+
+	fn apply_bindings_from_slice(&mut self, vertex_buffers: &[BufferId], index_buffer: Option<BufferId>, textures: &[TextureId], samplers: &[Option<SamplerId>]) {
+		self.inner.apply_bindings_from_slice(vertex_buffers, index_buffer, textures, samplers);
+		self.record(|| CapturedCall::ApplyBindings {
+			vertex_buffers: vertex_buffers.to_vec(),
+			index_buffer,
+			images: textures.to_vec(),
+			samplers: samplers.to_vec(),
+		});
+	}
+
+	// the trait takes a raw pointer, same as GlContext we trust it to point to `size` bytes
+	#[allow(clippy::not_unsafe_ptr_arg_deref)]
+	fn apply_uniforms_from_bytes(&mut self, uniform_ptr: *const u8, size: usize) {
+		self.inner.apply_uniforms_from_bytes(uniform_ptr, size);
+		self.record(|| CapturedCall::ApplyUniforms {
+			bytes: unsafe { std::slice::from_raw_parts(uniform_ptr, size) }.to_vec(),
+		});
+	}
+
+	fn apply_uniform_buffer(&mut self, binding: u32, buffer: BufferId, offset: usize, size: usize) {
+		self.inner.apply_uniform_buffer(binding, buffer, offset, size);
+		self.record(|| CapturedCall::ApplyUniformBuffer { binding, buffer, offset, size });
+	}
+
+	fn clear(&mut self, color: Option<(f32, f32, f32, f32)>, depth: Option<f32>, stencil: Option<i32>) {
+		self.inner.clear(color, depth, stencil);
+		self.record(|| CapturedCall::Clear { color, depth, stencil });
+	}
+
+	fn begin_default_pass(&mut self, action: PassAction) {
+		self.begin_pass(None, action);
+	}
+
+	fn begin_pass(&mut self, pass: Option<RenderPass>, action: PassAction) {
+		self.record(|| CapturedCall::BeginPass { pass, action: action.clone() });
+		self.inner.begin_pass(pass, action);
+		self.pass_written(pass);
+	}
+
+	fn end_render_pass(&mut self) {
+		self.inner.end_render_pass();
+		self.record(|| CapturedCall::EndRenderPass {});
+	}
+
+	fn commit_frame(&mut self) {
+		self.inner.commit_frame();
+		self.record(|| CapturedCall::CommitFrame {});
+
+		match self.state {
+			CaptureState::Armed => {
+				self.begin_recording();
+				self.state = CaptureState::Frame;
+			}
+			CaptureState::Frame => {
+				self.state = CaptureState::Idle;
+				self.finished = Some(self.end_recording());
+			}
+			CaptureState::Idle | CaptureState::Manual => {}
+		}
+	}
+
+	fn frame_stats(&self) -> FrameStats {
+		self.inner.frame_stats()
+	}
+
+	fn draw(&self, base_element: i32, num_elements: i32, num_instances: i32) {
+		self.inner.draw(base_element, num_elements, num_instances);
+		self.record(|| CapturedCall::Draw {
+			base_element,
+			num_elements,
+			num_instances,
+		});
+	}
+
+	fn draw_base_vertex(&self, base_element: i32, num_elements: i32, num_instances: i32, base_vertex: i32) {
+		self.inner.draw_base_vertex(base_element, num_elements, num_instances, base_vertex);
+		self.record(|| CapturedCall::DrawBaseVertex {
+			base_element,
+			num_elements,
+			num_instances,
+			base_vertex,
+		});
+	}
+
+	fn draw_arrays(&self, first_vertex: i32, num_vertices: i32, num_instances: i32) {
+		self.inner.draw_arrays(first_vertex, num_vertices, num_instances);
+		self.record(|| CapturedCall::DrawArrays {
+			first_vertex,
+			num_vertices,
+			num_instances,
+		});
+	}
+
+	fn draw_multi(&self, ranges: &[DrawRange]) {
+		self.inner.draw_multi(ranges);
+		self.record(|| CapturedCall::DrawMulti { ranges: ranges.to_vec() });
+	}
+}
@@ -0,0 +1,114 @@
+//! Parsing driver info logs, see `ShaderError::diagnostics`.
+
+use super::*;
+
+/// One message of a shader compile or link log.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShaderDiagnostic {
+	/// `None` for link errors
+	pub shader_type: Option<ShaderType>,
+	pub warning: bool,
+	/// 1-based line of the compiled source, or of `file` after `PreprocessedShader::remap`.
+	/// `None` when the driver did not report one, or used a format that is not recognized.
+	pub line: Option<u32>,
+	/// 1-based, only reported by some drivers, like Mesa
+	pub column: Option<u32>,
+	/// The `#include` the line is in, set by `PreprocessedShader::remap`
+	pub file: Option<String>,
+	pub message: String,
+}
+
+impl ShaderError {
+	/// The compile or link log split into messages with their lines, empty for other errors.
+	///
+	/// Understands the Mesa `0:12(5): error: ...`, ANGLE and Apple `ERROR: 0:12: ...`
+	/// and NVIDIA `0(12) : error C1008: ...` formats; other lines are kept as line-less messages.
+	///
+	/// ```
+	/// # use miniquad_wasm_bindgen::*;
+	/// let error = ShaderError::CompilationError {
+	///     shader_type: ShaderType::Fragment,
+	///     error_message: "0:3(12): error: `color' undeclared\nWARNING: 0:4: 'x' : unused\n0(5) : error C0000: syntax error".to_string(),
+	/// };
+	/// let diagnostics = error.diagnostics();
+	/// assert_eq!((diagnostics[0].line, diagnostics[0].column, diagnostics[0].message.as_str()), (Some(3), Some(12), "`color' undeclared"));
+	/// assert_eq!((diagnostics[1].warning, diagnostics[1].line, diagnostics[1].message.as_str()), (true, Some(4), "'x' : unused"));
+	/// assert_eq!((diagnostics[2].warning, diagnostics[2].line, diagnostics[2].message.as_str()), (false, Some(5), "syntax error"));
+	/// ```
+	pub fn diagnostics(&self) -> Vec<ShaderDiagnostic> {
+		let (shader_type, log) = match self {
+			ShaderError::CompilationError { shader_type, error_message } => (Some(*shader_type), error_message),
+			ShaderError::LinkError(error_message) => (None, error_message),
+			_ => return vec![],
+		};
+		log.lines()
+			.map(|line| line.trim_matches(|c: char| c.is_whitespace() || c == '\0'))
+			.filter(|line| !line.is_empty())
+			.map(|line| {
+				let (warning, line, column, message) = parse_line(line);
+				ShaderDiagnostic {
+					shader_type,
+					warning,
+					line,
+					column,
+					file: None,
+					message: message.to_string(),
+				}
+			})
+			.collect()
+	}
+}
+
+/// `(warning, line, column, message)`
+fn parse_line(text: &str) -> (bool, Option<u32>, Option<u32>, &str) {
+	// ANGLE and Apple put the severity first
+	let (mut warning, rest) = match severity(text) {
+		Some((warning, rest)) => (Some(warning), rest),
+		None => (None, text),
+	};
+	let (line, column, rest) = location(rest.trim_start()).map_or((None, None, rest), |(line, column, rest)| (Some(line), column, rest));
+	// Mesa and NVIDIA put it after the location
+	let mut rest = rest.trim_start();
+	if warning.is_none() {
+		if let Some((severity, message)) = severity(rest) {
+			warning = Some(severity);
+			rest = message;
+		}
+	}
+	(warning == Some(true), line, column, rest.trim())
+}
+
+/// `error: `, `ERROR: ` or `error C1008: `, the NVIDIA error code is dropped
+fn severity(text: &str) -> Option<(bool, &str)> {
+	let word = text.split(|c: char| !c.is_ascii_alphabetic()).next()?;
+	let warning = match word.to_ascii_lowercase().as_str() {
+		"error" => false,
+		"warning" => true,
+		_ => return None,
+	};
+	let rest = &text[word.len()..];
+	let code = rest.find(':')?;
+	// only the error code can be between the severity and the colon
+	rest[..code].trim().chars().all(|c| c.is_ascii_alphanumeric()).then_some((warning, &rest[code + 1..]))
+}
+
+/// `0:12(5):`, `0:12:` or `0(12) :`, the leading source string number is ignored
+fn location(text: &str) -> Option<(u32, Option<u32>, &str)> {
+	let (_, rest) = number(text)?;
+	let (line, column, rest) = if let Some(rest) = rest.strip_prefix(':') {
+		let (line, rest) = number(rest)?;
+		match rest.strip_prefix('(').and_then(number) {
+			Some((column, rest)) => (line, Some(column), rest.strip_prefix(')')?),
+			None => (line, None, rest),
+		}
+	} else {
+		let (line, rest) = number(rest.strip_prefix('(')?)?;
+		(line, None, rest.strip_prefix(')')?)
+	};
+	Some((line, column, rest.trim_start().strip_prefix(':')?))
+}
+
+fn number(text: &str) -> Option<(u32, &str)> {
+	let digits = text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+	Some((text[..digits].parse().ok()?, &text[digits..]))
+}
@@ -1,3 +1,5 @@
+use std::cell::Cell;
+
 use crate::graphics::*;
 
 #[derive(Clone, Copy, Debug, PartialEq, Default)]
@@ -28,45 +30,81 @@ pub struct GlCache {
 	pub stored_index_buffer: GLuint,
 	pub stored_index_type: Option<u32>,
 	pub stored_vertex_buffer: GLuint,
+	pub stored_uniform_buffer: GLuint,
 	pub stored_target: GLuint,
 	pub stored_texture: GLuint,
 	pub index_buffer: GLuint,
 	pub index_type: Option<u32>,
 	pub vertex_buffer: GLuint,
+	// the generic GL_UNIFORM_BUFFER binding, glBindBufferRange changes it too
+	pub uniform_buffer: GLuint,
 	pub textures: [CachedTexture; MAX_SHADERSTAGE_IMAGES],
+	// sampler objects of the texture units, 0 samples with the texture's own parameters
+	pub samplers: [GLuint; MAX_SHADERSTAGE_IMAGES],
 	pub cur_pipeline: Option<Pipeline>,
-	pub color_blend: Option<BlendState>,
-	pub alpha_blend: Option<BlendState>,
+	// blend state and write mask of the draw buffers
+	pub color_targets: [ColorTargetState; MAX_COLOR_ATTACHMENTS],
 	pub stencil: Option<StencilState>,
 	pub cur_pass: Option<RenderPass>,
-	pub color_write: ColorMask,
+	// color, depth and stencil StoreOps of the current pass, missing colors are stored
+	pub cur_pass_store: (Vec<StoreOp>, StoreOp, StoreOp),
 	pub cull_face: CullFace,
+	pub program_point_size: bool,
 	pub attributes: [Option<CachedAttribute>; MAX_VERTEX_ATTRIBUTES],
+	// 0 before the first pipeline, or after its shader was deleted or reloaded
+	pub program: GLuint,
+	pub scissor_test: bool,
+	pub depth_test: bool,
+	pub depth_func: Comparison,
+	pub depth_write: bool,
+	pub polygon_offset: Option<(f32, f32)>,
+	pub front_face: FrontFaceOrder,
+	// None until the first pass sets them
+	pub viewport: Option<(i32, i32, i32, i32)>,
+	pub scissor: Option<(i32, i32, i32, i32)>,
+	// counters of the current frame, draw takes &self
+	pub stats: Cell<FrameStats>,
 }
 
 impl GlCache {
+	pub fn count(&self, f: impl FnOnce(&mut FrameStats)) {
+		let mut stats = self.stats.get();
+		f(&mut stats);
+		self.stats.set(stats);
+	}
+
+	/// Count a piece of state sent to GL, or skipped when GL already had it
+	pub fn count_state(&self, issued: bool) {
+		self.count(|stats| match issued {
+			true => stats.gl_calls_issued += 1,
+			false => stats.gl_calls_skipped += 1,
+		});
+	}
+
 	pub fn bind_buffer(&mut self, target: GLenum, buffer: GLuint, index_type: Option<u32>) {
-		if target == GL_ARRAY_BUFFER {
-			if self.vertex_buffer != buffer {
-				self.vertex_buffer = buffer;
-				unsafe {
-					glBindBuffer(target, buffer);
-				}
-			}
+		let cached = if target == GL_ARRAY_BUFFER {
+			&mut self.vertex_buffer
+		} else if target == GL_UNIFORM_BUFFER {
+			&mut self.uniform_buffer
 		} else {
-			if self.index_buffer != buffer {
-				self.index_buffer = buffer;
-				unsafe {
-					glBindBuffer(target, buffer);
-				}
-			}
 			self.index_type = index_type;
+			&mut self.index_buffer
+		};
+		let issued = *cached != buffer;
+		if issued {
+			*cached = buffer;
+			unsafe {
+				glBindBuffer(target, buffer);
+			}
 		}
+		self.count_state(issued);
 	}
 
 	pub fn store_buffer_binding(&mut self, target: GLenum) {
 		if target == GL_ARRAY_BUFFER {
 			self.stored_vertex_buffer = self.vertex_buffer;
+		} else if target == GL_UNIFORM_BUFFER {
+			self.stored_uniform_buffer = self.uniform_buffer;
 		} else {
 			self.stored_index_buffer = self.index_buffer;
 			self.stored_index_type = self.index_type;
@@ -79,23 +117,52 @@ impl GlCache {
 				self.bind_buffer(target, self.stored_vertex_buffer, None);
 				self.stored_vertex_buffer = 0;
 			}
-		} else if self.stored_index_buffer != 0 {
+		} else if target == GL_UNIFORM_BUFFER {
+			if self.stored_uniform_buffer != 0 {
+				self.bind_buffer(target, self.stored_uniform_buffer, None);
+				self.stored_uniform_buffer = 0;
+			}
+		} else {
+			// also unbinds, a draw without an index buffer must not pick up the one just created
 			self.bind_buffer(target, self.stored_index_buffer, self.stored_index_type);
 			self.stored_index_buffer = 0;
 		}
 	}
 
 	pub fn bind_texture(&mut self, slot_index: usize, target: GLuint, texture: GLuint) {
+		let issued = self.textures[slot_index].target != target || self.textures[slot_index].texture != texture;
+		self.count_state(issued);
 		unsafe {
 			glActiveTexture(GL_TEXTURE0 + slot_index as GLuint);
-			if self.textures[slot_index].target != target || self.textures[slot_index].texture != texture {
+			if issued {
 				let target = if target == 0 { GL_TEXTURE_2D } else { target };
+				// every target has its own binding in the unit, do not leave the previous one behind
+				let cached = self.textures[slot_index];
+				if cached.target != target && cached.target != 0 && cached.texture != 0 {
+					glBindTexture(cached.target, 0);
+				}
 				glBindTexture(target, texture);
 				self.textures[slot_index] = CachedTexture { target, texture };
 			}
 		}
 	}
 
+	pub fn bind_sampler(&mut self, slot_index: usize, sampler: GLuint) {
+		let issued = self.samplers[slot_index] != sampler;
+		if issued {
+			unsafe { glBindSampler(slot_index as GLuint, sampler) };
+			self.samplers[slot_index] = sampler;
+		}
+		self.count_state(issued);
+	}
+
+	/// glDeleteSamplers unbinds the sampler from all the units
+	pub fn forget_sampler(&mut self, sampler: GLuint) {
+		for cached in self.samplers.iter_mut().filter(|cached| **cached == sampler) {
+			*cached = 0;
+		}
+	}
+
 	pub fn store_texture_binding(&mut self, slot_index: usize) {
 		self.stored_target = self.textures[slot_index].target;
 		self.stored_texture = self.textures[slot_index].texture;
@@ -111,6 +178,10 @@ impl GlCache {
 
 		self.bind_buffer(GL_ELEMENT_ARRAY_BUFFER, 0, None);
 		self.index_buffer = 0;
+
+		if self.uniform_buffer != 0 {
+			self.bind_buffer(GL_UNIFORM_BUFFER, 0, None);
+		}
 	}
 
 	pub fn clear_texture_bindings(&mut self) {
@@ -4,6 +4,7 @@ use crate::{window, ResourceManager};
 
 mod cache;
 
+use super::reflection::{attribute_format, image_type, uniform_type};
 use super::*;
 use cache::*;
 
@@ -39,10 +40,18 @@ struct ShaderUniform {
 	array_count: i32,
 }
 
+#[derive(Debug)]
+struct ShaderUniformBlock {
+	binding: u32,
+	/// std140 size of the block
+	size: usize,
+}
+
 struct ShaderInternal {
 	program: GLuint,
 	images: Vec<ShaderImage>,
 	uniforms: Vec<ShaderUniform>,
+	uniform_blocks: Vec<ShaderUniformBlock>,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -66,6 +75,22 @@ impl From<TextureFormat> for (GLenum, GLenum, GLenum) {
 				TextureFormat::Depth => (WebGl2RenderingContext::DEPTH_COMPONENT24, WebGl2RenderingContext::DEPTH_COMPONENT, WebGl2RenderingContext::UNSIGNED_INT),
 				TextureFormat::Depth32 => (WebGl2RenderingContext::DEPTH_COMPONENT32F, WebGl2RenderingContext::DEPTH_COMPONENT, WebGl2RenderingContext::FLOAT),
 				TextureFormat::Alpha => (WebGl2RenderingContext::ALPHA, WebGl2RenderingContext::ALPHA, WebGl2RenderingContext::UNSIGNED_BYTE),
+				TextureFormat::R8 => (WebGl2RenderingContext::R8, WebGl2RenderingContext::RED, WebGl2RenderingContext::UNSIGNED_BYTE),
+				TextureFormat::RG8 => (WebGl2RenderingContext::RG8, WebGl2RenderingContext::RG, WebGl2RenderingContext::UNSIGNED_BYTE),
+				TextureFormat::R16F => (WebGl2RenderingContext::R16F, WebGl2RenderingContext::RED, WebGl2RenderingContext::HALF_FLOAT),
+				TextureFormat::RG16F => (WebGl2RenderingContext::RG16F, WebGl2RenderingContext::RG, WebGl2RenderingContext::HALF_FLOAT),
+				TextureFormat::R32F => (WebGl2RenderingContext::R32F, WebGl2RenderingContext::RED, WebGl2RenderingContext::FLOAT),
+				TextureFormat::RGBA32F => (WebGl2RenderingContext::RGBA32F, WebGl2RenderingContext::RGBA, WebGl2RenderingContext::FLOAT),
+				TextureFormat::SRGB8A8 => (WebGl2RenderingContext::SRGB8_ALPHA8, WebGl2RenderingContext::RGBA, WebGl2RenderingContext::UNSIGNED_BYTE),
+				TextureFormat::Depth24Stencil8 => (WebGl2RenderingContext::DEPTH24_STENCIL8, WebGl2RenderingContext::DEPTH_STENCIL, WebGl2RenderingContext::UNSIGNED_INT_24_8),
+				// compressed formats come from extensions, WebGl2RenderingContext has no constants for them
+				TextureFormat::ETC2RGB8 => (GL_COMPRESSED_RGB8_ETC2, WebGl2RenderingContext::RGB, WebGl2RenderingContext::UNSIGNED_BYTE),
+				TextureFormat::ETC2RGBA8 => (GL_COMPRESSED_RGBA8_ETC2_EAC, WebGl2RenderingContext::RGBA, WebGl2RenderingContext::UNSIGNED_BYTE),
+				TextureFormat::ASTC4x4 => (GL_COMPRESSED_RGBA_ASTC_4x4_KHR, WebGl2RenderingContext::RGBA, WebGl2RenderingContext::UNSIGNED_BYTE),
+				TextureFormat::ASTC6x6 => (GL_COMPRESSED_RGBA_ASTC_6x6_KHR, WebGl2RenderingContext::RGBA, WebGl2RenderingContext::UNSIGNED_BYTE),
+				TextureFormat::ASTC8x8 => (GL_COMPRESSED_RGBA_ASTC_8x8_KHR, WebGl2RenderingContext::RGBA, WebGl2RenderingContext::UNSIGNED_BYTE),
+				TextureFormat::BC1 => (GL_COMPRESSED_RGBA_S3TC_DXT1_EXT, WebGl2RenderingContext::RGBA, WebGl2RenderingContext::UNSIGNED_BYTE),
+				TextureFormat::BC3 => (GL_COMPRESSED_RGBA_S3TC_DXT5_EXT, WebGl2RenderingContext::RGBA, WebGl2RenderingContext::UNSIGNED_BYTE),
 			}
 		}
 
@@ -77,6 +102,22 @@ impl From<TextureFormat> for (GLenum, GLenum, GLenum) {
 			TextureFormat::Depth => (GL_DEPTH_COMPONENT, GL_DEPTH_COMPONENT, GL_UNSIGNED_SHORT),
 			TextureFormat::Depth32 => (GL_DEPTH_COMPONENT, GL_DEPTH_COMPONENT, GL_FLOAT),
 			TextureFormat::Alpha => (GL_R8, GL_RED, GL_UNSIGNED_BYTE),
+			TextureFormat::R8 => (GL_R8, GL_RED, GL_UNSIGNED_BYTE),
+			TextureFormat::RG8 => (GL_RG8, GL_RG, GL_UNSIGNED_BYTE),
+			TextureFormat::R16F => (GL_R16F, GL_RED, GL_HALF_FLOAT),
+			TextureFormat::RG16F => (GL_RG16F, GL_RG, GL_HALF_FLOAT),
+			TextureFormat::R32F => (GL_R32F, GL_RED, GL_FLOAT),
+			TextureFormat::RGBA32F => (GL_RGBA32F, GL_RGBA, GL_FLOAT),
+			TextureFormat::SRGB8A8 => (GL_SRGB8_ALPHA8, GL_RGBA, GL_UNSIGNED_BYTE),
+			TextureFormat::Depth24Stencil8 => (GL_DEPTH24_STENCIL8, GL_DEPTH_STENCIL, GL_UNSIGNED_INT_24_8),
+			// format and pixel_type are unused by glCompressedTexImage2D
+			TextureFormat::ETC2RGB8 => (GL_COMPRESSED_RGB8_ETC2, GL_RGB, GL_UNSIGNED_BYTE),
+			TextureFormat::ETC2RGBA8 => (GL_COMPRESSED_RGBA8_ETC2_EAC, GL_RGBA, GL_UNSIGNED_BYTE),
+			TextureFormat::ASTC4x4 => (GL_COMPRESSED_RGBA_ASTC_4x4_KHR, GL_RGBA, GL_UNSIGNED_BYTE),
+			TextureFormat::ASTC6x6 => (GL_COMPRESSED_RGBA_ASTC_6x6_KHR, GL_RGBA, GL_UNSIGNED_BYTE),
+			TextureFormat::ASTC8x8 => (GL_COMPRESSED_RGBA_ASTC_8x8_KHR, GL_RGBA, GL_UNSIGNED_BYTE),
+			TextureFormat::BC1 => (GL_COMPRESSED_RGBA_S3TC_DXT1_EXT, GL_RGBA, GL_UNSIGNED_BYTE),
+			TextureFormat::BC3 => (GL_COMPRESSED_RGBA_S3TC_DXT5_EXT, GL_RGBA, GL_UNSIGNED_BYTE),
 		}
 	}
 }
@@ -86,6 +127,8 @@ impl From<TextureKind> for GLuint {
 		match kind {
 			TextureKind::Texture2D => GL_TEXTURE_2D,
 			TextureKind::CubeMap => GL_TEXTURE_CUBE_MAP,
+			TextureKind::Texture2DArray => GL_TEXTURE_2D_ARRAY,
+			TextureKind::Texture3D => GL_TEXTURE_3D,
 		}
 	}
 }
@@ -148,19 +191,16 @@ impl From<CompareFunc> for GLenum {
 }
 
 impl Texture {
-	pub fn new(ctx: &mut GlContext, access: TextureAccess, source: TextureSource, params: TextureParams) -> Texture {
-		#[cfg(debug_assertions)]
-		if let TextureSource::Bytes(bytes_data) = source {
-			assert_eq!(params.format.size(params.width, params.height) as usize, bytes_data.len());
-		}
-
-		#[cfg(debug_assertions)]
-		if access != TextureAccess::RenderTarget {
-			assert!(params.sample_count == 0, "Multisampling is only supported for render textures");
+	pub fn new(ctx: &mut GlContext, access: TextureAccess, source: TextureSource, params: TextureParams) -> Result<Texture, GraphicsError> {
+		if params.format.is_compressed() && (access == TextureAccess::RenderTarget || params.kind.is_layered() || !ctx.texture_compression.supports(params.format)) {
+			return Err(GraphicsError::UnsupportedFormat(params.format));
 		}
+		source.check(access, &params)?;
 
 		let (internal_format, format, pixel_type) = params.format.into();
 
+		take_gl_error();
+
 		if access == TextureAccess::RenderTarget && params.sample_count != 0 {
 			let mut renderbuffer: u32 = 0;
 
@@ -168,9 +208,14 @@ impl Texture {
 				glGenRenderbuffers(1, &mut renderbuffer as *mut _);
 				glBindRenderbuffer(GL_RENDERBUFFER, renderbuffer as _);
 				glRenderbufferStorageMultisample(GL_RENDERBUFFER, params.sample_count, internal_format, params.width as _, params.height as _);
+
+				if let Err(err) = texture_allocation_result(params.format) {
+					glDeleteRenderbuffers(1, &renderbuffer as *const _);
+					return Err(err);
+				}
 			}
 
-			return Texture { raw: renderbuffer, params };
+			return Ok(Texture { raw: renderbuffer, params });
 		}
 
 		ctx.cache.store_texture_binding(0);
@@ -195,6 +240,8 @@ impl Texture {
 			}
 
 			match source {
+				source if params.kind.is_layered() => Texture::tex_image_3d(&params, source),
+				source if params.format.is_compressed() => Texture::compressed_tex_image_2d(&params, source),
 				TextureSource::Empty => {
 					glTexImage2D(
 						GL_TEXTURE_2D,
@@ -209,10 +256,6 @@ impl Texture {
 					);
 				}
 				TextureSource::Bytes(source) => {
-					debug_assert!(
-						params.kind == TextureKind::Texture2D,
-						"incompatible TextureKind and TextureSource. Cubemaps require TextureSource::Array of 6 textures."
-					);
 					glTexImage2D(
 						GL_TEXTURE_2D,
 						0,
@@ -226,27 +269,23 @@ impl Texture {
 					);
 				}
 				TextureSource::Array(array) => {
-					#[cfg(debug_assertions)]
-					if params.kind == TextureKind::CubeMap {
-						assert!(array.len() == 6, "Cubemaps require TextureSource::Array of 6 textures.");
-					}
-
 					for (cubemap_face, mipmaps) in array.iter().enumerate() {
 						if mipmaps.len() != 1 {
-							glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_BASE_LEVEL, 0);
-							glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MAX_LEVEL, array.len() as _);
+							glTexParameteri(params.kind.into(), GL_TEXTURE_BASE_LEVEL, 0);
+							glTexParameteri(params.kind.into(), GL_TEXTURE_MAX_LEVEL, mipmaps.len() as i32 - 1);
 						}
 						for (mipmap_level, bytes) in mipmaps.iter().enumerate() {
 							let target = match params.kind {
 								TextureKind::Texture2D => GL_TEXTURE_2D,
 								TextureKind::CubeMap => GL_TEXTURE_CUBE_MAP_POSITIVE_X + cubemap_face as u32,
+								TextureKind::Texture2DArray | TextureKind::Texture3D => unreachable!(),
 							};
 							glTexImage2D(
 								target,
 								mipmap_level as _,
 								internal_format as i32,
-								params.width as i32,
-								params.height as i32,
+								(params.width as i32 >> mipmap_level).max(1),
+								(params.height as i32 >> mipmap_level).max(1),
 								0,
 								format,
 								pixel_type,
@@ -257,11 +296,7 @@ impl Texture {
 				}
 			}
 
-			let wrap = match params.wrap {
-				TextureWrap::Repeat => GL_REPEAT,
-				TextureWrap::Mirror => GL_MIRRORED_REPEAT,
-				TextureWrap::Clamp => GL_CLAMP_TO_EDGE,
-			};
+			let wrap = Texture::gl_wrap(params.wrap, ctx.features.clamp_to_border);
 
 			let min_filter = Texture::gl_filter(params.min_filter, params.mipmap_filter);
 			let mag_filter = match params.mag_filter {
@@ -271,15 +306,84 @@ impl Texture {
 
 			glTexParameteri(params.kind.into(), GL_TEXTURE_WRAP_S, wrap as i32);
 			glTexParameteri(params.kind.into(), GL_TEXTURE_WRAP_T, wrap as i32);
+			if params.kind == TextureKind::Texture3D {
+				glTexParameteri(params.kind.into(), GL_TEXTURE_WRAP_R, wrap as i32);
+			}
 			glTexParameteri(params.kind.into(), GL_TEXTURE_MIN_FILTER, min_filter as i32);
 			glTexParameteri(params.kind.into(), GL_TEXTURE_MAG_FILTER, mag_filter as i32);
+			if let Some(comparison) = params.compare {
+				glTexParameteri(params.kind.into(), GL_TEXTURE_COMPARE_MODE, GL_COMPARE_REF_TO_TEXTURE as i32);
+				glTexParameteri(params.kind.into(), GL_TEXTURE_COMPARE_FUNC, GLenum::from(comparison) as i32);
+			}
+		}
+
+		let result = texture_allocation_result(params.format);
+		if result.is_err() {
+			unsafe { glDeleteTextures(1, &texture as *const _) };
 		}
 
 		ctx.cache.restore_texture_binding(0);
-		Texture { raw: texture, params }
+		result.map(|_| Texture { raw: texture, params })
+	}
+
+	/// Upload a compressed `Texture2D` or `CubeMap` with all the mip levels of `source`, checked by `TextureSource::check`.
+	/// The texture should be bound to the first texture unit.
+	unsafe fn compressed_tex_image_2d(params: &TextureParams, source: TextureSource) {
+		let (internal_format, _, _) = params.format.into();
+		let faces: &[&[&[u8]]] = match source {
+			TextureSource::Empty => unreachable!(),
+			TextureSource::Bytes(bytes) => &[&[bytes]],
+			TextureSource::Array(array) => array,
+		};
+
+		let levels = faces[0].len();
+		if levels != 1 {
+			glTexParameteri(params.kind.into(), GL_TEXTURE_BASE_LEVEL, 0);
+			glTexParameteri(params.kind.into(), GL_TEXTURE_MAX_LEVEL, levels as i32 - 1);
+		}
+		for (cubemap_face, mipmaps) in faces.iter().enumerate() {
+			let target = match params.kind {
+				TextureKind::CubeMap => GL_TEXTURE_CUBE_MAP_POSITIVE_X + cubemap_face as u32,
+				_ => GL_TEXTURE_2D,
+			};
+			for (level, bytes) in mipmaps.iter().enumerate() {
+				let (width, height) = ((params.width >> level).max(1), (params.height >> level).max(1));
+				glCompressedTexImage2D(target, level as _, internal_format, width as _, height as _, 0, bytes.len() as _, bytes.as_ptr() as *const _);
+			}
+		}
+	}
+
+	/// Allocate a `Texture2DArray` or `Texture3D` with all the mip levels of `source`, checked by `TextureSource::check`.
+	/// The texture should be bound to the first texture unit.
+	unsafe fn tex_image_3d(params: &TextureParams, source: TextureSource) {
+		let (internal_format, format, pixel_type) = params.format.into();
+		let target = params.kind.into();
+
+		let (width, height, depth) = (params.width as i32, params.height as i32, params.depth as i32);
+		match source {
+			TextureSource::Empty => glTexImage3D(target, 0, internal_format as _, width, height, depth, 0, format, pixel_type, std::ptr::null()),
+			TextureSource::Bytes(bytes) => glTexImage3D(target, 0, internal_format as _, width, height, depth, 0, format, pixel_type, bytes.as_ptr() as *const _),
+			TextureSource::Array(array) => {
+				let levels = array.first().map_or(1, |mipmaps| mipmaps.len());
+				if levels != 1 {
+					glTexParameteri(target, GL_TEXTURE_BASE_LEVEL, 0);
+					glTexParameteri(target, GL_TEXTURE_MAX_LEVEL, levels as i32 - 1);
+				}
+				for level in 0..levels {
+					let (width, height) = ((width >> level).max(1), (height >> level).max(1));
+					let depth = if params.kind == TextureKind::Texture3D { (depth >> level).max(1) } else { depth };
+
+					glTexImage3D(target, level as _, internal_format as _, width, height, depth, 0, format, pixel_type, std::ptr::null());
+					for (layer, mipmaps) in array.iter().take(depth as usize).enumerate() {
+						glTexSubImage3D(target, level as _, 0, 0, layer as _, width, height, 1, format, pixel_type, mipmaps[level].as_ptr() as *const _);
+					}
+				}
+			}
+		}
 	}
 
 	pub fn resize(&mut self, ctx: &mut GlContext, width: u32, height: u32, source: Option<&[u8]>) {
+		assert!(!self.params.format.is_compressed(), "Compressed textures can not be resized");
 		ctx.cache.store_texture_binding(0);
 		ctx.cache.bind_texture(0, self.params.kind.into(), self.raw);
 
@@ -288,6 +392,12 @@ impl Texture {
 		self.params.width = width;
 		self.params.height = height;
 
+		if self.params.kind.is_layered() {
+			unsafe { Texture::tex_image_3d(&self.params, source.map_or(TextureSource::Empty, TextureSource::Bytes)) };
+			ctx.cache.restore_texture_binding(0);
+			return;
+		}
+
 		unsafe {
 			glTexImage2D(
 				GL_TEXTURE_2D,
@@ -308,10 +418,16 @@ impl Texture {
 		ctx.cache.restore_texture_binding(0);
 	}
 
-	pub fn update_texture_part(&self, ctx: &mut GlContext, x_offset: i32, y_offset: i32, width: i32, height: i32, source: &[u8]) {
+	pub fn update_texture_part(&self, ctx: &mut GlContext, layer: i32, x_offset: i32, y_offset: i32, width: i32, height: i32, source: &[u8]) {
+		assert!(!self.params.format.is_compressed(), "Compressed textures can not be updated");
 		assert_eq!(self.size(width as _, height as _), source.len());
 		assert!(x_offset + width <= self.params.width as _);
 		assert!(y_offset + height <= self.params.height as _);
+		if self.params.kind.is_layered() {
+			assert!(layer >= 0 && layer < self.params.depth as _, "Layer {} is out of bounds", layer);
+		} else {
+			assert!(layer == 0, "{:?} has no layers", self.params.kind);
+		}
 
 		ctx.cache.store_texture_binding(0);
 		ctx.cache.bind_texture(0, self.params.kind.into(), self.raw);
@@ -326,14 +442,18 @@ impl Texture {
 				if self.params.format == TextureFormat::Alpha {
 					// if alpha miniquad_wasm_bindgen texture, the value on non-WASM is stored in red channel
 					// swizzle red -> alpha
-					glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_SWIZZLE_A, GL_RED as _);
+					glTexParameteri(self.params.kind.into(), GL_TEXTURE_SWIZZLE_A, GL_RED as _);
 				} else {
 					// keep alpha -> alpha
-					glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_SWIZZLE_A, GL_ALPHA as _);
+					glTexParameteri(self.params.kind.into(), GL_TEXTURE_SWIZZLE_A, GL_ALPHA as _);
 				}
 			}
 
-			glTexSubImage2D(GL_TEXTURE_2D, 0, x_offset as _, y_offset as _, width as _, height as _, format, pixel_type, source.as_ptr() as *const _);
+			if self.params.kind.is_layered() {
+				glTexSubImage3D(self.params.kind.into(), 0, x_offset, y_offset, layer, width, height, 1, format, pixel_type, source.as_ptr() as *const _);
+			} else {
+				glTexSubImage2D(GL_TEXTURE_2D, 0, x_offset as _, y_offset as _, width as _, height as _, format, pixel_type, source.as_ptr() as *const _);
+			}
 		}
 
 		ctx.cache.restore_texture_binding(0);
@@ -343,6 +463,16 @@ impl Texture {
 	pub fn read_pixels(&self, bytes: &mut [u8]) {
 		let (_, format, pixel_type) = self.params.format.into();
 
+		self.with_framebuffer(|| unsafe {
+			glPixelStorei(GL_PACK_ALIGNMENT, 1); // rows of R8 and RG8 textures are not 4 byte aligned
+			glReadPixels(0, 0, self.params.width as _, self.params.height as _, format, pixel_type, bytes.as_mut_ptr() as _);
+			glPixelStorei(GL_PACK_ALIGNMENT, 4);
+		});
+	}
+
+	/// Run `f` with the texture attached to a temporary framebuffer
+	fn with_framebuffer<R>(&self, f: impl FnOnce() -> R) -> R {
+		assert!(!self.params.format.is_compressed(), "Compressed textures can not be read back");
 		let mut fbo = 0;
 
 		unsafe {
@@ -350,12 +480,17 @@ impl Texture {
 			glGetIntegerv(GL_DRAW_FRAMEBUFFER_BINDING, &mut bound_fbo);
 			glGenFramebuffers(1, &mut fbo);
 			glBindFramebuffer(gl::GL_FRAMEBUFFER, fbo);
-			glFramebufferTexture2D(gl::GL_FRAMEBUFFER, gl::GL_COLOR_ATTACHMENT0, gl::GL_TEXTURE_2D, self.raw, 0);
+			if self.params.kind.is_layered() {
+				glFramebufferTextureLayer(gl::GL_FRAMEBUFFER, gl::GL_COLOR_ATTACHMENT0, self.raw, 0, 0);
+			} else {
+				glFramebufferTexture2D(gl::GL_FRAMEBUFFER, gl::GL_COLOR_ATTACHMENT0, gl::GL_TEXTURE_2D, self.raw, 0);
+			}
 
-			glReadPixels(0, 0, self.params.width as _, self.params.height as _, format, pixel_type, bytes.as_mut_ptr() as _);
+			let res = f();
 
 			glBindFramebuffer(gl::GL_FRAMEBUFFER, bound_fbo as _);
 			glDeleteFramebuffers(1, &fbo);
+			res
 		}
 	}
 
@@ -364,6 +499,15 @@ impl Texture {
 		self.params.format.size(width, height) as usize
 	}
 
+	fn gl_wrap(wrap: TextureWrap, clamp_to_border: bool) -> GLenum {
+		match wrap {
+			TextureWrap::Repeat => GL_REPEAT,
+			TextureWrap::Mirror => GL_MIRRORED_REPEAT,
+			TextureWrap::ClampToBorder if clamp_to_border => GL_CLAMP_TO_BORDER,
+			TextureWrap::Clamp | TextureWrap::ClampToBorder => GL_CLAMP_TO_EDGE,
+		}
+	}
+
 	fn gl_filter(filter: FilterMode, mipmap_filter: MipmapFilterMode) -> GLenum {
 		match filter {
 			FilterMode::Nearest => match mipmap_filter {
@@ -390,6 +534,7 @@ type UniformLocation = Option<GLint>;
 
 pub struct ShaderImage {
 	gl_loc: UniformLocation,
+	image_type: ImageType,
 }
 
 fn get_uniform_location(program: GLuint, name: &str) -> Option<i32> {
@@ -410,27 +555,145 @@ pub(crate) struct RenderPassInternal {
 	depth_texture: Option<TextureId>,
 }
 
-struct Textures(Vec<Texture>);
+struct Textures(ResourceManager<Texture>);
 
 impl Textures {
 	fn get(&self, texture: TextureId) -> Texture {
+		self.try_get(texture).unwrap_or_else(|_| panic!("{:?} is deleted", texture))
+	}
+
+	fn try_get(&self, texture: TextureId) -> Result<Texture, GraphicsError> {
 		match texture.0 {
-			TextureIdInner::Raw(RawId(texture)) => Texture {
+			TextureIdInner::Raw(RawId(texture)) => Ok(Texture {
 				raw: texture,
 				params: Default::default(),
-			},
-			TextureIdInner::Managed(texture) => self.0[texture],
+			}),
+			TextureIdInner::Managed(id) => self.0.get(id).copied().ok_or(GraphicsError::StaleId { kind: "TextureId", id }),
+		}
+	}
+}
+
+/// A `glFenceSync` object. Only used from the GL thread, same as the rest of GL names.
+struct Fence(GLsync);
+
+unsafe impl Send for Fence {}
+
+enum Readback {
+	/// Pixel pack buffer the pixels are copied to, and a fence signaled once they are there
+	Pending { buffer: GLuint, fence: Fence, size: usize },
+	/// No pixel buffers on GL2 and GLES2, the pixels were read right away
+	Done(Vec<u8>),
+}
+
+impl Readback {
+	fn size(&self) -> usize {
+		match self {
+			Readback::Pending { size, .. } => *size,
+			Readback::Done(pixels) => pixels.len(),
+		}
+	}
+}
+
+/// Copy the first `bytes.len()` bytes of the bound GL_PIXEL_PACK_BUFFER.
+unsafe fn read_pixel_buffer(bytes: &mut [u8]) {
+	#[cfg(not(target_arch = "wasm32"))]
+	{
+		let mapped = glMapBufferRange(GL_PIXEL_PACK_BUFFER, 0, bytes.len() as _, GL_MAP_READ_BIT);
+		assert!(!mapped.is_null(), "Failed to map the pixel buffer");
+		std::ptr::copy_nonoverlapping(mapped as *const u8, bytes.as_mut_ptr(), bytes.len());
+		glUnmapBuffer(GL_PIXEL_PACK_BUFFER);
+	}
+
+	#[cfg(target_arch = "wasm32")]
+	glGetBufferSubData(GL_PIXEL_PACK_BUFFER, 0, bytes.len() as _, bytes.as_mut_ptr() as _);
+}
+
+/// Read and reset the GL error flags.
+/// Called before an allocation too, to not blame it for the errors of earlier calls.
+#[allow(unused_unsafe)] // glGetError is safe on WebGL
+fn take_gl_error() -> GLenum {
+	let mut error = GL_NO_ERROR;
+	// there is one flag per error kind, the loop is bounded in case the context is lost
+	for _ in 0..8 {
+		match unsafe { glGetError() } {
+			GL_NO_ERROR => break,
+			GL_OUT_OF_MEMORY => error = GL_OUT_OF_MEMORY,
+			other if error == GL_NO_ERROR => error = other,
+			_ => {}
 		}
 	}
+	error
+}
+
+fn texture_allocation_result(format: TextureFormat) -> Result<(), GraphicsError> {
+	match take_gl_error() {
+		GL_NO_ERROR => Ok(()),
+		GL_OUT_OF_MEMORY => Err(GraphicsError::OutOfMemory),
+		// formats and format/type combinations the driver does not know about
+		GL_INVALID_ENUM | GL_INVALID_VALUE | GL_INVALID_OPERATION => Err(GraphicsError::UnsupportedFormat(format)),
+		_ => Ok(()),
+	}
+}
+/// Names of the extensions supported by the context, with their "GL_" prefix on desktop and GLES.
+unsafe fn gl_extensions() -> Vec<String> {
+	// GL3 and GLES3 list extensions one by one
+	#[cfg(not(target_arch = "wasm32"))]
+	{
+		let mut count = 0;
+		glGetIntegerv(GL_NUM_EXTENSIONS, &mut count);
+		take_gl_error();
+		if count > 0 {
+			return (0..count as GLuint)
+				.map(|i| glGetStringi(GL_EXTENSIONS, i))
+				.filter(|name| !name.is_null())
+				.map(|name| std::ffi::CStr::from_ptr(name as _).to_string_lossy().into_owned())
+				.collect();
+		}
+	}
+
+	// GL2, GLES2 and WebGL have a single space separated string
+	let names = glGetString(GL_EXTENSIONS);
+	take_gl_error();
+	if names.is_null() {
+		return vec![];
+	}
+	let extensions = std::ffi::CStr::from_ptr(names as _).to_string_lossy().split_whitespace().map(String::from).collect();
+
+	#[cfg(target_arch = "wasm32")]
+	{
+		// reclaim memory
+		let _ = std::ffi::CString::from_raw(names as _);
+	}
+
+	extensions
 }
+
 pub struct GlContext {
 	shaders: ResourceManager<ShaderInternal>,
 	pipelines: ResourceManager<PipelineInternal>,
 	passes: ResourceManager<RenderPassInternal>,
 	buffers: ResourceManager<Buffer>,
 	textures: Textures,
+	readbacks: ResourceManager<Readback>,
 	default_framebuffer: GLuint,
+	uniform_buffer_offset_alignment: usize,
+	// pixel pack buffers and fences come with GL3 and GLES3, same as uniform buffers
+	pixel_buffers: bool,
+	// GL_PROGRAM_POINT_SIZE is desktop GL only, GLES and WebGL always use gl_PointSize
+	program_point_size_cap: bool,
+	// glInvalidateFramebuffer is GL4.3, GLES3 and WebGL2, the store actions are only hints without it
+	invalidate_framebuffer: bool,
+	// glMultiDrawElements is desktop GL only, natively GLES has it as EXT_multi_draw_arrays with a suffix
+	multi_draw: bool,
+	texture_compression: TextureCompression,
+	features: Features,
+	limits: Limits,
+	extensions: Vec<String>,
+	// GL sampler object and the params it was made with
+	samplers: ResourceManager<(GLuint, SamplerParams)>,
 	pub(crate) cache: GlCache,
+	// counters of the last committed frame
+	frame_stats: FrameStats,
 }
 
 impl Default for GlContext {
@@ -449,71 +712,205 @@ impl GlContext {
 			glGenVertexArrays(1, &mut vao as *mut _);
 			glBindVertexArray(vao);
 
+			// stays 0 on GL2 and GLES2
+			let mut uniform_buffer_offset_alignment = 0;
+			glGetIntegerv(GL_UNIFORM_BUFFER_OFFSET_ALIGNMENT, &mut uniform_buffer_offset_alignment as *mut _);
+			take_gl_error();
+
+			// desktop GL rather than GLES, and the GL or GLES version
+			#[cfg(not(target_arch = "wasm32"))]
+			let (desktop_gl, gles3, version) = {
+				let version_string = glGetString(GL_VERSION);
+				let version_string = if version_string.is_null() { &[][..] } else { std::ffi::CStr::from_ptr(version_string as _).to_bytes() };
+				// "4.6.0 NVIDIA 550.54" or "OpenGL ES 3.2 Mesa 22.3.6", major and minor are single digits
+				let version = match version_string.iter().position(u8::is_ascii_digit).map(|start| &version_string[start..]) {
+					Some([major, b'.', minor, ..]) if minor.is_ascii_digit() => (major - b'0', minor - b'0'),
+					_ => (0, 0),
+				};
+				(!version_string.is_empty() && !version_string.starts_with(b"OpenGL ES"), version_string.starts_with(b"OpenGL ES 3"), version)
+			};
+			// WebGL2 is GLES3.0
+			#[cfg(target_arch = "wasm32")]
+			let (desktop_gl, gles3, version) = (false, false, (3, 0));
+
+			let extensions = gl_extensions();
+			let has_extension = |name: &str| extensions.iter().any(|extension| extension == name || extension.strip_prefix("GL_") == Some(name));
+			let texture_compression = TextureCompression {
+				etc2: gles3 || has_extension("ARB_ES3_compatibility") || has_extension("WEBGL_compressed_texture_etc"),
+				astc: has_extension("KHR_texture_compression_astc_ldr") || has_extension("WEBGL_compressed_texture_astc"),
+				s3tc: has_extension("EXT_texture_compression_s3tc") || has_extension("WEBGL_compressed_texture_s3tc"),
+			};
+			let features = Features {
+				instancing: version >= (3, 0) || has_extension("ARB_instanced_arrays") || has_extension("ANGLE_instanced_arrays") || has_extension("EXT_instanced_arrays"),
+				// desktop GL3 can render to float textures without an extension
+				float_render_targets: (desktop_gl && uniform_buffer_offset_alignment != 0) || has_extension("EXT_color_buffer_float"),
+				anisotropic_filtering: has_extension("EXT_texture_filter_anisotropic") || has_extension("ARB_texture_filter_anisotropic"),
+				samplers: version >= (3, 3) || (!desktop_gl && version >= (3, 0)) || has_extension("ARB_sampler_objects"),
+				clamp_to_border: desktop_gl || version >= (3, 2) || has_extension("EXT_texture_border_clamp") || has_extension("OES_texture_border_clamp"),
+				// the GLES3.0 extension functions are only loaded on WebGL, natively they have an OES suffix
+				independent_blend: version >= (4, 0) || (!desktop_gl && version >= (3, 2)) || (cfg!(target_arch = "wasm32") && has_extension("OES_draw_buffers_indexed")),
+				base_vertex: version >= (3, 2) || has_extension("ARB_draw_elements_base_vertex") || (cfg!(target_arch = "wasm32") && has_extension("WEBGL_draw_instanced_base_vertex_base_instance")),
+			};
+
+			// limits unknown to the context, like the GL3 ones on GL2, are left at 0
+			let get_integer = |name| {
+				let mut value = 0;
+				glGetIntegerv(name, &mut value);
+				take_gl_error();
+				value.max(0) as u32
+			};
+			// desktop GL before 4.1 only has the uniform components
+			let uniform_vectors = |vectors, components| match get_integer(vectors) {
+				0 => get_integer(components) / 4,
+				vectors => vectors,
+			};
+			let limits = Limits {
+				max_texture_size: get_integer(GL_MAX_TEXTURE_SIZE),
+				max_cube_map_texture_size: get_integer(GL_MAX_CUBE_MAP_TEXTURE_SIZE),
+				max_3d_texture_size: get_integer(GL_MAX_3D_TEXTURE_SIZE),
+				max_array_texture_layers: get_integer(GL_MAX_ARRAY_TEXTURE_LAYERS),
+				max_color_attachments: get_integer(GL_MAX_COLOR_ATTACHMENTS).min(get_integer(GL_MAX_DRAW_BUFFERS)).max(1),
+				max_samples: get_integer(GL_MAX_SAMPLES),
+				max_vertex_attributes: get_integer(GL_MAX_VERTEX_ATTRIBS).min(MAX_VERTEX_ATTRIBUTES as u32),
+				max_vertex_uniform_vectors: uniform_vectors(GL_MAX_VERTEX_UNIFORM_VECTORS, GL_MAX_VERTEX_UNIFORM_COMPONENTS),
+				max_fragment_uniform_vectors: uniform_vectors(GL_MAX_FRAGMENT_UNIFORM_VECTORS, GL_MAX_FRAGMENT_UNIFORM_COMPONENTS),
+				max_texture_units: get_integer(GL_MAX_TEXTURE_IMAGE_UNITS).min(MAX_SHADERSTAGE_IMAGES as u32),
+				max_anisotropy: if features.anisotropic_filtering { get_integer(GL_MAX_TEXTURE_MAX_ANISOTROPY_EXT).max(1) } else { 1 },
+			};
+
 			GlContext {
 				default_framebuffer,
-				shaders: ResourceManager::default(),
-				pipelines: ResourceManager::default(),
-				passes: ResourceManager::default(),
-				buffers: ResourceManager::default(),
-				textures: Textures(vec![]),
+				uniform_buffer_offset_alignment: uniform_buffer_offset_alignment as usize,
+				pixel_buffers: uniform_buffer_offset_alignment != 0,
+				program_point_size_cap: desktop_gl,
+				invalidate_framebuffer: version >= (4, 3) || (!desktop_gl && version >= (3, 0)) || has_extension("ARB_invalidate_subdata"),
+				multi_draw: desktop_gl || (cfg!(target_arch = "wasm32") && has_extension("WEBGL_multi_draw")),
+				texture_compression,
+				features,
+				limits,
+				extensions,
+				samplers: ResourceManager::new("SamplerId"),
+				shaders: ResourceManager::new("ShaderId"),
+				pipelines: ResourceManager::new("Pipeline"),
+				passes: ResourceManager::new("RenderPass"),
+				buffers: ResourceManager::new("BufferId"),
+				textures: Textures(ResourceManager::new("TextureId")),
+				readbacks: ResourceManager::new("ReadbackId"),
 				cache: GlCache {
 					cur_pass: None,
+					cur_pass_store: Default::default(),
 					stored_index_buffer: 0,
 					stored_index_type: None,
 					stored_vertex_buffer: 0,
+					stored_uniform_buffer: 0,
 					index_buffer: 0,
 					index_type: None,
 					vertex_buffer: 0,
+					uniform_buffer: 0,
 					cur_pipeline: None,
-					color_blend: None,
-					alpha_blend: None,
+					color_targets: [ColorTargetState::default(); MAX_COLOR_ATTACHMENTS],
 					stencil: None,
-					color_write: (true, true, true, true),
 					cull_face: CullFace::Nothing,
+					program_point_size: false,
 					stored_texture: 0,
 					stored_target: 0,
 					textures: [CachedTexture { target: 0, texture: 0 }; MAX_SHADERSTAGE_IMAGES],
+					samplers: [0; MAX_SHADERSTAGE_IMAGES],
 					attributes: [None; MAX_VERTEX_ATTRIBUTES],
+					program: 0,
+					scissor_test: false,
+					depth_test: false,
+					depth_func: Comparison::Less,
+					depth_write: true,
+					polygon_offset: None,
+					front_face: FrontFaceOrder::CounterClockwise,
+					viewport: None,
+					scissor: None,
+					stats: Default::default(),
 				},
+				frame_stats: FrameStats::default(),
 			}
 		}
 	}
 }
 
-fn load_shader_internal(vertex_shader: &str, fragment_shader: &str, meta: ShaderMeta) -> Result<ShaderInternal, ShaderError> {
-	unsafe {
-		let vertex_shader = load_shader(GL_VERTEX_SHADER, vertex_shader)?;
-		let fragment_shader = load_shader(GL_FRAGMENT_SHADER, fragment_shader)?;
+impl GlContext {
+	/// Start reading the bound framebuffer, see `texture_read_pixels_async`.
+	unsafe fn read_pixels_async(&mut self, width: i32, height: i32, format: GLenum, pixel_type: GLenum, size: usize) -> ReadbackId {
+		// `size` is computed for tightly packed rows
+		glPixelStorei(GL_PACK_ALIGNMENT, 1);
+		let readback = if self.pixel_buffers {
+			let mut buffer = 0;
+			glGenBuffers(1, &mut buffer as *mut _);
+			glBindBuffer(GL_PIXEL_PACK_BUFFER, buffer);
+			glBufferData(GL_PIXEL_PACK_BUFFER, size as _, std::ptr::null(), GL_STREAM_READ);
+			glReadPixels(0, 0, width, height, format, pixel_type, std::ptr::null_mut());
+			glBindBuffer(GL_PIXEL_PACK_BUFFER, 0);
+
+			let fence = Fence(glFenceSync(GL_SYNC_GPU_COMMANDS_COMPLETE, 0));
+			Readback::Pending { buffer, fence, size }
+		} else {
+			let mut pixels = vec![0; size];
+			glReadPixels(0, 0, width, height, format, pixel_type, pixels.as_mut_ptr() as _);
+			Readback::Done(pixels)
+		};
+		glPixelStorei(GL_PACK_ALIGNMENT, 4);
 
-		let program = glCreateProgram();
+		ReadbackId(self.readbacks.add(readback))
+	}
+}
 
-		glAttachShader(program, vertex_shader);
-		glAttachShader(program, fragment_shader);
-		glLinkProgram(program);
+/// `attribute_locations` are bound before linking, to give a reloaded program the attribute locations of the old one
+unsafe fn link_program(vertex_shader: &str, fragment_shader: &str, attribute_locations: &[(String, GLint)]) -> Result<GLuint, ShaderError> {
+	let attribute_locations = attribute_locations
+		.iter()
+		.map(|(name, location)| Ok((CString::new(name.as_str())?, *location)))
+		.collect::<Result<Vec<_>, ShaderError>>()?;
+	let vertex_shader = load_shader(GL_VERTEX_SHADER, vertex_shader)?;
+	let fragment_shader = load_shader(GL_FRAGMENT_SHADER, fragment_shader).inspect_err(|_| glDeleteShader(vertex_shader))?;
+
+	let program = glCreateProgram();
+
+	glAttachShader(program, vertex_shader);
+	glAttachShader(program, fragment_shader);
+	for (cname, location) in &attribute_locations {
+		glBindAttribLocation(program, *location as GLuint, cname.as_ptr() as *const _);
+	}
+	glLinkProgram(program);
 
-		// delete no longer used shaders
-		glDetachShader(program, vertex_shader);
-		glDeleteShader(vertex_shader);
-		glDeleteShader(fragment_shader);
+	// delete no longer used shaders
+	glDetachShader(program, vertex_shader);
+	glDeleteShader(vertex_shader);
+	glDeleteShader(fragment_shader);
 
-		let mut link_status = 0;
-		glGetProgramiv(program, GL_LINK_STATUS, &mut link_status as *mut _);
-		if link_status == 0 {
-			let mut max_length: i32 = 0;
-			glGetProgramiv(program, GL_INFO_LOG_LENGTH, &mut max_length as *mut _);
+	let mut link_status = 0;
+	glGetProgramiv(program, GL_LINK_STATUS, &mut link_status as *mut _);
+	if link_status == 0 {
+		let mut max_length: i32 = 0;
+		glGetProgramiv(program, GL_INFO_LOG_LENGTH, &mut max_length as *mut _);
 
-			let mut error_message = vec![0u8; max_length as usize + 1];
-			glGetProgramInfoLog(program, max_length, &mut max_length as *mut _, error_message.as_mut_ptr() as *mut _);
-			assert!(max_length >= 1);
-			let error_message = std::string::String::from_utf8_lossy(&error_message[0..max_length as usize - 1]);
-			return Err(ShaderError::LinkError(error_message.to_string()));
-		}
+		let mut error_message = vec![0u8; max_length as usize + 1];
+		glGetProgramInfoLog(program, max_length, &mut max_length as *mut _, error_message.as_mut_ptr() as *mut _);
+		assert!(max_length >= 1);
+		let error_message = std::string::String::from_utf8_lossy(&error_message[0..max_length as usize - 1]);
+		glDeleteProgram(program);
+		return Err(ShaderError::LinkError(error_message.to_string()));
+	}
+
+	Ok(program)
+}
+
+/// Leaves the program in use, also when it fails after linking
+fn load_shader_internal(vertex_shader: &str, fragment_shader: &str, meta: ShaderMeta, attribute_locations: &[(String, GLint)]) -> Result<ShaderInternal, ShaderError> {
+	unsafe {
+		let program = link_program(vertex_shader, fragment_shader, attribute_locations)?;
 
 		glUseProgram(program);
 
 		#[rustfmt::skip]
-        let images = meta.images.iter().map(|name| ShaderImage {
-            gl_loc: get_uniform_location(program, name),
+        let images = meta.images.iter().map(|image| ShaderImage {
+            gl_loc: get_uniform_location(program, &image.name),
+            image_type: image.image_type,
         }).collect();
 
 		#[rustfmt::skip]
@@ -527,8 +924,148 @@ fn load_shader_internal(vertex_shader: &str, fragment_shader: &str, meta: Shader
             Some(res)
         }).collect();
 
-		Ok(ShaderInternal { program, images, uniforms })
+		let mut uniform_blocks = vec![];
+		for block in &meta.uniform_blocks {
+			let cname = CString::new(block.name.as_str()).inspect_err(|_| glDeleteProgram(program))?;
+			let index = glGetUniformBlockIndex(program, cname.as_ptr() as *const _);
+			// unused blocks are optimized out, same as plain uniforms
+			if index == GL_INVALID_INDEX {
+				continue;
+			}
+			glUniformBlockBinding(program, index, block.binding);
+
+			// drivers may or may not pad the block to 16 bytes
+			let size = block.layout.std140_size();
+			let unpadded = block.layout.std140_offsets().last().map_or(0, |offset| {
+				let last = block.layout.uniforms.last().unwrap();
+				offset + last.uniform_type.size() * last.array_count
+			});
+			let mut data_size = 0;
+			glGetActiveUniformBlockiv(program, index, GL_UNIFORM_BLOCK_DATA_SIZE, &mut data_size as *mut _);
+			if (data_size as usize) < unpadded || data_size as usize > size {
+				glDeleteProgram(program);
+				return Err(ShaderError::LinkError(format!(
+					"Uniform block {} is {} bytes, but its UniformBlockLayout takes {} bytes with std140 layout",
+					block.name, data_size, size
+				)));
+			}
+
+			uniform_blocks.push(ShaderUniformBlock { binding: block.binding, size });
+		}
+
+		Ok(ShaderInternal {
+			program,
+			images,
+			uniforms,
+			uniform_blocks,
+		})
+	}
+}
+
+/// Active uniforms, images, uniform blocks and attributes of a linked program,
+/// with the ones of types `ShaderMeta` can not describe as `ShaderMismatch::UnsupportedType`
+unsafe fn reflect_program(program: GLuint, uniform_blocks: bool) -> (ShaderMeta, Vec<AttributeDesc>, Vec<ShaderMismatch>) {
+	let mut meta = ShaderMeta {
+		uniforms: UniformBlockLayout { uniforms: vec![] },
+		images: vec![],
+		uniform_blocks: vec![],
+	};
+	let mut unsupported = vec![];
+
+	// block members with their offsets, the offsets give the member order
+	let mut block_members: Vec<Vec<(GLint, UniformDesc)>> = vec![];
+	if uniform_blocks {
+		let mut count = 0;
+		glGetProgramiv(program, GL_ACTIVE_UNIFORM_BLOCKS, &mut count as *mut _);
+		for index in 0..count as GLuint {
+			let mut length = 0;
+			glGetActiveUniformBlockiv(program, index, GL_UNIFORM_BLOCK_NAME_LENGTH, &mut length as *mut _);
+			let mut name = vec![0u8; length.max(1) as usize];
+			glGetActiveUniformBlockName(program, index, name.len() as _, &mut length as *mut _, name.as_mut_ptr() as *mut _);
+			name.truncate(length as usize);
+			meta.uniform_blocks.push(UniformBlockDesc::new(&String::from_utf8_lossy(&name), index, vec![]));
+			block_members.push(vec![]);
+		}
+	}
+
+	let mut count = 0;
+	glGetProgramiv(program, GL_ACTIVE_UNIFORMS, &mut count as *mut _);
+	for index in 0..count as GLuint {
+		let Some((name, array_count, gl_type)) = active_variable(program, index, GL_ACTIVE_UNIFORM_MAX_LENGTH, glGetActiveUniform) else {
+			continue;
+		};
+		let mut block = -1;
+		if uniform_blocks {
+			glGetActiveUniformsiv(program, 1, &index as *const _, GL_UNIFORM_BLOCK_INDEX, &mut block as *mut _);
+		}
+
+		if block >= 0 {
+			let mut offset = 0;
+			glGetActiveUniformsiv(program, 1, &index as *const _, GL_UNIFORM_OFFSET, &mut offset as *mut _);
+			// "Block.member" for blocks with an instance name
+			let member = name.rsplit('.').next().unwrap();
+			match uniform_type(gl_type) {
+				Some(uniform_type) => block_members[block as usize].push((offset, UniformDesc::new(member, uniform_type).array(array_count))),
+				None => unsupported.push(ShaderMismatch::UnsupportedType { name, gl_type }),
+			}
+		} else if let Some(image_type) = image_type(gl_type) {
+			meta.images.push(ImageDesc::new(&name, image_type));
+		} else if let Some(uniform_type) = uniform_type(gl_type) {
+			meta.uniforms.uniforms.push(UniformDesc::new(&name, uniform_type).array(array_count));
+		} else {
+			unsupported.push(ShaderMismatch::UnsupportedType { name, gl_type });
+		}
+	}
+	for (block, mut members) in meta.uniform_blocks.iter_mut().zip(block_members) {
+		members.sort_by_key(|(offset, _)| *offset);
+		block.layout.uniforms = members.into_iter().map(|(_, member)| member).collect();
+	}
+
+	let mut attributes = vec![];
+	glGetProgramiv(program, GL_ACTIVE_ATTRIBUTES, &mut count as *mut _);
+	for index in 0..count as GLuint {
+		let Some((name, _, gl_type)) = active_variable(program, index, GL_ACTIVE_ATTRIBUTE_MAX_LENGTH, glGetActiveAttrib) else {
+			continue;
+		};
+		match attribute_format(gl_type) {
+			Some(format) => attributes.push(AttributeDesc { name, format }),
+			None => unsupported.push(ShaderMismatch::UnsupportedType { name, gl_type }),
+		}
+	}
+
+	(meta, attributes, unsupported)
+}
+
+type GetActiveVariable = unsafe fn(GLuint, GLuint, GLsizei, *mut GLsizei, *mut GLint, *mut GLenum, *mut GLchar);
+
+/// Active attributes with their locations
+unsafe fn attribute_locations(program: GLuint) -> Vec<(String, GLint)> {
+	let mut count = 0;
+	glGetProgramiv(program, GL_ACTIVE_ATTRIBUTES, &mut count as *mut _);
+	(0..count as GLuint)
+		.filter_map(|index| {
+			let (name, _, _) = active_variable(program, index, GL_ACTIVE_ATTRIBUTE_MAX_LENGTH, glGetActiveAttrib)?;
+			let cname = CString::new(name.as_str()).ok()?;
+			let location = glGetAttribLocation(program, cname.as_ptr() as *const _);
+			(location >= 0).then_some((name, location))
+		})
+		.collect()
+}
+
+/// Name without the "[0]" of arrays, array length and type of an active uniform or attribute, `None` for built-ins
+unsafe fn active_variable(program: GLuint, index: GLuint, max_length: GLenum, get: GetActiveVariable) -> Option<(String, usize, GLenum)> {
+	let mut length = 0;
+	glGetProgramiv(program, max_length, &mut length as *mut _);
+	let mut name = vec![0u8; length.max(1) as usize];
+	let (mut size, mut gl_type): (GLint, GLenum) = (0, 0);
+	get(program, index, name.len() as _, &mut length, &mut size, &mut gl_type, name.as_mut_ptr() as *mut _);
+	name.truncate(length as usize);
+
+	let name = String::from_utf8_lossy(&name);
+	if name.starts_with("gl_") {
+		return None;
 	}
+	Some((name.strip_suffix("[0]").unwrap_or(&name).to_string(), size as usize, gl_type))
 }
 
 pub unsafe fn load_shader(shader_type: GLenum, source: &str) -> Result<GLuint, ShaderError> {
@@ -557,6 +1094,7 @@ pub unsafe fn load_shader(shader_type: GLenum, source: &str) -> Result<GLuint, S
 		if error_message.ends_with('\0') {
 			error_message.pop();
 		}
+		glDeleteShader(shader);
 
 		return Err(ShaderError::CompilationError {
 			shader_type: match shader_type {
@@ -573,18 +1111,105 @@ pub unsafe fn load_shader(shader_type: GLenum, source: &str) -> Result<GLuint, S
 
 #[allow(unused_unsafe)]
 impl GlContext {
-	fn set_blend(&mut self, color_blend: Option<BlendState>, alpha_blend: Option<BlendState>) {
-		if color_blend.is_none() && alpha_blend.is_some() {
-			panic!("AlphaBlend without ColorBlend");
-		}
-		if self.cache.color_blend == color_blend && self.cache.alpha_blend == alpha_blend {
+	fn set_color_targets(&mut self, params: &PipelineParams) {
+		// indices past GL_MAX_DRAW_BUFFERS are invalid, try_new_pipeline makes sure they are not used
+		let count = (self.limits.max_color_attachments as usize).min(MAX_COLOR_ATTACHMENTS);
+		let targets: [ColorTargetState; MAX_COLOR_ATTACHMENTS] = std::array::from_fn(|i| params.color_target(i));
+		let issued = self.cache.color_targets[..count] != targets[..count];
+		self.cache.count_state(issued);
+		if !issued {
 			return;
 		}
 
+		let uniform = |targets: &[ColorTargetState]| targets.iter().all(|target| *target == targets[0]);
 		unsafe {
+			if uniform(&targets[..count]) {
+				let cached = uniform(&self.cache.color_targets[..count]).then_some(self.cache.color_targets[0]);
+				Self::apply_color_target(None, cached, targets[0]);
+			} else {
+				for (i, (cached, target)) in self.cache.color_targets.iter().zip(targets).take(count).enumerate() {
+					Self::apply_color_target(Some(i as GLuint), Some(*cached), target);
+				}
+			}
+		}
+		self.cache.color_targets = targets;
+	}
+
+	fn clear_attachments(&mut self, colors: &[Option<(f32, f32, f32, f32)>]) {
+		if colors.iter().any(Option::is_some) {
+			self.reset_color_write();
+		}
+		for (i, color) in colors.iter().enumerate() {
+			if let Some((r, g, b, a)) = *color {
+				unsafe {
+					glClearBufferfv(GL_COLOR, i as _, [r, g, b, a].as_ptr());
+				}
+			}
+		}
+	}
+
+	/// Drop the contents of the attachments of the bound framebuffer, a hint for tile-based GPUs.
+	fn invalidate_attachments(&self, colors: impl Iterator<Item = bool>, depth: bool, stencil: bool) {
+		if !self.invalidate_framebuffer {
+			return;
+		}
+		// the window's framebuffer has its own attachment names
+		let window = self.cache.cur_pass.is_none() && self.default_framebuffer == 0;
+		let mut attachments = vec![];
+		for (i, _) in colors.enumerate().filter(|(_, invalidate)| *invalidate) {
+			attachments.push(if window { GL_COLOR } else { GL_COLOR_ATTACHMENT0 + i as u32 });
+		}
+		if depth {
+			attachments.push(if window { GL_DEPTH } else { GL_DEPTH_ATTACHMENT });
+		}
+		if stencil {
+			attachments.push(if window { GL_STENCIL } else { GL_STENCIL_ATTACHMENT });
+		}
+		if !attachments.is_empty() {
+			unsafe {
+				glInvalidateFramebuffer(GL_FRAMEBUFFER, attachments.len() as _, attachments.as_ptr());
+			}
+		}
+	}
+
+	fn primitive_type(&self) -> GLenum {
+		let pipeline = self.cache.cur_pipeline.expect("Drawing without bound pipeline");
+		self.pipelines[pipeline.0].params.primitive_type.into()
+	}
+
+	/// GL type and size in bytes of the bound index buffer's indices, `None` without an index buffer
+	fn index_type(&self) -> Option<(GLenum, i32)> {
+		self.cache.index_type.map(|index_type| match index_type {
+			1 => (GL_UNSIGNED_BYTE, 1),
+			2 => (GL_UNSIGNED_SHORT, 2),
+			4 => (GL_UNSIGNED_INT, 4),
+			_ => unreachable!(),
+		})
+	}
+
+	// clears are masked by glColorMask too, the last pipeline's masks should not leak into them
+	fn reset_color_write(&mut self) {
+		let all = (true, true, true, true);
+		if self.cache.color_targets.iter().any(|target| target.color_write != all) {
+			unsafe {
+				glColorMask(1, 1, 1, 1);
+			}
+			self.cache.color_targets.iter_mut().for_each(|target| target.color_write = all);
+		}
+	}
+
+	/// Blend state and write mask of the draw buffer `index`, or of all of them for None.
+	/// Only what differs from `cached` is set, None when the current state is unknown.
+	unsafe fn apply_color_target(index: Option<GLuint>, cached: Option<ColorTargetState>, target: ColorTargetState) {
+		let ColorTargetState { color_blend, alpha_blend, color_write } = target;
+
+		if cached.is_none_or(|cached| cached.color_blend != color_blend || cached.alpha_blend != alpha_blend) {
 			if let Some(color_blend) = color_blend {
-				if self.cache.color_blend.is_none() {
-					glEnable(GL_BLEND);
+				if cached.is_none_or(|cached| cached.color_blend.is_none()) {
+					match index {
+						Some(index) => glEnablei(GL_BLEND, index),
+						None => glEnable(GL_BLEND),
+					}
 				}
 
 				let BlendState {
@@ -592,29 +1217,41 @@ impl GlContext {
 					sfactor: src_rgb,
 					dfactor: dst_rgb,
 				} = color_blend;
-
-				if let Some(BlendState {
+				let BlendState {
 					equation: eq_alpha,
 					sfactor: src_alpha,
 					dfactor: dst_alpha,
-				}) = alpha_blend
-				{
-					glBlendFuncSeparate(src_rgb.into(), dst_rgb.into(), src_alpha.into(), dst_alpha.into());
-					glBlendEquationSeparate(eq_rgb.into(), eq_alpha.into());
-				} else {
-					glBlendFunc(src_rgb.into(), dst_rgb.into());
-					glBlendEquationSeparate(eq_rgb.into(), eq_rgb.into());
+				} = alpha_blend.unwrap_or(color_blend);
+
+				match index {
+					Some(index) => {
+						glBlendFuncSeparatei(index, src_rgb.into(), dst_rgb.into(), src_alpha.into(), dst_alpha.into());
+						glBlendEquationSeparatei(index, eq_rgb.into(), eq_alpha.into());
+					}
+					None => {
+						glBlendFuncSeparate(src_rgb.into(), dst_rgb.into(), src_alpha.into(), dst_alpha.into());
+						glBlendEquationSeparate(eq_rgb.into(), eq_alpha.into());
+					}
+				}
+			} else if cached.is_none_or(|cached| cached.color_blend.is_some()) {
+				match index {
+					Some(index) => glDisablei(GL_BLEND, index),
+					None => glDisable(GL_BLEND),
 				}
-			} else if self.cache.color_blend.is_some() {
-				glDisable(GL_BLEND);
 			}
 		}
 
-		self.cache.color_blend = color_blend;
-		self.cache.alpha_blend = alpha_blend;
+		if cached.is_none_or(|cached| cached.color_write != color_write) {
+			let (r, g, b, a) = color_write;
+			match index {
+				Some(index) => glColorMaski(index, r as _, g as _, b as _, a as _),
+				None => glColorMask(r as _, g as _, b as _, a as _),
+			}
+		}
 	}
 
 	fn set_stencil(&mut self, stencil_test: Option<StencilState>) {
+		self.cache.count_state(self.cache.stencil != stencil_test);
 		if self.cache.stencil == stencil_test {
 			return;
 		}
@@ -643,6 +1280,7 @@ impl GlContext {
 	}
 
 	fn set_cull_face(&mut self, cull_face: CullFace) {
+		self.cache.count_state(self.cache.cull_face != cull_face);
 		if self.cache.cull_face == cull_face {
 			return;
 		}
@@ -663,13 +1301,127 @@ impl GlContext {
 		self.cache.cull_face = cull_face;
 	}
 
-	fn set_color_write(&mut self, color_write: ColorMask) {
-		if self.cache.color_write == color_write {
+	fn set_program_point_size(&mut self, program_point_size: bool) {
+		if !self.program_point_size_cap {
+			return;
+		}
+		self.cache.count_state(self.cache.program_point_size != program_point_size);
+		if self.cache.program_point_size == program_point_size {
+			return;
+		}
+
+		unsafe {
+			if program_point_size {
+				glEnable(GL_PROGRAM_POINT_SIZE);
+			} else {
+				glDisable(GL_PROGRAM_POINT_SIZE);
+			}
+		}
+		self.cache.program_point_size = program_point_size;
+	}
+
+	fn set_program(&mut self, program: GLuint) {
+		self.cache.count_state(self.cache.program != program);
+		if self.cache.program != program {
+			unsafe { glUseProgram(program) };
+			self.cache.program = program;
+		}
+	}
+
+	fn set_scissor_test(&mut self, scissor_test: bool) {
+		self.cache.count_state(self.cache.scissor_test != scissor_test);
+		if self.cache.scissor_test == scissor_test {
 			return;
 		}
-		let (r, g, b, a) = color_write;
-		unsafe { glColorMask(r as _, g as _, b as _, a as _) }
-		self.cache.color_write = color_write;
+
+		unsafe {
+			if scissor_test {
+				glEnable(GL_SCISSOR_TEST);
+			} else {
+				glDisable(GL_SCISSOR_TEST);
+			}
+		}
+		self.cache.scissor_test = scissor_test;
+	}
+
+	/// Depth test with the comparison, or no depth test for None
+	fn set_depth_test(&mut self, depth_test: Option<Comparison>) {
+		self.cache.count_state(self.cache.depth_test != depth_test.is_some());
+		if self.cache.depth_test != depth_test.is_some() {
+			unsafe {
+				if depth_test.is_some() {
+					glEnable(GL_DEPTH_TEST);
+				} else {
+					glDisable(GL_DEPTH_TEST);
+				}
+			}
+			self.cache.depth_test = depth_test.is_some();
+		}
+
+		if let Some(depth_func) = depth_test {
+			self.cache.count_state(self.cache.depth_func != depth_func);
+			if self.cache.depth_func != depth_func {
+				unsafe { glDepthFunc(depth_func.into()) };
+				self.cache.depth_func = depth_func;
+			}
+		}
+	}
+
+	// clears are masked by glDepthMask too
+	fn set_depth_write(&mut self, depth_write: bool) {
+		self.cache.count_state(self.cache.depth_write != depth_write);
+		if self.cache.depth_write != depth_write {
+			unsafe { glDepthMask(depth_write as _) };
+			self.cache.depth_write = depth_write;
+		}
+	}
+
+	fn set_polygon_offset(&mut self, polygon_offset: Option<(f32, f32)>) {
+		self.cache.count_state(self.cache.polygon_offset != polygon_offset);
+		if self.cache.polygon_offset == polygon_offset {
+			return;
+		}
+
+		unsafe {
+			if let Some((factor, units)) = polygon_offset {
+				if self.cache.polygon_offset.is_none() {
+					glEnable(GL_POLYGON_OFFSET_FILL);
+				}
+				glPolygonOffset(factor, units);
+			} else {
+				glDisable(GL_POLYGON_OFFSET_FILL);
+			}
+		}
+		self.cache.polygon_offset = polygon_offset;
+	}
+
+	fn set_front_face(&mut self, front_face: FrontFaceOrder) {
+		self.cache.count_state(self.cache.front_face != front_face);
+		if self.cache.front_face != front_face {
+			match front_face {
+				FrontFaceOrder::Clockwise => unsafe { glFrontFace(GL_CW) },
+				FrontFaceOrder::CounterClockwise => unsafe { glFrontFace(GL_CCW) },
+			}
+			self.cache.front_face = front_face;
+		}
+	}
+
+	fn set_viewport(&mut self, viewport: (i32, i32, i32, i32)) {
+		self.cache.count_state(self.cache.viewport != Some(viewport));
+		if self.cache.viewport != Some(viewport) {
+			let (x, y, w, h) = viewport;
+			unsafe { glViewport(x, y, w, h) };
+			self.cache.viewport = Some(viewport);
+		}
+	}
+
+	fn set_scissor(&mut self, scissor: (i32, i32, i32, i32)) {
+		self.cache.count_state(self.cache.scissor != Some(scissor));
+		if self.cache.scissor != Some(scissor) {
+			let (x, y, w, h) = scissor;
+			unsafe { glScissor(x, y, w, h) };
+			self.cache.scissor = Some(scissor);
+		}
 	}
 }
 
@@ -711,39 +1463,98 @@ impl RenderingBackend for GlContext {
 			glsl_support.v130 = true;
 		}
 
-		ContextInfo { gl_version_string, glsl_support }
+		ContextInfo {
+			gl_version_string,
+			glsl_support,
+			uniform_buffer_offset_alignment: self.uniform_buffer_offset_alignment,
+			texture_compression: self.texture_compression,
+			features: self.features.clone(),
+			limits: self.limits,
+			extensions: self.extensions.clone(),
+		}
 	}
 	fn new_shader(&mut self, source: ShaderSource, meta: ShaderMeta) -> Result<ShaderId, ShaderError> {
 		let ShaderSource { vertex, fragment } = source;
-		let shader = load_shader_internal(vertex, fragment, meta)?;
+		// load_shader_internal leaves the program in use, a failed one too
+		let shader = load_shader_internal(vertex, fragment, meta, &[]).inspect_err(|_| self.cache.program = 0)?;
+		self.cache.program = shader.program;
 
 		Ok(ShaderId(self.shaders.add(shader)))
 	}
 
-	fn new_texture(&mut self, access: TextureAccess, source: TextureSource, params: TextureParams) -> TextureId {
-		let texture = Texture::new(self, access, source, params);
+	fn reload_shader(&mut self, id: ShaderId, source: ShaderSource, meta: ShaderMeta) -> Result<(), ShaderError> {
+		let old = self.shaders.get(id.0).unwrap_or_else(|| panic!("reload_shader failed: {:?} is deleted", id)).program;
+		let ShaderSource { vertex, fragment } = source;
+		let shader = load_shader_internal(vertex, fragment, meta, &unsafe { attribute_locations(old) }).inspect_err(|_| self.cache.program = 0)?;
 
-		self.textures.0.push(texture);
-		TextureId(TextureIdInner::Managed(self.textures.0.len() - 1))
+		unsafe { glDeleteProgram(old) };
+		self.cache.program = shader.program;
+		*self.shaders.get_mut(id.0).unwrap() = shader;
+		self.cache.cur_pipeline = None;
+		Ok(())
 	}
 
-	fn delete_texture(&mut self, texture: TextureId) {
+	fn reflect_shader(&mut self, source: ShaderSource) -> Result<ShaderReflection, ShaderError> {
+		let ShaderSource { vertex, fragment } = source;
+		let (meta, attributes, unsupported) = unsafe {
+			let program = link_program(vertex, fragment, &[])?;
+			let reflection = reflect_program(program, self.uniform_buffer_offset_alignment != 0);
+			glDeleteProgram(program);
+			reflection
+		};
+		if !unsupported.is_empty() {
+			return Err(ShaderError::MetaMismatch(unsupported));
+		}
+
+		Ok(ShaderReflection::new(meta, attributes, vertex, fragment))
+	}
+
+	fn try_new_texture(&mut self, access: TextureAccess, source: TextureSource, params: TextureParams) -> Result<TextureId, GraphicsError> {
+		let size = source.size();
+		let texture = Texture::new(self, access, source, params)?;
+		self.cache.count(|stats| stats.bytes_uploaded += size);
+
+		Ok(TextureId(TextureIdInner::Managed(self.textures.0.add(texture))))
+	}
+
+	fn try_delete_texture(&mut self, texture: TextureId) -> Result<(), GraphicsError> {
 		// self.cache.clear_texture_bindings();
 
-		let t = self.textures.get(texture);
+		let t = match texture.0 {
+			TextureIdInner::Raw(_) => self.textures.get(texture),
+			TextureIdInner::Managed(id) => self.textures.0.remove(id).ok_or(GraphicsError::StaleId { kind: "TextureId", id })?,
+		};
 		unsafe {
-			glDeleteTextures(1, &t.raw as *const _);
+			if t.params.sample_count != 0 {
+				glDeleteRenderbuffers(1, &t.raw as *const _);
+			} else {
+				glDeleteTextures(1, &t.raw as *const _);
+			}
 		}
+		Ok(())
 	}
 
-	fn delete_shader(&mut self, program: ShaderId) {
-		unsafe { glDeleteProgram(self.shaders[program.0].program) };
-		self.shaders.remove(program.0);
+	fn try_delete_shader(&mut self, program: ShaderId) -> Result<(), GraphicsError> {
+		let shader = self.shaders.remove(program.0).ok_or(GraphicsError::StaleId { kind: "ShaderId", id: program.0 })?;
+		unsafe { glDeleteProgram(shader.program) };
 		self.cache.cur_pipeline = None;
+		self.cache.program = 0;
+		Ok(())
 	}
 
-	fn delete_pipeline(&mut self, pipeline: Pipeline) {
-		self.pipelines.remove(pipeline.0);
+	fn try_delete_sampler(&mut self, sampler: SamplerId) -> Result<(), GraphicsError> {
+		let (sampler, _) = self.samplers.remove(sampler.0).ok_or(GraphicsError::StaleId { kind: "SamplerId", id: sampler.0 })?;
+		unsafe { glDeleteSamplers(1, &sampler) };
+		self.cache.forget_sampler(sampler);
+		Ok(())
+	}
+
+	fn try_delete_pipeline(&mut self, pipeline: Pipeline) -> Result<(), GraphicsError> {
+		self.pipelines.remove(pipeline.0).ok_or(GraphicsError::StaleId { kind: "Pipeline", id: pipeline.0 })?;
+		if self.cache.cur_pipeline == Some(pipeline) {
+			self.cache.cur_pipeline = None;
+		}
+		Ok(())
 	}
 
 	fn texture_set_wrap(&mut self, texture: TextureId, wrap_x: TextureWrap, wrap_y: TextureWrap) {
@@ -752,26 +1563,46 @@ impl RenderingBackend for GlContext {
 		self.cache.store_texture_binding(0);
 		self.cache.bind_texture(0, t.params.kind.into(), t.raw);
 
-		let wrap_x = match wrap_x {
-			TextureWrap::Repeat => GL_REPEAT,
-			TextureWrap::Mirror => GL_MIRRORED_REPEAT,
-			TextureWrap::Clamp => GL_CLAMP_TO_EDGE,
-		} as i32;
-
-		let wrap_y = match wrap_y {
-			TextureWrap::Repeat => GL_REPEAT,
-			TextureWrap::Mirror => GL_MIRRORED_REPEAT,
-			TextureWrap::Clamp => GL_CLAMP_TO_EDGE,
-		} as i32;
+		let wrap_x = Texture::gl_wrap(wrap_x, self.features.clamp_to_border) as i32;
+		let wrap_y = Texture::gl_wrap(wrap_y, self.features.clamp_to_border) as i32;
 
 		unsafe {
-			glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_S, wrap_x);
-			glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_WRAP_T, wrap_y);
+			glTexParameteri(t.params.kind.into(), GL_TEXTURE_WRAP_S, wrap_x);
+			glTexParameteri(t.params.kind.into(), GL_TEXTURE_WRAP_T, wrap_y);
 		}
 
 		self.cache.restore_texture_binding(0);
 	}
 
+	fn new_sampler(&mut self, params: SamplerParams) -> SamplerId {
+		assert!(self.features.samplers, "Sampler objects are not supported by this context");
+
+		let mut sampler = 0;
+		unsafe {
+			glGenSamplers(1, &mut sampler);
+			let wrap = |wrap| Texture::gl_wrap(wrap, self.features.clamp_to_border) as i32;
+			glSamplerParameteri(sampler, GL_TEXTURE_WRAP_S, wrap(params.wrap_x));
+			glSamplerParameteri(sampler, GL_TEXTURE_WRAP_T, wrap(params.wrap_y));
+			glSamplerParameteri(sampler, GL_TEXTURE_WRAP_R, wrap(params.wrap_z));
+			glSamplerParameteri(sampler, GL_TEXTURE_MIN_FILTER, Texture::gl_filter(params.min_filter, params.mipmap_filter) as i32);
+			glSamplerParameteri(sampler, GL_TEXTURE_MAG_FILTER, Texture::gl_filter(params.mag_filter, MipmapFilterMode::None) as i32);
+			// WebGL2 has no border color
+			#[cfg(not(target_arch = "wasm32"))]
+			if self.features.clamp_to_border {
+				glSamplerParameterfv(sampler, GL_TEXTURE_BORDER_COLOR, params.border_color.as_ptr());
+			}
+			if let Some(comparison) = params.compare {
+				glSamplerParameteri(sampler, GL_TEXTURE_COMPARE_MODE, GL_COMPARE_REF_TO_TEXTURE as i32);
+				glSamplerParameteri(sampler, GL_TEXTURE_COMPARE_FUNC, GLenum::from(comparison) as i32);
+			}
+			if self.features.anisotropic_filtering && params.max_anisotropy > 1 {
+				glSamplerParameterf(sampler, GL_TEXTURE_MAX_ANISOTROPY_EXT, params.max_anisotropy.min(self.limits.max_anisotropy) as f32);
+			}
+		}
+
+		SamplerId(self.samplers.add((sampler, params)))
+	}
+
 	fn texture_set_min_filter(&mut self, texture: TextureId, filter: FilterMode, mipmap_filter: MipmapFilterMode) {
 		let t = self.textures.get(texture);
 		self.cache.store_texture_binding(0);
@@ -802,6 +1633,7 @@ impl RenderingBackend for GlContext {
 	fn texture_resize(&mut self, texture: TextureId, width: u32, height: u32, source: Option<&[u8]>) {
 		let mut t = self.textures.get(texture);
 		t.resize(self, width, height, source);
+		self.cache.count(|stats| stats.bytes_uploaded += source.map_or(0, <[u8]>::len));
 		if let TextureIdInner::Managed(tex_id) = texture.0 {
 			self.textures.0[tex_id].params = t.params;
 		};
@@ -812,8 +1644,86 @@ impl RenderingBackend for GlContext {
 		t.read_pixels(source);
 	}
 
+	fn default_pass_read_pixels(&mut self, bytes: &mut [u8]) {
+		let (screen_width, screen_height) = crate::window::screen_size();
+		assert!(bytes.len() >= (screen_width * screen_height * 4) as usize, "bytes is too small for the default framebuffer");
+
+		unsafe {
+			let mut bound_fbo: i32 = 0;
+			glGetIntegerv(GL_FRAMEBUFFER_BINDING, &mut bound_fbo);
+			glBindFramebuffer(GL_FRAMEBUFFER, self.default_framebuffer);
+
+			glReadPixels(0, 0, screen_width as _, screen_height as _, GL_RGBA, GL_UNSIGNED_BYTE, bytes.as_mut_ptr() as _);
+
+			glBindFramebuffer(GL_FRAMEBUFFER, bound_fbo as _);
+		}
+	}
+
+	fn texture_read_pixels_async(&mut self, texture: TextureId) -> ReadbackId {
+		let t = self.textures.get(texture);
+		let (_, format, pixel_type) = t.params.format.into();
+		let size = t.size(t.params.width, t.params.height);
+
+		t.with_framebuffer(|| unsafe { self.read_pixels_async(t.params.width as _, t.params.height as _, format, pixel_type, size) })
+	}
+
+	fn default_pass_read_pixels_async(&mut self) -> ReadbackId {
+		let (screen_width, screen_height) = crate::window::screen_size();
+
+		unsafe {
+			let mut bound_fbo: i32 = 0;
+			glGetIntegerv(GL_FRAMEBUFFER_BINDING, &mut bound_fbo);
+			glBindFramebuffer(GL_FRAMEBUFFER, self.default_framebuffer);
+
+			let readback = self.read_pixels_async(screen_width as _, screen_height as _, GL_RGBA, GL_UNSIGNED_BYTE, (screen_width * screen_height * 4) as usize);
+
+			glBindFramebuffer(GL_FRAMEBUFFER, bound_fbo as _);
+			readback
+		}
+	}
+
+	fn readback_size(&self, readback: ReadbackId) -> usize {
+		self.readbacks[readback.0].size()
+	}
+
+	fn readback_poll(&mut self, readback: ReadbackId, bytes: &mut [u8]) -> bool {
+		let size = self.readback_size(readback);
+		assert!(bytes.len() >= size, "bytes is too small for the readback");
+
+		if let Readback::Pending { fence, .. } = &self.readbacks[readback.0] {
+			// flush, or the fence may never be signaled if nothing else is submitted.
+			// GL_WAIT_FAILED is treated as done: reading the buffer then waits, but does not lose the pixels
+			if unsafe { glClientWaitSync(fence.0, GL_SYNC_FLUSH_COMMANDS_BIT, 0) } == GL_TIMEOUT_EXPIRED {
+				return false;
+			}
+		}
+
+		match self.readbacks.remove(readback.0).unwrap() {
+			Readback::Pending { buffer, fence, size } => unsafe {
+				glBindBuffer(GL_PIXEL_PACK_BUFFER, buffer);
+				read_pixel_buffer(&mut bytes[..size]);
+				glBindBuffer(GL_PIXEL_PACK_BUFFER, 0);
+				glDeleteBuffers(1, &buffer as *const _);
+				glDeleteSync(fence.0);
+			},
+			Readback::Done(pixels) => bytes[..pixels.len()].copy_from_slice(&pixels),
+		}
+		true
+	}
+
+	fn delete_readback(&mut self, readback: ReadbackId) {
+		let readback = self.readbacks.remove(readback.0).unwrap_or_else(|| panic!("{:?} is deleted", readback));
+		if let Readback::Pending { buffer, fence, .. } = readback {
+			unsafe {
+				glDeleteBuffers(1, &buffer as *const _);
+				glDeleteSync(fence.0);
+			}
+		}
+	}
+
 	fn texture_generate_mipmaps(&mut self, texture: TextureId) {
 		let t = self.textures.get(texture);
+		assert!(!t.params.format.is_compressed(), "Mipmaps of compressed textures can not be generated");
 		self.cache.store_texture_binding(0);
 		self.cache.bind_texture(0, t.params.kind.into(), t.raw);
 		unsafe {
@@ -824,7 +1734,42 @@ impl RenderingBackend for GlContext {
 
 	fn texture_update_part(&mut self, texture: TextureId, x_offset: i32, y_offset: i32, width: i32, height: i32, source: &[u8]) {
 		let t = self.textures.get(texture);
-		t.update_texture_part(self, x_offset, y_offset, width, height, source);
+		t.update_texture_part(self, 0, x_offset, y_offset, width, height, source);
+		self.cache.count(|stats| stats.bytes_uploaded += source.len());
+	}
+
+	fn texture_update_layer_part(&mut self, texture: TextureId, layer: i32, x_offset: i32, y_offset: i32, width: i32, height: i32, source: &[u8]) {
+		let t = self.textures.get(texture);
+		t.update_texture_part(self, layer, x_offset, y_offset, width, height, source);
+		self.cache.count(|stats| stats.bytes_uploaded += source.len());
+	}
+
+	fn copy_texture_region(&mut self, src: TextureId, dst: TextureId, src_rect: (i32, i32, i32, i32), dst_offset: (i32, i32)) {
+		let src = self.textures.get(src);
+		let dst = self.textures.get(dst);
+		let (x, y, width, height) = src_rect;
+		let (x_offset, y_offset) = dst_offset;
+		assert!(src.params.kind == TextureKind::Texture2D && dst.params.kind == TextureKind::Texture2D, "Only Texture2Ds can be copied");
+		assert!(src.params.sample_count == 0, "Multisampled textures can not be copied, use blit_pass to resolve them");
+		assert!(src.params.format == dst.params.format && !src.params.format.is_depth(), "Copying between different or depth formats");
+		assert!(
+			x >= 0 && y >= 0 && x + width <= src.params.width as _ && y + height <= src.params.height as _,
+			"Source region is out of bounds"
+		);
+		assert!(
+			x_offset >= 0 && y_offset >= 0 && x_offset + width <= dst.params.width as _ && y_offset + height <= dst.params.height as _,
+			"Destination region is out of bounds"
+		);
+
+		// glCopyTexSubImage2D reads from the framebuffer with src attached
+		src.with_framebuffer(|| {
+			self.cache.store_texture_binding(0);
+			self.cache.bind_texture(0, GL_TEXTURE_2D, dst.raw);
+			unsafe {
+				glCopyTexSubImage2D(GL_TEXTURE_2D, 0, x_offset, y_offset, x, y, width, height);
+			}
+			self.cache.restore_texture_binding(0);
+		});
 	}
 
 	fn texture_params(&self, texture: TextureId) -> TextureParams {
@@ -837,19 +1782,21 @@ impl RenderingBackend for GlContext {
 		RawId(texture.raw)
 	}
 
-	fn new_render_pass_mrt(&mut self, color_img: &[TextureId], resolve_img: &[TextureId], depth_img: Option<TextureId>) -> RenderPass {
+	fn try_new_render_pass_mrt(&mut self, color_img: &[TextureId], resolve_img: &[TextureId], depth_img: Option<TextureId>) -> Result<RenderPass, GraphicsError> {
 		if color_img.is_empty() && depth_img.is_none() {
 			panic!("Render pass should have at least one target");
 		}
+		let color_textures = color_img.iter().map(|id| self.textures.try_get(*id)).collect::<Result<Vec<_>, _>>()?;
+		let resolve_textures = resolve_img.iter().map(|id| self.textures.try_get(*id)).collect::<Result<Vec<_>, _>>()?;
+		let depth_texture = depth_img.map(|id| self.textures.try_get(id)).transpose()?;
+
 		let mut gl_fb = 0;
 
-		let resolves = unsafe {
+		unsafe {
 			glGenFramebuffers(1, &mut gl_fb);
 			glBindFramebuffer(GL_FRAMEBUFFER, gl_fb);
 
-			for (i, color_img) in color_img.iter().enumerate() {
-				let texture = self.textures.get(*color_img);
-
+			for (i, texture) in color_textures.iter().enumerate() {
 				if texture.params.sample_count != 0 {
 					glFramebufferRenderbuffer(GL_FRAMEBUFFER, GL_COLOR_ATTACHMENT0 + i as u32, GL_RENDERBUFFER, texture.raw);
 				} else {
@@ -857,41 +1804,52 @@ impl RenderingBackend for GlContext {
 				}
 			}
 
-			if let Some(depth_img) = depth_img {
-				let texture = self.textures.get(depth_img);
-
+			if let Some(texture) = depth_texture {
+				let attachment = match texture.params.format {
+					TextureFormat::Depth24Stencil8 => GL_DEPTH_STENCIL_ATTACHMENT,
+					_ => GL_DEPTH_ATTACHMENT,
+				};
 				if texture.params.sample_count != 0 {
-					glFramebufferRenderbuffer(GL_FRAMEBUFFER, GL_DEPTH_ATTACHMENT, GL_RENDERBUFFER, texture.raw);
+					glFramebufferRenderbuffer(GL_FRAMEBUFFER, attachment, GL_RENDERBUFFER, texture.raw);
 				} else {
-					glFramebufferTexture2D(GL_FRAMEBUFFER, GL_DEPTH_ATTACHMENT, GL_TEXTURE_2D, texture.raw, 0);
+					glFramebufferTexture2D(GL_FRAMEBUFFER, attachment, GL_TEXTURE_2D, texture.raw, 0);
 				}
 			}
+		}
 
-			let attachments = (0..color_img.len()).map(|i| GL_COLOR_ATTACHMENT0 + i as u32).collect::<Vec<_>>();
-			if color_img.len() > 1 {
-				glDrawBuffers(color_img.len() as _, attachments.as_ptr() as _);
-			}
+		let attachments = (0..color_img.len()).map(|i| GL_COLOR_ATTACHMENT0 + i as u32).collect::<Vec<_>>();
+		if color_img.len() > 1 {
+			unsafe { glDrawBuffers(color_img.len() as _, attachments.as_ptr() as _) };
+		}
 
-			glBindFramebuffer(GL_FRAMEBUFFER, self.default_framebuffer);
+		let mut framebuffers = vec![gl_fb];
+		let mut status = unsafe { glCheckFramebufferStatus(GL_FRAMEBUFFER) };
+
+		let mut resolves = vec![];
+		for (i, (texture, resolve_img)) in resolve_textures.iter().zip(resolve_img).enumerate() {
+			if status != GL_FRAMEBUFFER_COMPLETE {
+				break;
+			}
+			unsafe {
+				let mut resolve_fb = 0;
+				glGenFramebuffers(1, &mut resolve_fb as *mut _);
+				glBindFramebuffer(GL_FRAMEBUFFER, resolve_fb);
+				framebuffers.push(resolve_fb);
 
-			resolve_img
-				.iter()
-				.enumerate()
-				.map(|(i, resolve_img)| unsafe {
-					let mut resolve_fb = 0;
-					glGenFramebuffers(1, &mut resolve_fb as *mut _);
-					glBindFramebuffer(GL_FRAMEBUFFER, resolve_fb);
+				glFramebufferTexture2D(GL_FRAMEBUFFER, GL_COLOR_ATTACHMENT0 + i as u32, GL_TEXTURE_2D, texture.raw, 0);
+				glDrawBuffers(1, attachments.as_ptr() as _);
+				status = glCheckFramebufferStatus(GL_FRAMEBUFFER);
 
-					let texture = self.textures.get(*resolve_img);
-					glFramebufferTexture2D(GL_FRAMEBUFFER, GL_COLOR_ATTACHMENT0 + i as u32, GL_TEXTURE_2D, texture.raw, 0);
-					debug_assert!(glCheckFramebufferStatus(GL_FRAMEBUFFER) != 0);
+				resolves.push((resolve_fb, *resolve_img));
+			}
+		}
 
-					glDrawBuffers(1, attachments.as_ptr() as _);
+		unsafe { glBindFramebuffer(GL_FRAMEBUFFER, self.default_framebuffer) };
 
-					(resolve_fb, *resolve_img)
-				})
-				.collect()
-		};
+		if status != GL_FRAMEBUFFER_COMPLETE {
+			unsafe { glDeleteFramebuffers(framebuffers.len() as _, framebuffers.as_ptr()) };
+			return Err(GraphicsError::IncompleteFramebuffer(status));
+		}
 
 		let pass = RenderPassInternal {
 			gl_fb,
@@ -900,29 +1858,83 @@ impl RenderingBackend for GlContext {
 			depth_texture: depth_img,
 		};
 
-		RenderPass(self.passes.add(pass))
+		Ok(RenderPass(self.passes.add(pass)))
 	}
 
 	fn render_pass_color_attachments(&self, render_pass: RenderPass) -> &[TextureId] {
 		&self.passes[render_pass.0].color_textures
 	}
 
-	fn delete_render_pass(&mut self, render_pass: RenderPass) {
+	fn try_delete_render_pass(&mut self, render_pass: RenderPass) -> Result<(), GraphicsError> {
 		let pass_id = render_pass.0;
 
-		let render_pass = self.passes.remove(pass_id);
+		let render_pass = self.passes.remove(pass_id).ok_or(GraphicsError::StaleId { kind: "RenderPass", id: pass_id })?;
 
 		unsafe { glDeleteFramebuffers(1, &render_pass.gl_fb as *const _) }
+		for (resolve_fb, _) in &render_pass.resolves {
+			unsafe { glDeleteFramebuffers(1, resolve_fb as *const _) }
+		}
 
+		// the attachments may have been deleted by the user already
 		for color_texture in &render_pass.color_textures {
-			self.delete_texture(*color_texture);
+			let _ = self.try_delete_texture(*color_texture);
 		}
 		if let Some(depth_texture) = render_pass.depth_texture {
-			self.delete_texture(depth_texture);
+			let _ = self.try_delete_texture(depth_texture);
+		}
+		Ok(())
+	}
+
+	fn blit_pass(&mut self, src: Option<RenderPass>, dst: Option<RenderPass>, src_rect: (i32, i32, i32, i32), dst_rect: (i32, i32, i32, i32), filter: FilterMode) {
+		// glBlitFramebuffer fails with GL_INVALID_OPERATION otherwise, the checks of NullContext
+		let color_params = |pass: Option<RenderPass>| {
+			pass.map(|pass| {
+				let texture = *self.passes[pass.0].color_textures.first().expect("Blitting a depth-only render pass");
+				self.textures.get(texture).params
+			})
+		};
+		let src_params = color_params(src);
+		let dst_params = color_params(dst);
+		assert!(dst_params.is_none_or(|params| params.sample_count == 0), "Blitting into a multisampled render pass");
+		if let Some(src_params) = src_params.filter(|params| params.sample_count != 0) {
+			assert_eq!((src_rect.2, src_rect.3), (dst_rect.2, dst_rect.3), "Resolving blits need rectangles of the same size");
+			assert!(dst_params.is_none_or(|params| params.format == src_params.format), "Resolving blits need the same format");
+		}
+
+		let framebuffer = |pass: Option<RenderPass>| pass.map_or(self.default_framebuffer, |pass| self.passes[pass.0].gl_fb);
+		let (src_fb, dst_fb, cur_fb) = (framebuffer(src), framebuffer(dst), framebuffer(self.cache.cur_pass));
+		let (sx, sy, sw, sh) = src_rect;
+		let (dx, dy, dw, dh) = dst_rect;
+		let filter = match filter {
+			FilterMode::Linear => GL_LINEAR,
+			FilterMode::Nearest => GL_NEAREST,
+		};
+
+		unsafe {
+			glBindFramebuffer(GL_READ_FRAMEBUFFER, src_fb);
+			glBindFramebuffer(GL_DRAW_FRAMEBUFFER, dst_fb);
+			if src.is_some() {
+				glReadBuffer(GL_COLOR_ATTACHMENT0);
+			}
+		}
+		// blits are scissored too
+		let scissor_test = self.cache.scissor_test;
+		self.set_scissor_test(false);
+		unsafe {
+			glBlitFramebuffer(sx, sy, sx + sw, sy + sh, dx, dy, dx + dw, dy + dh, GL_COLOR_BUFFER_BIT, filter);
+		}
+		self.set_scissor_test(scissor_test);
+		// back to the framebuffer of the current pass, the default one between passes
+		unsafe {
+			glBindFramebuffer(GL_FRAMEBUFFER, cur_fb);
 		}
 	}
 
-	fn new_pipeline(&mut self, buffer_layout: &[BufferLayout], attributes: &[VertexAttribute], shader: ShaderId, params: PipelineParams) -> Pipeline {
+	fn try_new_pipeline(&mut self, buffer_layout: &[BufferLayout], attributes: &[VertexAttribute], shader: ShaderId, params: PipelineParams) -> Result<Pipeline, GraphicsError> {
+		let program = self.shaders.get(shader.0).ok_or(GraphicsError::StaleId { kind: "ShaderId", id: shader.0 })?.program;
+
+		params.check(&self.limits, &self.features)?;
+
 		#[derive(Clone, Copy, Default)]
 		struct BufferCacheData {
 			stride: i32,
@@ -932,8 +1944,10 @@ impl RenderingBackend for GlContext {
 		let mut buffer_cache: Vec<BufferCacheData> = vec![BufferCacheData::default(); buffer_layout.len()];
 
 		for VertexAttribute { format, buffer_index, .. } in attributes {
-			let layout = buffer_layout.get(*buffer_index).unwrap_or_else(|| panic!());
-			let cache = buffer_cache.get_mut(*buffer_index).unwrap_or_else(|| panic!());
+			let layout = buffer_layout
+				.get(*buffer_index)
+				.ok_or(GraphicsError::InvalidParams("VertexAttribute::buffer_index past the buffer layouts"))?;
+			let cache = &mut buffer_cache[*buffer_index];
 
 			if layout.stride == 0 {
 				cache.stride += format.size_bytes();
@@ -942,7 +1956,6 @@ impl RenderingBackend for GlContext {
 			}
 		}
 
-		let program = self.shaders[shader.0].program;
 		let attributes_len = attributes
 			.iter()
 			.map(|layout| match layout.format {
@@ -957,7 +1970,7 @@ impl RenderingBackend for GlContext {
 			let buffer_data = &mut buffer_cache.get_mut(*buffer_index).unwrap();
 			let layout = buffer_layout.get(*buffer_index).unwrap();
 
-			let cname = CString::new(*name).unwrap();
+			let cname = CString::new(*name).map_err(|_| GraphicsError::InvalidParams("VertexAttribute::name with a nul byte"))?;
 			let attr_loc = unsafe { glGetAttribLocation(program, cname.as_ptr() as *const _) };
 			let attr_loc = if attr_loc == -1 { None } else { Some(attr_loc) };
 			let divisor = if layout.step_func == VertexStep::PerVertex { 0 } else { layout.step_rate };
@@ -998,52 +2011,29 @@ impl RenderingBackend for GlContext {
 
 		let pipeline = PipelineInternal { layout: vertex_layout, shader, params };
 
-		Pipeline(self.pipelines.add(pipeline))
+		Ok(Pipeline(self.pipelines.add(pipeline)))
 	}
 
 	fn apply_pipeline(&mut self, pipeline: &Pipeline) {
-		self.cache.cur_pipeline = Some(*pipeline);
-
-		{
-			let pipeline = &self.pipelines[pipeline.0];
-			let shader = &mut self.shaders[pipeline.shader.0];
-			unsafe {
-				glUseProgram(shader.program);
-			}
-
-			unsafe {
-				glEnable(GL_SCISSOR_TEST);
-			}
-
-			if pipeline.params.depth_write {
-				unsafe {
-					glEnable(GL_DEPTH_TEST);
-					glDepthFunc(pipeline.params.depth_test.into())
-				}
-			} else {
-				unsafe {
-					glDisable(GL_DEPTH_TEST);
-				}
-			}
-
-			match pipeline.params.front_face_order {
-				FrontFaceOrder::Clockwise => unsafe {
-					glFrontFace(GL_CW);
-				},
-				FrontFaceOrder::CounterClockwise => unsafe {
-					glFrontFace(GL_CCW);
-				},
-			}
+		if self.cache.cur_pipeline != Some(*pipeline) {
+			self.cache.count(|stats| stats.pipeline_switches += 1);
 		}
+		self.cache.cur_pipeline = Some(*pipeline);
 
-		self.set_cull_face(self.pipelines[pipeline.0].params.cull_face);
-		self.set_blend(self.pipelines[pipeline.0].params.color_blend, self.pipelines[pipeline.0].params.alpha_blend);
-
-		self.set_stencil(self.pipelines[pipeline.0].params.stencil_test);
-		self.set_color_write(self.pipelines[pipeline.0].params.color_write);
+		let params = self.pipelines[pipeline.0].params;
+		self.set_program(self.shaders[self.pipelines[pipeline.0].shader.0].program);
+		self.set_scissor_test(true);
+		self.set_depth_test(params.depth_write.then_some(params.depth_test));
+		self.set_depth_write(params.depth_write);
+		self.set_polygon_offset(params.depth_write_offset);
+		self.set_front_face(params.front_face_order);
+		self.set_cull_face(params.cull_face);
+		self.set_program_point_size(params.program_point_size);
+		self.set_color_targets(&params);
+		self.set_stencil(params.stencil_test);
 	}
 
-	fn new_buffer(&mut self, type_: BufferType, usage: BufferUsage, data: BufferSource) -> BufferId {
+	fn try_new_buffer(&mut self, type_: BufferType, usage: BufferUsage, data: BufferSource) -> Result<BufferId, GraphicsError> {
 		let gl_target = gl_buffer_target(&type_);
 		let gl_usage = gl_usage(&usage);
 		let (size, element_size) = match &data {
@@ -1053,8 +2043,8 @@ impl RenderingBackend for GlContext {
 
 		let index_type = match type_ {
 			BufferType::IndexBuffer if element_size == 1 || element_size == 2 || element_size == 4 => Some(element_size as u32),
-			BufferType::IndexBuffer => panic!("unsupported index buffer dimension"),
-			BufferType::VertexBuffer => None,
+			BufferType::IndexBuffer => return Err(GraphicsError::UnsupportedIndexSize(element_size)),
+			BufferType::VertexBuffer | BufferType::UniformBuffer => None,
 		};
 
 		let mut gl_buf: u32 = 0;
@@ -1063,10 +2053,17 @@ impl RenderingBackend for GlContext {
 			self.cache.store_buffer_binding(gl_target);
 			self.cache.bind_buffer(gl_target, gl_buf, index_type);
 
+			take_gl_error();
 			glBufferData(gl_target, size as _, std::ptr::null() as *const _, gl_usage);
+			if take_gl_error() == GL_OUT_OF_MEMORY {
+				self.cache.restore_buffer_binding(gl_target);
+				glDeleteBuffers(1, &gl_buf as *const _);
+				return Err(GraphicsError::OutOfMemory);
+			}
 			if let BufferSource::Slice(data) = data {
 				debug_assert!(data.is_slice);
 				glBufferSubData(gl_target, 0, size as _, data.ptr as _);
+				self.cache.count(|stats| stats.bytes_uploaded += size);
 			}
 
 			self.cache.restore_buffer_binding(gl_target);
@@ -1079,7 +2076,7 @@ impl RenderingBackend for GlContext {
 			index_type,
 		};
 
-		BufferId(self.buffers.add(buffer))
+		Ok(BufferId(self.buffers.add(buffer)))
 	}
 
 	fn buffer_update(&mut self, buffer: BufferId, data: BufferSource) {
@@ -1090,9 +2087,13 @@ impl RenderingBackend for GlContext {
 		debug_assert!(data.is_slice);
 		let buffer = &self.buffers[buffer.0];
 
-		if matches!(buffer.buffer_type, BufferType::IndexBuffer) {
-			assert!(buffer.index_type.is_some());
-			assert!(data.element_size as u32 == buffer.index_type.unwrap());
+		if let Some(index_type) = buffer.index_type {
+			assert!(
+				data.element_size as u32 == index_type,
+				"Index buffer of {} byte indices updated with {} byte elements",
+				index_type,
+				data.element_size
+			);
 		};
 
 		assert!(data.size <= buffer.size);
@@ -1101,6 +2102,7 @@ impl RenderingBackend for GlContext {
 		self.cache.store_buffer_binding(gl_target);
 		self.cache.bind_buffer(gl_target, buffer.gl_buf, buffer.index_type);
 		unsafe { glBufferSubData(gl_target, 0, data.size as _, data.ptr as _) };
+		self.cache.count(|stats| stats.bytes_uploaded += data.size);
 		self.cache.restore_buffer_binding(gl_target);
 	}
 
@@ -1109,54 +2111,60 @@ impl RenderingBackend for GlContext {
 		self.buffers[buffer.0].size
 	}
 
-	/// Delete GPU buffer, leaving handle unmodified.
-	///
-	/// More high-level code on top of miniquad probably is going to call this in Drop implementation of some
-	/// more RAII buffer object.
-	///
-	/// There is no protection against using deleted textures later. However its not an UB in OpenGl and thats why
-	/// this function is not marked as unsafe
-	fn delete_buffer(&mut self, buffer: BufferId) {
-		unsafe { glDeleteBuffers(1, &self.buffers[buffer.0].gl_buf as *const _) }
+	fn try_delete_buffer(&mut self, buffer: BufferId) -> Result<(), GraphicsError> {
+		let buffer = self.buffers.remove(buffer.0).ok_or(GraphicsError::StaleId { kind: "BufferId", id: buffer.0 })?;
+		unsafe { glDeleteBuffers(1, &buffer.gl_buf as *const _) }
 		self.cache.clear_buffer_bindings();
 		self.cache.clear_vertex_attributes();
-		self.buffers.remove(buffer.0);
+		Ok(())
 	}
 
 	/// Set a new viewport rectangle.
 	/// Should be applied after begin_pass.
 	fn apply_viewport(&mut self, x: i32, y: i32, w: i32, h: i32) {
-		unsafe {
-			glViewport(x, y, w, h);
-		}
+		self.set_viewport((x, y, w, h));
 	}
 
 	/// Set a new scissor rectangle.
 	/// Should be applied after begin_pass.
 	fn apply_scissor_rect(&mut self, x: i32, y: i32, w: i32, h: i32) {
-		unsafe {
-			glScissor(x, y, w, h);
-		}
+		self.set_scissor((x, y, w, h));
 	}
 
-	fn apply_bindings_from_slice(&mut self, vertex_buffers: &[BufferId], index_buffer: BufferId, textures: &[TextureId]) {
-		let pip = &self.pipelines[self.cache.cur_pipeline.unwrap().0];
+	fn apply_bindings_from_slice(&mut self, vertex_buffers: &[BufferId], index_buffer: Option<BufferId>, textures: &[TextureId], samplers: &[Option<SamplerId>]) {
+		let pip = &self.pipelines[self.cache.cur_pipeline.expect("apply_bindings without applied pipeline").0];
 		let shader = &self.shaders[pip.shader.0];
 
 		for (n, shader_image) in shader.images.iter().enumerate() {
 			let bindings_image = textures.get(n).unwrap_or_else(|| panic!("Image count in bindings and shader did not match!"));
 			if let Some(gl_loc) = shader_image.gl_loc {
 				let texture = self.textures.get(*bindings_image);
+				let (sampler, compare) = match samplers.get(n).copied().flatten() {
+					Some(sampler) => (self.samplers[sampler.0].0, self.samplers[sampler.0].1.compare),
+					None => (0, texture.params.compare),
+				};
+				// WebGL fails the draw call, GL returns garbage
+				assert!(
+					compare.is_some() == (shader_image.image_type == ImageType::Shadow),
+					"Image {} is {:?}, the bound texture and sampler should {}compare",
+					n,
+					shader_image.image_type,
+					if compare.is_some() { "not " } else { "" }
+				);
 				unsafe {
 					self.cache.bind_texture(n, texture.params.kind.into(), texture.raw);
+					self.cache.bind_sampler(n, sampler);
 					glUniform1i(gl_loc, n as i32);
 				}
 			}
 		}
 
-		self.cache.bind_buffer(GL_ELEMENT_ARRAY_BUFFER, self.buffers[index_buffer.0].gl_buf, self.buffers[index_buffer.0].index_type);
+		match index_buffer {
+			Some(index_buffer) => self.cache.bind_buffer(GL_ELEMENT_ARRAY_BUFFER, self.buffers[index_buffer.0].gl_buf, self.buffers[index_buffer.0].index_type),
+			None => self.cache.bind_buffer(GL_ELEMENT_ARRAY_BUFFER, 0, None),
+		}
 
-		let pip = &self.pipelines[self.cache.cur_pipeline.unwrap().0];
+		let pip = &self.pipelines[self.cache.cur_pipeline.expect("apply_bindings without applied pipeline").0];
 
 		for attr_index in 0..MAX_VERTEX_ATTRIBUTES {
 			let cached_attr = &mut self.cache.attributes[attr_index];
@@ -1195,7 +2203,7 @@ impl RenderingBackend for GlContext {
 	}
 
 	fn apply_uniforms_from_bytes(&mut self, uniform_ptr: *const u8, size: usize) {
-		let pip = &self.pipelines[self.cache.cur_pipeline.unwrap().0];
+		let pip = &self.pipelines[self.cache.cur_pipeline.expect("apply_uniforms without applied pipeline").0];
 		let shader = &self.shaders[pip.shader.0];
 
 		let mut offset = 0;
@@ -1245,10 +2253,36 @@ impl RenderingBackend for GlContext {
 		}
 	}
 
+	fn apply_uniform_buffer(&mut self, binding: u32, buffer: BufferId, offset: usize, size: usize) {
+		assert!(self.uniform_buffer_offset_alignment != 0, "Uniform buffers are not supported by this context");
+
+		let pip = &self.pipelines[self.cache.cur_pipeline.expect("apply_uniform_buffer without applied pipeline").0];
+		let shader = &self.shaders[pip.shader.0];
+		let gl_buf = {
+			let b = &self.buffers[buffer.0];
+			assert!(b.buffer_type == BufferType::UniformBuffer, "{:?} is not a uniform buffer", buffer);
+			assert!(offset + size <= b.size, "Uniform buffer range {}..{} is outside of the {} bytes buffer", offset, offset + size, b.size);
+			b.gl_buf
+		};
+		assert!(
+			offset.is_multiple_of(self.uniform_buffer_offset_alignment),
+			"Uniform buffer offset {} is not a multiple of {}",
+			offset,
+			self.uniform_buffer_offset_alignment
+		);
+		if let Some(block) = shader.uniform_blocks.iter().find(|block| block.binding == binding) {
+			assert!(size >= block.size, "Uniform block at binding {} takes {} bytes, but only {} bytes are bound", binding, block.size, size);
+		}
+
+		unsafe { glBindBufferRange(GL_UNIFORM_BUFFER, binding, gl_buf, offset as _, size as _) };
+		self.cache.uniform_buffer = gl_buf;
+	}
+
 	fn clear(&mut self, color: Option<(f32, f32, f32, f32)>, depth: Option<f32>, stencil: Option<i32>) {
 		let mut bits = 0;
 		if let Some((r, g, b, a)) = color {
 			bits |= GL_COLOR_BUFFER_BIT;
+			self.reset_color_write();
 			unsafe {
 				glClearColor(r, g, b, a);
 			}
@@ -1256,6 +2290,7 @@ impl RenderingBackend for GlContext {
 
 		if let Some(v) = depth {
 			bits |= GL_DEPTH_BUFFER_BIT;
+			self.set_depth_write(true);
 			unsafe {
 				glClearDepthf(v);
 			}
@@ -1281,6 +2316,7 @@ impl RenderingBackend for GlContext {
 
 	fn begin_pass(&mut self, pass: Option<RenderPass>, action: PassAction) {
 		self.cache.cur_pass = pass;
+		self.cache.cur_pass_store = Default::default();
 
 		let (framebuffer, w, h) = match pass {
 			None => {
@@ -1298,26 +2334,39 @@ impl RenderingBackend for GlContext {
 		};
 		unsafe {
 			glBindFramebuffer(GL_FRAMEBUFFER, framebuffer);
-			glViewport(0, 0, w, h);
-			glScissor(0, 0, w, h);
 		}
+		self.set_viewport((0, 0, w, h));
+		self.set_scissor((0, 0, w, h));
 		match action {
 			PassAction::Nothing => {}
 			PassAction::Clear { color, depth, stencil } => {
 				self.clear(color, depth, stencil);
 			}
+			PassAction::ClearAttachments { colors, depth, stencil } => {
+				self.clear(None, depth, stencil);
+				self.clear_attachments(&colors);
+			}
+			PassAction::Attachments { colors, depth, stencil } => {
+				self.clear(None, depth.load.clear_value(), stencil.load.clear_value());
+				self.clear_attachments(&colors.iter().map(|color| color.load.clear_value()).collect::<Vec<_>>());
+				self.invalidate_attachments(colors.iter().map(|color| color.load == LoadOp::DontCare), depth.load == LoadOp::DontCare, stencil.load == LoadOp::DontCare);
+				self.cache.cur_pass_store = (colors.iter().map(|color| color.store).collect(), depth.store, stencil.store);
+			}
 		}
 	}
 
 	fn end_render_pass(&mut self) {
 		unsafe {
-			if let Some(pass) = self.cache.cur_pass.take() {
+			if let Some(pass) = self.cache.cur_pass {
 				let pass = &self.passes[pass.0];
 
 				if !pass.resolves.is_empty() {
 					glBindFramebuffer(GL_READ_FRAMEBUFFER, pass.gl_fb);
 
 					for (i, (resolve_fb, resolve_img)) in pass.resolves.iter().enumerate() {
+						if self.cache.cur_pass_store.0.get(i) == Some(&StoreOp::StoreUnresolved) {
+							continue;
+						}
 						let texture = self.textures.get(*resolve_img);
 						let w = texture.params.width;
 						let h = texture.params.height;
@@ -1326,9 +2375,15 @@ impl RenderingBackend for GlContext {
 						glReadBuffer(GL_COLOR_ATTACHMENT0 + i as u32);
 						glBlitFramebuffer(0, 0, w as _, h as _, 0, 0, w as _, h as _, GL_COLOR_BUFFER_BIT, GL_NEAREST);
 					}
+					glBindFramebuffer(GL_FRAMEBUFFER, pass.gl_fb);
 				}
 			}
 
+			// the pass framebuffer is still bound, after the resolves as well
+			let (colors, depth, stencil) = std::mem::take(&mut self.cache.cur_pass_store);
+			self.invalidate_attachments(colors.iter().map(|store| *store == StoreOp::Discard), depth == StoreOp::Discard, stencil == StoreOp::Discard);
+			self.cache.cur_pass = None;
+
 			glBindFramebuffer(GL_FRAMEBUFFER, self.default_framebuffer);
 			self.cache.bind_buffer(GL_ARRAY_BUFFER, 0, None);
 			self.cache.bind_buffer(GL_ELEMENT_ARRAY_BUFFER, 0, None);
@@ -1338,28 +2393,62 @@ impl RenderingBackend for GlContext {
 	fn commit_frame(&mut self) {
 		self.cache.clear_buffer_bindings();
 		self.cache.clear_texture_bindings();
+		self.frame_stats = self.cache.stats.take();
+	}
+
+	fn frame_stats(&self) -> FrameStats {
+		self.frame_stats
 	}
 
 	fn draw(&self, base_element: i32, num_elements: i32, num_instances: i32) {
-		assert!(self.cache.cur_pipeline.is_some(), "Drawing without bound pipeline");
+		self.cache.count(|stats| stats.draw_calls += 1);
+		let primitive_type = self.primitive_type();
+		let Some((index_type, index_size)) = self.index_type() else {
+			unsafe { glDrawArraysInstanced(primitive_type, base_element, num_elements, num_instances) };
+			return;
+		};
 
-		let pip = &self.pipelines[self.cache.cur_pipeline.unwrap().0];
-		let primitive_type = pip.params.primitive_type.into();
-		let index_type = self.cache.index_type.expect("Unset index buffer type");
+		unsafe {
+			glDrawElementsInstanced(primitive_type, num_elements, index_type, (index_size * base_element) as *mut _, num_instances);
+		}
+	}
+
+	fn draw_base_vertex(&self, base_element: i32, num_elements: i32, num_instances: i32, base_vertex: i32) {
+		assert!(self.features.base_vertex, "draw_base_vertex is not supported by this context, see Features::base_vertex");
+		let primitive_type = self.primitive_type();
+		let (index_type, index_size) = self.index_type().expect("draw_base_vertex without an index buffer");
+		self.cache.count(|stats| stats.draw_calls += 1);
 
 		unsafe {
-			glDrawElementsInstanced(
-				primitive_type,
-				num_elements,
-				match index_type {
-					1 => GL_UNSIGNED_BYTE,
-					2 => GL_UNSIGNED_SHORT,
-					4 => GL_UNSIGNED_INT,
-					_ => panic!("Unsupported index buffer type!"),
-				},
-				(index_type as i32 * base_element) as *mut _,
-				num_instances,
-			);
+			glDrawElementsInstancedBaseVertex(primitive_type, num_elements, index_type, (index_size * base_element) as *mut _, num_instances, base_vertex);
+		}
+	}
+
+	fn draw_arrays(&self, first_vertex: i32, num_vertices: i32, num_instances: i32) {
+		self.cache.count(|stats| stats.draw_calls += 1);
+		let primitive_type = self.primitive_type();
+
+		unsafe {
+			glDrawArraysInstanced(primitive_type, first_vertex, num_vertices, num_instances);
+		}
+	}
+
+	fn draw_multi(&self, ranges: &[DrawRange]) {
+		let index_type = self.index_type().filter(|_| self.multi_draw);
+		let Some((index_type, index_size)) = index_type else {
+			for range in ranges {
+				self.draw(range.base_element, range.num_elements, 1);
+			}
+			return;
+		};
+
+		self.cache.count(|stats| stats.draw_calls += 1);
+		let primitive_type = self.primitive_type();
+		let counts: Vec<GLsizei> = ranges.iter().map(|range| range.num_elements).collect();
+		let offsets: Vec<*const std::ffi::c_void> = ranges.iter().map(|range| (index_size * range.base_element) as *const _).collect();
+
+		unsafe {
+			glMultiDrawElements(primitive_type, counts.as_ptr(), index_type, offsets.as_ptr(), ranges.len() as _);
 		}
 	}
 }
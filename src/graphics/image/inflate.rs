@@ -0,0 +1,271 @@
+//! zlib (RFC 1950) and DEFLATE (RFC 1951) decompression, as much as PNG needs.
+
+use super::ImageError;
+
+const LENGTH_BASE: [u16; 29] = [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+const DISTANCE_BASE: [u16; 30] = [
+	1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+/// The best DEFLATE can do, 258 bytes long matches coded with 2 bits
+const MAX_RATIO: usize = 1032;
+/// Order of the code length code lengths in a dynamic block header
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+struct BitReader<'a> {
+	bytes: &'a [u8],
+	position: usize,
+	bits: u32,
+	bit_count: u32,
+}
+
+impl BitReader<'_> {
+	fn read(&mut self, count: u32) -> Result<u32, ImageError> {
+		while self.bit_count < count {
+			let byte = *self.bytes.get(self.position).ok_or(ImageError::UnexpectedEnd)?;
+			self.bits |= (byte as u32) << self.bit_count;
+			self.position += 1;
+			self.bit_count += 8;
+		}
+		let value = self.bits & ((1 << count) - 1);
+		self.bits >>= count;
+		self.bit_count -= count;
+		Ok(value)
+	}
+
+	/// Skip to the next byte boundary, the buffered bits are always from the current byte
+	fn align(&mut self) {
+		self.bits = 0;
+		self.bit_count = 0;
+	}
+
+	fn read_bytes(&mut self, count: usize) -> Result<&[u8], ImageError> {
+		let bytes = self.bytes.get(self.position..self.position + count).ok_or(ImageError::UnexpectedEnd)?;
+		self.position += count;
+		Ok(bytes)
+	}
+}
+
+/// Canonical Huffman code, decoded one bit at a time.
+struct Huffman {
+	/// Number of codes of each length
+	counts: [u16; 16],
+	/// Symbols ordered by code
+	symbols: Vec<u16>,
+}
+
+impl Huffman {
+	fn new(lengths: &[u8]) -> Result<Huffman, ImageError> {
+		let mut counts = [0u16; 16];
+		for &length in lengths {
+			counts[length as usize] += 1;
+		}
+		counts[0] = 0;
+
+		let mut left = 1i32;
+		for &count in &counts[1..] {
+			left = left * 2 - count as i32;
+			if left < 0 {
+				return Err(ImageError::InvalidData("over-subscribed Huffman code"));
+			}
+		}
+
+		let mut offsets = [0u16; 16];
+		for length in 1..15 {
+			offsets[length + 1] = offsets[length] + counts[length];
+		}
+		let mut symbols = vec![0; lengths.len()];
+		for (symbol, &length) in lengths.iter().enumerate() {
+			if length != 0 {
+				symbols[offsets[length as usize] as usize] = symbol as u16;
+				offsets[length as usize] += 1;
+			}
+		}
+
+		Ok(Huffman { counts, symbols })
+	}
+
+	fn decode(&self, reader: &mut BitReader) -> Result<u16, ImageError> {
+		let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+		for &count in &self.counts[1..] {
+			code |= reader.read(1)? as i32;
+			let count = count as i32;
+			if code - first < count {
+				return Ok(self.symbols[(index + code - first) as usize]);
+			}
+			index += count;
+			first = (first + count) << 1;
+			code <<= 1;
+		}
+		Err(ImageError::InvalidData("invalid Huffman code"))
+	}
+}
+
+fn fixed_codes() -> (Huffman, Huffman) {
+	let mut lengths = [0u8; 288];
+	lengths[..144].fill(8);
+	lengths[144..256].fill(9);
+	lengths[256..280].fill(7);
+	lengths[280..].fill(8);
+	(Huffman::new(&lengths).unwrap(), Huffman::new(&[5; 30]).unwrap())
+}
+
+fn dynamic_codes(reader: &mut BitReader) -> Result<(Huffman, Huffman), ImageError> {
+	let literals = reader.read(5)? as usize + 257;
+	let distances = reader.read(5)? as usize + 1;
+	let code_lengths = reader.read(4)? as usize + 4;
+
+	let mut lengths = [0u8; 19];
+	for &index in &CODE_LENGTH_ORDER[..code_lengths] {
+		lengths[index] = reader.read(3)? as u8;
+	}
+	let code_length_code = Huffman::new(&lengths)?;
+
+	let mut lengths = vec![0u8; literals + distances];
+	let mut i = 0;
+	while i < lengths.len() {
+		let symbol = code_length_code.decode(reader)?;
+		let (length, repeat) = match symbol {
+			0..=15 => (symbol as u8, 1),
+			16 => {
+				let previous = *lengths[..i].last().ok_or(ImageError::InvalidData("repeated code length without a previous one"))?;
+				(previous, 3 + reader.read(2)? as usize)
+			}
+			17 => (0, 3 + reader.read(3)? as usize),
+			_ => (0, 11 + reader.read(7)? as usize),
+		};
+		let run = lengths.get_mut(i..i + repeat).ok_or(ImageError::InvalidData("too many code lengths"))?;
+		run.fill(length);
+		i += repeat;
+	}
+	if lengths[256] == 0 {
+		return Err(ImageError::InvalidData("no end of block code"));
+	}
+
+	Ok((Huffman::new(&lengths[..literals])?, Huffman::new(&lengths[literals..])?))
+}
+
+fn inflate_block(reader: &mut BitReader, out: &mut Vec<u8>, size: usize, literals: &Huffman, distances: &Huffman) -> Result<(), ImageError> {
+	loop {
+		if out.len() > size {
+			return Err(ImageError::InvalidData("more data than expected"));
+		}
+		let symbol = literals.decode(reader)? as usize;
+		match symbol {
+			0..=255 => out.push(symbol as u8),
+			256 => return Ok(()),
+			_ => {
+				let index = symbol - 257;
+				if index >= LENGTH_BASE.len() {
+					return Err(ImageError::InvalidData("invalid length symbol"));
+				}
+				let length = LENGTH_BASE[index] as usize + reader.read(LENGTH_EXTRA[index] as u32)? as usize;
+
+				let index = distances.decode(reader)? as usize;
+				if index >= DISTANCE_BASE.len() {
+					return Err(ImageError::InvalidData("invalid distance symbol"));
+				}
+				let distance = DISTANCE_BASE[index] as usize + reader.read(DISTANCE_EXTRA[index] as u32)? as usize;
+				if distance > out.len() {
+					return Err(ImageError::InvalidData("distance is too far back"));
+				}
+
+				// the copy may overlap the bytes it produces
+				let start = out.len() - distance;
+				for i in 0..length {
+					out.push(out[start + i]);
+				}
+			}
+		}
+	}
+}
+
+/// Decompress a zlib stream of at most `size` bytes.
+/// The Adler-32 checksum is not verified.
+///
+/// ```
+/// # use miniquad_wasm_bindgen::graphics::*;
+/// # // 16x16 8 bit grayscale PNG of the given zlib stream, the CRCs are not verified
+/// # fn png(zlib: &[u8]) -> Vec<u8> {
+/// #     let chunk = |kind: &[u8], data: &[u8]| [&(data.len() as u32).to_be_bytes()[..], kind, data, &[0; 4]].concat();
+/// #     [&b"\x89PNG\r\n\x1a\n"[..], &chunk(b"IHDR", &[0, 0, 0, 16, 0, 0, 0, 16, 8, 0, 0, 0, 0]), &chunk(b"IDAT", zlib), &chunk(b"IEND", &[])].concat()
+/// # }
+/// // rows of a filter type byte and 16 gray levels
+/// let rows: Vec<u8> = (0..16).flat_map(|y| [0].into_iter().chain((0..16).map(move |x| (x * x + y) % 5 * 50))).collect();
+/// let stored = [&[0x78, 0x01, 0x01, 16, 1, !16, !1][..], &rows].concat();
+/// // zlib level 9, with the Z_FIXED strategy and the default one
+/// let fixed = [
+///     0x78, 0x01, 0x63, 0x60, 0x30, 0x3a, 0x71, 0xc2, 0x08, 0x89, 0x60, 0x30, 0x4a, 0x61, 0x60, 0x48, 0x41, 0x22, 0x18, 0x52, 0xa6, 0x19, 0x19, 0x4d, 0x43, 0x22, 0x18, 0xa6, 0x9d, 0x48,
+///     0x49, 0x39, 0x81, 0x44, 0x30, 0x9c, 0x60, 0x98, 0x86, 0x4a, 0x0c, 0x5d, 0x43, 0x01, 0x92, 0x4b, 0x63, 0x9d,
+/// ];
+/// let dynamic = [
+///     0x78, 0xda, 0xd5, 0xcc, 0x31, 0x01, 0x00, 0x30, 0x10, 0x02, 0xb1, 0xd3, 0x84, 0x26, 0x34, 0xa1, 0x09, 0x59, 0x5d, 0x79, 0x09, 0x5d, 0x32, 0x06, 0xd4, 0x6a, 0x40, 0x06, 0x0f, 0x38,
+///     0x52, 0x06, 0x52, 0xbb, 0x03, 0x25, 0x97, 0x7f, 0xd3, 0x07, 0x92, 0x4b, 0x63, 0x9d,
+/// ];
+///
+/// let pixels: Vec<u8> = rows.chunks(17).flat_map(|row| row[1..].iter().flat_map(|&gray| [gray, gray, gray, 255])).collect();
+/// for zlib in [&stored[..], &fixed, &dynamic] {
+///     assert_eq!(decode_image(&png(zlib)).unwrap().bytes, pixels);
+/// }
+///
+/// assert_eq!(decode_image(&png(&dynamic[..30])).unwrap_err(), ImageError::UnexpectedEnd);
+/// assert_eq!(decode_image(&png(&stored[..100])).unwrap_err(), ImageError::UnexpectedEnd);
+/// assert_eq!(decode_image(&png(&[0x78, 0x00, 0x01])).unwrap_err(), ImageError::InvalidData("invalid zlib header"));
+/// assert_eq!(decode_image(&png(&[0x78, 0x01, 0x01, 16, 1, 16, 1])).unwrap_err(), ImageError::InvalidData("stored block length mismatch"));
+/// assert_eq!(decode_image(&png(&[0x78, 0x01, 0x07])).unwrap_err(), ImageError::InvalidData("invalid block type"));
+/// // fixed block starting with a match
+/// assert_eq!(decode_image(&png(&[0x78, 0x01, 0x03, 0x02, 0x00, 0x00])).unwrap_err(), ImageError::InvalidData("distance is too far back"));
+/// // two copies of the rows is more than the image holds
+/// let stored = [&[0x78, 0x01, 0x01, 32, 2, !32, !2][..], &rows, &rows].concat();
+/// assert_eq!(decode_image(&png(&stored)).unwrap_err(), ImageError::InvalidData("more data than expected"));
+/// ```
+pub(super) fn zlib_decompress(bytes: &[u8], size: usize) -> Result<Vec<u8>, ImageError> {
+	let [method, flags, ..] = *bytes else {
+		return Err(ImageError::UnexpectedEnd);
+	};
+	if method & 0x0F != 8 || !(method as u16 * 256 + flags as u16).is_multiple_of(31) {
+		return Err(ImageError::InvalidData("invalid zlib header"));
+	}
+	if flags & 0x20 != 0 {
+		return Err(ImageError::Unsupported("zlib preset dictionary"));
+	}
+
+	let mut reader = BitReader {
+		bytes: &bytes[2..],
+		position: 0,
+		bits: 0,
+		bit_count: 0,
+	};
+	// the size comes from the image header, do not trust it more than the data
+	let mut out = Vec::with_capacity(size.min(bytes.len().saturating_mul(MAX_RATIO)));
+	loop {
+		let last = reader.read(1)? == 1;
+		match reader.read(2)? {
+			0 => {
+				reader.align();
+				let header = reader.read_bytes(4)?;
+				let length = u16::from_le_bytes([header[0], header[1]]);
+				if length != !u16::from_le_bytes([header[2], header[3]]) {
+					return Err(ImageError::InvalidData("stored block length mismatch"));
+				}
+				out.extend_from_slice(reader.read_bytes(length as usize)?);
+				if out.len() > size {
+					return Err(ImageError::InvalidData("more data than expected"));
+				}
+			}
+			1 => {
+				let (literals, distances) = fixed_codes();
+				inflate_block(&mut reader, &mut out, size, &literals, &distances)?;
+			}
+			2 => {
+				let (literals, distances) = dynamic_codes(&mut reader)?;
+				inflate_block(&mut reader, &mut out, size, &literals, &distances)?;
+			}
+			_ => return Err(ImageError::InvalidData("invalid block type")),
+		}
+		if last {
+			return Ok(out);
+		}
+	}
+}
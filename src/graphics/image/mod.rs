@@ -0,0 +1,66 @@
+//! Minimal PNG and QOI decoders, enough to turn an asset loaded with `fs::load_file` into a texture
+//! without pulling an image crate. Enabled with the "image-decode" feature.
+
+use super::*;
+
+mod inflate;
+mod png;
+mod qoi;
+
+/// Error returned by `decode_image`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImageError {
+	/// Neither a PNG nor a QOI image
+	UnknownFormat,
+	UnexpectedEnd,
+	/// Corrupted data or an invalid header field
+	InvalidData(&'static str),
+	/// A valid image using a feature the decoder does not implement
+	Unsupported(&'static str),
+}
+
+impl Display for ImageError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{:?}", self) // Display the same way as Debug
+	}
+}
+
+impl Error for ImageError {}
+
+/// RGBA8 pixels of a decoded image, rows from top to bottom.
+#[derive(Debug, Clone)]
+pub struct DecodedImage {
+	/// `Texture2D` of the image size, `TextureFormat::RGBA8` and default filtering
+	pub params: TextureParams,
+	pub bytes: Vec<u8>,
+}
+
+/// Decode a PNG or a QOI image, the format is detected from the first bytes.
+///
+/// 16 bit images are truncated to 8 bits, grayscale and palette images are expanded to RGBA.
+/// Checksums are not verified.
+///
+/// ```
+/// # use miniquad_wasm_bindgen::graphics::*;
+/// // 2x1 QOI image: header, a red RGB pixel, a run of one, end marker
+/// let qoi = [b"qoif", &[0, 0, 0, 2, 0, 0, 0, 1, 3, 0][..], &[0xFE, 255, 0, 0, 0xC0], &[0, 0, 0, 0, 0, 0, 0, 1]].concat();
+///
+/// let image = decode_image(&qoi).unwrap();
+/// assert_eq!((image.params.width, image.params.height, image.params.format), (2, 1, TextureFormat::RGBA8));
+/// assert_eq!(image.bytes, [255, 0, 0, 255, 255, 0, 0, 255]);
+///
+/// assert_eq!(decode_image(b"GIF89a").unwrap_err(), ImageError::UnknownFormat);
+/// assert_eq!(decode_image(&qoi[..16]).unwrap_err(), ImageError::UnexpectedEnd);
+/// ```
+pub fn decode_image(bytes: &[u8]) -> Result<DecodedImage, ImageError> {
+	let (width, height, bytes) = if bytes.starts_with(png::SIGNATURE) {
+		png::decode(bytes)?
+	} else if bytes.starts_with(qoi::MAGIC) {
+		qoi::decode(bytes)?
+	} else {
+		return Err(ImageError::UnknownFormat);
+	};
+
+	let params = TextureParams { width, height, ..Default::default() };
+	Ok(DecodedImage { params, bytes })
+}
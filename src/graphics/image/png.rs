@@ -0,0 +1,289 @@
+//! PNG decoding: all the color types and bit depths, interlaced or not, converted to RGBA8.
+
+use super::{inflate::zlib_decompress, ImageError};
+
+pub(super) const SIGNATURE: &[u8; 8] = b"\x89PNG\r\n\x1a\n";
+
+/// (x, y) of the first pixel and (x, y) step of each Adam7 pass
+const ADAM7: [(usize, usize, usize, usize); 7] = [(0, 0, 8, 8), (4, 0, 8, 8), (0, 4, 4, 8), (2, 0, 4, 4), (0, 2, 2, 4), (1, 0, 2, 2), (0, 1, 1, 2)];
+
+struct Header {
+	width: usize,
+	height: usize,
+	bit_depth: u8,
+	color_type: u8,
+	interlaced: bool,
+}
+
+impl Header {
+	fn channels(&self) -> usize {
+		match self.color_type {
+			2 => 3,
+			4 => 2,
+			6 => 4,
+			// grayscale and palette
+			_ => 1,
+		}
+	}
+
+	fn bits_per_pixel(&self) -> usize {
+		self.channels() * self.bit_depth as usize
+	}
+
+	/// Filtered bytes of a `width` pixels wide row, without the filter type byte
+	fn row_size(&self, width: usize) -> usize {
+		(width * self.bits_per_pixel()).div_ceil(8)
+	}
+}
+
+/// Palette and transparency of the image, applied while converting the samples to RGBA8
+struct Colors {
+	palette: Vec<[u8; 4]>,
+	/// tRNS key of grayscale and RGB images, compared with the samples before scaling
+	transparent: Option<[u16; 3]>,
+}
+
+fn be_u32(bytes: &[u8]) -> u32 {
+	u32::from_be_bytes(bytes[..4].try_into().unwrap())
+}
+
+/// ```
+/// # use miniquad_wasm_bindgen::graphics::*;
+/// # // PNG with the given IHDR fields, chunks and filtered rows stored in a single IDAT, the CRCs are not verified
+/// # fn png((width, height): (u32, u32), [depth, color, interlace]: [u8; 3], chunks: &[(&[u8], &[u8])], rows: &[u8]) -> Vec<u8> {
+/// #     let chunk = |kind: &[u8], data: &[u8]| [&(data.len() as u32).to_be_bytes()[..], kind, data, &[0; 4]].concat();
+/// #     let size = (rows.len() as u16).to_le_bytes();
+/// #     let zlib = [&[0x78, 0x01, 0x01, size[0], size[1], !size[0], !size[1]][..], rows].concat();
+/// #     let ihdr = [&width.to_be_bytes()[..], &height.to_be_bytes(), &[depth, color, 0, 0, interlace]].concat();
+/// #     let chunks = chunks.iter().map(|&(kind, data)| chunk(kind, data)).collect::<Vec<_>>().concat();
+/// #     [&b"\x89PNG\r\n\x1a\n"[..], &chunk(b"IHDR", &ihdr), &chunks, &chunk(b"IDAT", &zlib), &chunk(b"IEND", &[])].concat()
+/// # }
+/// // 2 bit palette indices 0 to 3, 2 has no tRNS entry and 3 is out of the palette
+/// let palette = png((4, 1), [2, 3, 0], &[(b"PLTE", &[255, 0, 0, 0, 255, 0, 0, 0, 255]), (b"tRNS", &[128, 0])], &[0, 0b00_01_10_11]);
+/// assert_eq!(decode_image(&palette).unwrap().bytes, [255, 0, 0, 128, 0, 255, 0, 0, 0, 0, 255, 255, 0, 0, 0, 255]);
+///
+/// // 16 bit RGB truncated to 8 bits, the second pixel matches the tRNS key
+/// let rgb16 = png((2, 1), [16, 2, 0], &[(b"tRNS", &[0xAB, 0xCD, 0, 0, 0xFF, 0xFF])], &[0, 0x12, 0x34, 0xAB, 0xCD, 0xFF, 0xFF, 0xAB, 0xCD, 0, 0, 0xFF, 0xFF]);
+/// assert_eq!(decode_image(&rgb16).unwrap().bytes, [0x12, 0xAB, 0xFF, 255, 0xAB, 0x00, 0xFF, 0]);
+///
+/// // sub, up, average and paeth filtered rows of a 2x4 8 bit grayscale image
+/// let filtered = png((2, 4), [8, 0, 0], &[], &[1, 10, 5, 2, 1, 2, 3, 4, 4, 4, 1, 1]);
+/// let gray: Vec<u8> = decode_image(&filtered).unwrap().bytes.chunks(4).map(|pixel| pixel[0]).collect();
+/// assert_eq!(gray, [10, 15, 11, 17, 9, 17, 10, 18]);
+///
+/// // 3x3 Adam7 interlaced 8 bit grayscale image, passes 1, 4, 5, 6 and 7 hold
+/// // (0, 0); (2, 0); (0, 2) and (2, 2); (1, 0) and (1, 2) on two rows; the middle row
+/// let rows = [0, 0, 0, 20, 0, 60, 80, 0, 10, 0, 70, 0, 30, 40, 50];
+/// let adam7 = png((3, 3), [8, 0, 1], &[], &rows);
+/// let gray: Vec<u8> = decode_image(&adam7).unwrap().bytes.chunks(4).map(|pixel| pixel[0]).collect();
+/// assert_eq!(gray, [0, 10, 20, 30, 40, 50, 60, 70, 80]);
+///
+/// assert_eq!(decode_image(&palette[..40]).unwrap_err(), ImageError::UnexpectedEnd);
+/// assert_eq!(decode_image(&png((2, 4), [8, 0, 0], &[], &[1, 10, 5])).unwrap_err(), ImageError::UnexpectedEnd);
+/// assert_eq!(decode_image(&png((1, 1), [8, 0, 0], &[], &[5, 0])).unwrap_err(), ImageError::InvalidData("invalid filter type"));
+/// assert_eq!(decode_image(&png((1, 1), [8, 3, 0], &[], &[0, 0])).unwrap_err(), ImageError::InvalidData("no PLTE chunk"));
+/// assert_eq!(decode_image(&png((1, 1), [4, 2, 0], &[], &[0, 0])).unwrap_err(), ImageError::InvalidData("invalid bit depth for the color type"));
+/// assert_eq!(decode_image(&png((0, 1), [8, 0, 0], &[], &[0])).unwrap_err(), ImageError::InvalidData("empty image"));
+/// assert_eq!(decode_image(&png((1, 1), [8, 0, 0], &[(b"ABCD", &[])], &[0, 0])).unwrap_err(), ImageError::Unsupported("unknown critical chunk"));
+/// ```
+pub(super) fn decode(bytes: &[u8]) -> Result<(u32, u32, Vec<u8>), ImageError> {
+	let mut chunks = &bytes[SIGNATURE.len()..];
+	let mut header = None;
+	let mut colors = Colors { palette: vec![], transparent: None };
+	let mut transparency = None;
+	let mut data = vec![];
+
+	loop {
+		if chunks.len() < 12 {
+			return Err(ImageError::UnexpectedEnd);
+		}
+		let length = be_u32(chunks) as usize;
+		let kind = &chunks[4..8];
+		// length, type, data and CRC, which is not verified
+		let end = length.checked_add(12).filter(|&end| end <= chunks.len()).ok_or(ImageError::UnexpectedEnd)?;
+		let chunk = &chunks[8..end - 4];
+		chunks = &chunks[end..];
+
+		match kind {
+			b"IHDR" => {
+				if chunk.len() != 13 {
+					return Err(ImageError::InvalidData("invalid IHDR size"));
+				}
+				let (bit_depth, color_type) = (chunk[8], chunk[9]);
+				let valid_depth = match color_type {
+					0 => matches!(bit_depth, 1 | 2 | 4 | 8 | 16),
+					3 => matches!(bit_depth, 1 | 2 | 4 | 8),
+					2 | 4 | 6 => matches!(bit_depth, 8 | 16),
+					_ => return Err(ImageError::InvalidData("invalid color type")),
+				};
+				if !valid_depth {
+					return Err(ImageError::InvalidData("invalid bit depth for the color type"));
+				}
+				if chunk[10] != 0 || chunk[11] != 0 || chunk[12] > 1 {
+					return Err(ImageError::Unsupported("unknown compression, filter or interlace method"));
+				}
+				header = Some(Header {
+					width: be_u32(chunk) as usize,
+					height: be_u32(&chunk[4..]) as usize,
+					bit_depth,
+					color_type,
+					interlaced: chunk[12] == 1,
+				});
+			}
+			b"PLTE" => colors.palette = chunk.chunks_exact(3).map(|rgb| [rgb[0], rgb[1], rgb[2], 255]).collect(),
+			b"tRNS" => transparency = Some(chunk),
+			b"IDAT" => data.extend_from_slice(chunk),
+			b"IEND" => break,
+			// every critical chunk is known, ancillary chunks are safe to skip
+			_ if kind[0].is_ascii_uppercase() => return Err(ImageError::Unsupported("unknown critical chunk")),
+			_ => {}
+		}
+	}
+
+	let header = header.ok_or(ImageError::InvalidData("no IHDR chunk"))?;
+	if header.width == 0 || header.height == 0 {
+		return Err(ImageError::InvalidData("empty image"));
+	}
+	// 16 bytes per pixel is more than the filtered data of any color type, with the filter type bytes
+	let pixel_count = header
+		.width
+		.checked_mul(header.height)
+		.filter(|pixels| pixels.checked_mul(16).is_some())
+		.ok_or(ImageError::InvalidData("image is too large"))?;
+	let size = pixel_count * 4;
+
+	if header.color_type == 3 && colors.palette.is_empty() {
+		return Err(ImageError::InvalidData("no PLTE chunk"));
+	}
+	if let Some(transparency) = transparency {
+		let sample = |i: usize| transparency.get(i * 2..i * 2 + 2).map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]));
+		match header.color_type {
+			0 => colors.transparent = sample(0).map(|gray| [gray; 3]),
+			2 => colors.transparent = sample(0).zip(sample(1)).zip(sample(2)).map(|((r, g), b)| [r, g, b]),
+			3 => {
+				for (color, &alpha) in colors.palette.iter_mut().zip(transparency) {
+					color[3] = alpha;
+				}
+			}
+			_ => {}
+		}
+	}
+
+	// row sizes of all the passes, plus a filter type byte per row
+	let filtered_size: usize = passes(&header).map(|(_, _, _, _, width, height)| (header.row_size(width) + 1) * height).sum();
+	let filtered = zlib_decompress(&data, filtered_size)?;
+	if filtered.len() < filtered_size {
+		return Err(ImageError::UnexpectedEnd);
+	}
+
+	let mut pixels = vec![0; size];
+	let mut filtered = &filtered[..];
+	for (x0, y0, dx, dy, width, height) in passes(&header) {
+		let row_size = header.row_size(width);
+		let (pass, rest) = filtered.split_at((row_size + 1) * height);
+		filtered = rest;
+
+		let mut previous = vec![0; row_size];
+		for (y, row) in pass.chunks_exact(row_size + 1).enumerate() {
+			let mut current = row[1..].to_vec();
+			unfilter(row[0], (header.bits_per_pixel() / 8).max(1), &previous, &mut current)?;
+			for x in 0..width {
+				let pixel = ((y0 + y * dy) * header.width + x0 + x * dx) * 4;
+				pixels[pixel..pixel + 4].copy_from_slice(&to_rgba8(&header, &colors, &current, x));
+			}
+			previous = current;
+		}
+	}
+
+	Ok((header.width as u32, header.height as u32, pixels))
+}
+
+/// (x0, y0, dx, dy, width, height) of the non empty passes, one pass covering everything for non interlaced images
+fn passes(header: &Header) -> impl Iterator<Item = (usize, usize, usize, usize, usize, usize)> + '_ {
+	let passes: &[_] = if header.interlaced { &ADAM7 } else { &[(0, 0, 1, 1)] };
+	passes
+		.iter()
+		.map(|&(x0, y0, dx, dy)| (x0, y0, dx, dy, (header.width + dx - 1 - x0) / dx, (header.height + dy - 1 - y0) / dy))
+		.filter(|&(.., width, height)| width != 0 && height != 0)
+}
+
+fn unfilter(filter: u8, pixel_size: usize, previous: &[u8], row: &mut [u8]) -> Result<(), ImageError> {
+	match filter {
+		0 => {}
+		1 => {
+			for i in pixel_size..row.len() {
+				row[i] = row[i].wrapping_add(row[i - pixel_size]);
+			}
+		}
+		2 => {
+			for (byte, &up) in row.iter_mut().zip(previous) {
+				*byte = byte.wrapping_add(up);
+			}
+		}
+		3 => {
+			for i in 0..row.len() {
+				let left = if i >= pixel_size { row[i - pixel_size] } else { 0 };
+				row[i] = row[i].wrapping_add(((left as u16 + previous[i] as u16) / 2) as u8);
+			}
+		}
+		4 => {
+			for i in 0..row.len() {
+				let (left, up_left) = if i >= pixel_size { (row[i - pixel_size], previous[i - pixel_size]) } else { (0, 0) };
+				row[i] = row[i].wrapping_add(paeth(left, previous[i], up_left));
+			}
+		}
+		_ => return Err(ImageError::InvalidData("invalid filter type")),
+	}
+	Ok(())
+}
+
+fn paeth(left: u8, up: u8, up_left: u8) -> u8 {
+	let estimate = left as i16 + up as i16 - up_left as i16;
+	let (to_left, to_up, to_up_left) = ((estimate - left as i16).abs(), (estimate - up as i16).abs(), (estimate - up_left as i16).abs());
+	if to_left <= to_up && to_left <= to_up_left {
+		left
+	} else if to_up <= to_up_left {
+		up
+	} else {
+		up_left
+	}
+}
+
+/// Pixel `x` of an unfiltered row
+fn to_rgba8(header: &Header, colors: &Colors, row: &[u8], x: usize) -> [u8; 4] {
+	let depth = header.bit_depth as usize;
+	let sample = |channel: usize| -> u16 {
+		let index = x * header.channels() + channel;
+		match depth {
+			16 => u16::from_be_bytes([row[index * 2], row[index * 2 + 1]]),
+			8 => row[index] as u16,
+			_ => ((row[index * depth / 8] >> (8 - depth - index * depth % 8)) & ((1 << depth) - 1) as u8) as u16,
+		}
+	};
+	let scale = |sample: u16| -> u8 {
+		match depth {
+			16 => (sample >> 8) as u8,
+			8 => sample as u8,
+			_ => (sample * 255 / ((1 << depth) - 1)) as u8,
+		}
+	};
+	let opaque = |samples: [u16; 3]| if colors.transparent == Some(samples) { 0 } else { 255 };
+
+	match header.color_type {
+		0 => {
+			let gray = sample(0);
+			[scale(gray), scale(gray), scale(gray), opaque([gray; 3])]
+		}
+		2 => {
+			let rgb = [sample(0), sample(1), sample(2)];
+			[scale(rgb[0]), scale(rgb[1]), scale(rgb[2]), opaque(rgb)]
+		}
+		// out of range palette indices are black
+		3 => colors.palette.get(sample(0) as usize).copied().unwrap_or([0, 0, 0, 255]),
+		4 => {
+			let gray = scale(sample(0));
+			[gray, gray, gray, scale(sample(1))]
+		}
+		_ => [scale(sample(0)), scale(sample(1)), scale(sample(2)), scale(sample(3))],
+	}
+}
@@ -0,0 +1,100 @@
+//! QOI decoding, see https://qoiformat.org/qoi-specification.pdf
+
+use super::ImageError;
+
+pub(super) const MAGIC: &[u8; 4] = b"qoif";
+const HEADER_SIZE: usize = 14;
+
+const OP_RGB: u8 = 0xFE;
+const OP_RGBA: u8 = 0xFF;
+const OP_INDEX: u8 = 0x00;
+const OP_DIFF: u8 = 0x40;
+const OP_LUMA: u8 = 0x80;
+const OP_MASK: u8 = 0xC0;
+
+/// ```
+/// # use miniquad_wasm_bindgen::graphics::*;
+/// # fn qoi(width: u32, height: u32, ops: &[u8]) -> Vec<u8> {
+/// #     [&b"qoif"[..], &width.to_be_bytes(), &height.to_be_bytes(), &[4, 0], ops, &[0, 0, 0, 0, 0, 0, 0, 1]].concat()
+/// # }
+/// let ops = [
+///     &[0xFE, 10, 20, 30][..], // OP_RGB
+///     &[0xFF, 1, 2, 3, 4], // OP_RGBA
+///     &[0x09], // OP_INDEX of the first pixel
+///     &[0x40 | (1 << 4) | (2 << 2) | 3], // OP_DIFF -1, 0, +1
+///     &[0x80 | (32 + 5), ((8 - 3) << 4) | (8 + 2)], // OP_LUMA green +5, red -3 and blue +2 relative to green
+///     &[0xC0 | 2], // OP_RUN of 3
+/// ]
+/// .concat();
+/// let image = decode_image(&qoi(4, 2, &ops)).unwrap();
+/// let pixels: Vec<[u8; 4]> = image.bytes.chunks(4).map(|pixel| pixel.try_into().unwrap()).collect();
+/// assert_eq!(pixels, [[10, 20, 30, 255], [1, 2, 3, 4], [10, 20, 30, 255], [9, 20, 31, 255], [11, 25, 38, 255], [11, 25, 38, 255], [11, 25, 38, 255], [11, 25, 38, 255]]);
+///
+/// // runs stop at the end of the image
+/// assert_eq!(decode_image(&qoi(1, 1, &[0xC0 | 61])).unwrap().bytes, [0, 0, 0, 255]);
+///
+/// assert_eq!(decode_image(&qoi(4, 2, &ops)[..14]).unwrap_err(), ImageError::UnexpectedEnd);
+/// assert_eq!(decode_image(&qoi(4, 2, &ops)[..20]).unwrap_err(), ImageError::UnexpectedEnd);
+/// assert_eq!(decode_image(&qoi(0, 2, &ops)).unwrap_err(), ImageError::InvalidData("empty image"));
+/// assert_eq!(decode_image(&qoi(u32::MAX, u32::MAX, &ops)).unwrap_err(), ImageError::InvalidData("image is too large"));
+/// ```
+pub(super) fn decode(bytes: &[u8]) -> Result<(u32, u32, Vec<u8>), ImageError> {
+	if bytes.len() < HEADER_SIZE {
+		return Err(ImageError::UnexpectedEnd);
+	}
+	let width = u32::from_be_bytes(bytes[4..8].try_into().unwrap());
+	let height = u32::from_be_bytes(bytes[8..12].try_into().unwrap());
+	if width == 0 || height == 0 {
+		return Err(ImageError::InvalidData("empty image"));
+	}
+	// channels and colorspace are informative only, the data is always decoded the same way
+	let size = (width as usize)
+		.checked_mul(height as usize)
+		.and_then(|pixels| pixels.checked_mul(4))
+		.ok_or(ImageError::InvalidData("image is too large"))?;
+
+	// a run of 62 pixels is the most a byte can produce, do not trust the header more than the data
+	let mut pixels = Vec::with_capacity(size.min((bytes.len() - HEADER_SIZE).saturating_mul(62 * 4)));
+	let mut index = [[0u8; 4]; 64];
+	let mut pixel = [0, 0, 0, 255u8];
+	let mut data = bytes[HEADER_SIZE..].iter().copied();
+	let mut next = || data.next().ok_or(ImageError::UnexpectedEnd);
+
+	while pixels.len() < size {
+		let op = next()?;
+		let mut run = 1;
+		match op {
+			OP_RGB => {
+				pixel[..3].copy_from_slice(&[next()?, next()?, next()?]);
+			}
+			OP_RGBA => {
+				pixel = [next()?, next()?, next()?, next()?];
+			}
+			_ => match op & OP_MASK {
+				OP_INDEX => pixel = index[op as usize],
+				OP_DIFF => {
+					pixel[0] = pixel[0].wrapping_add((op >> 4) & 3).wrapping_sub(2);
+					pixel[1] = pixel[1].wrapping_add((op >> 2) & 3).wrapping_sub(2);
+					pixel[2] = pixel[2].wrapping_add(op & 3).wrapping_sub(2);
+				}
+				OP_LUMA => {
+					let green = (op & 0x3F).wrapping_sub(32);
+					let red_blue = next()?;
+					pixel[0] = pixel[0].wrapping_add(green).wrapping_add(red_blue >> 4).wrapping_sub(8);
+					pixel[1] = pixel[1].wrapping_add(green);
+					pixel[2] = pixel[2].wrapping_add(green).wrapping_add(red_blue & 0x0F).wrapping_sub(8);
+				}
+				// OP_RUN, the last two run lengths are OP_RGB and OP_RGBA
+				_ => run = (op & 0x3F) as usize + 1,
+			},
+		}
+
+		let [r, g, b, a] = pixel;
+		index[(r as usize * 3 + g as usize * 5 + b as usize * 7 + a as usize * 11) % 64] = pixel;
+		for _ in 0..run.min((size - pixels.len()) / 4) {
+			pixels.extend_from_slice(&pixel);
+		}
+	}
+
+	Ok((width, height, pixels))
+}
@@ -0,0 +1,180 @@
+//! KTX2 container parsing: `TextureParams` and mip levels of a texture file,
+//! borrowed from the file bytes and ready for `new_texture`.
+//!
+//! Supercompressed files (Basis Universal, Zstandard) are not supported, the data is uploaded as is.
+
+use super::*;
+
+/// Error returned by `Ktx2::parse`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Ktx2Error {
+	/// Not a KTX2 file
+	BadMagic,
+	UnexpectedEnd,
+	/// VkFormat with no matching `TextureFormat`
+	UnsupportedFormat(u32),
+	/// Basis Universal, Zstandard or another supercompression scheme
+	Supercompressed(u32),
+	/// 1D textures, cubemap arrays and arrays of 3D textures
+	UnsupportedKind,
+}
+
+impl Display for Ktx2Error {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{:?}", self) // Display the same way as Debug
+	}
+}
+
+impl Error for Ktx2Error {}
+
+const MAGIC: &[u8; 12] = &[0xAB, b'K', b'T', b'X', b' ', b'2', b'0', 0xBB, b'\r', b'\n', 0x1A, b'\n'];
+const HEADER_SIZE: usize = 80;
+const LEVEL_INDEX_ENTRY_SIZE: usize = 24;
+/// A level more and a u32 sized texture would be smaller than a pixel
+const MAX_LEVELS: u32 = 32;
+
+/// Texture stored in a KTX2 file, usually loaded with `fs::load_file`.
+///
+/// ```
+/// # use miniquad_wasm_bindgen::*;
+/// # let mut file = vec![0xAB, b'K', b'T', b'X', b' ', b'2', b'0', 0xBB, b'\r', b'\n', 0x1A, b'\n'];
+/// # // R8G8B8A8_UNORM, type size, 2x2, no depth, no layers, 1 face, 1 level, no supercompression
+/// # for value in [37u32, 1, 2, 2, 0, 0, 1, 1, 0, 0, 0, 0, 0] { file.extend(value.to_le_bytes()) }
+/// # for value in [0u64, 0, 104, 16, 16] { file.extend(value.to_le_bytes()) }
+/// # file.extend([255u8; 16]);
+/// let ktx = Ktx2::parse(&file).unwrap();
+/// assert_eq!((ktx.params.format, ktx.params.width, ktx.params.height), (TextureFormat::RGBA8, 2, 2));
+///
+/// let mut ctx = NullContext::new();
+/// let texture = ktx.new_texture(&mut ctx).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Ktx2<'a> {
+	pub params: TextureParams,
+	/// `[cubemap_face][mipmap_level][bytes]`, or `[layer][mipmap_level][bytes]` for `Texture2DArray` and `Texture3D`,
+	/// the layout of `TextureSource::Array`. Slices missing from the smaller mip levels of a `Texture3D` are empty.
+	pub images: Vec<Vec<&'a [u8]>>,
+}
+
+impl<'a> Ktx2<'a> {
+	/// Malformed files are errors, the sizes in the header are checked against the data before allocating.
+	///
+	/// ```
+	/// # use miniquad_wasm_bindgen::*;
+	/// # fn file(width: u32, height: u32, layers: u32, levels: u32) -> Vec<u8> {
+	/// #     let mut file = vec![0xAB, b'K', b'T', b'X', b' ', b'2', b'0', 0xBB, b'\r', b'\n', 0x1A, b'\n'];
+	/// #     for value in [37u32, 1, width, height, 0, layers, 1, levels, 0, 0, 0, 0, 0] { file.extend(value.to_le_bytes()) }
+	/// #     for value in [0u64, 0, 104, 16, 16] { file.extend(value.to_le_bytes()) }
+	/// #     file.extend([255u8; 16]);
+	/// #     file
+	/// # }
+	/// assert!(Ktx2::parse(&file(2, 2, 0, 1)).is_ok());
+	/// // truncated in the level index or in the data
+	/// assert_eq!(Ktx2::parse(&file(2, 2, 0, 1)[..90]).unwrap_err(), Ktx2Error::UnexpectedEnd);
+	/// assert_eq!(Ktx2::parse(&file(2, 2, 0, 1)[..110]).unwrap_err(), Ktx2Error::UnexpectedEnd);
+	/// // sizes the 16 bytes of data can not hold, or that overflow
+	/// assert_eq!(Ktx2::parse(&file(65536, 65536, 0, 1)).unwrap_err(), Ktx2Error::UnexpectedEnd);
+	/// assert_eq!(Ktx2::parse(&file(2, 2, 0xFFFF_FFF0, 1)).unwrap_err(), Ktx2Error::UnexpectedEnd);
+	/// assert_eq!(Ktx2::parse(&file(2, 2, 0, 40)).unwrap_err(), Ktx2Error::UnsupportedKind);
+	/// ```
+	pub fn parse(bytes: &'a [u8]) -> Result<Ktx2<'a>, Ktx2Error> {
+		if bytes.len() < HEADER_SIZE {
+			return Err(if bytes.starts_with(MAGIC) { Ktx2Error::UnexpectedEnd } else { Ktx2Error::BadMagic });
+		}
+		if !bytes.starts_with(MAGIC) {
+			return Err(Ktx2Error::BadMagic);
+		}
+
+		let header = |field: usize| u32::from_le_bytes(bytes[12 + field * 4..][..4].try_into().unwrap());
+		let (vk_format, width, height, depth, layers, faces, levels, supercompression) = (header(0), header(2), header(3), header(4), header(5), header(6), header(7).max(1), header(8));
+
+		if supercompression != 0 {
+			return Err(Ktx2Error::Supercompressed(supercompression));
+		}
+		let format = texture_format(vk_format).ok_or(Ktx2Error::UnsupportedFormat(vk_format))?;
+
+		if levels > MAX_LEVELS {
+			return Err(Ktx2Error::UnsupportedKind);
+		}
+		let (kind, depth) = match (faces, depth, layers) {
+			_ if width == 0 || height == 0 => return Err(Ktx2Error::UnsupportedKind),
+			(1, 0, 0) => (TextureKind::Texture2D, 1),
+			(1, 0, layers) => (TextureKind::Texture2DArray, layers),
+			(1, depth, 0) => (TextureKind::Texture3D, depth),
+			(6, 0, 0) => (TextureKind::CubeMap, 1),
+			_ => return Err(Ktx2Error::UnsupportedKind),
+		};
+
+		// faces of a cubemap, layers of an array, slices of a 3D texture
+		let images_count = if kind == TextureKind::CubeMap { 6 } else { depth as usize };
+		// every image of the first level has to be in the file
+		let first_level_size = format.checked_size(width, height).and_then(|size| (size as usize).checked_mul(images_count));
+		if first_level_size.is_none_or(|size| size > bytes.len()) {
+			return Err(Ktx2Error::UnexpectedEnd);
+		}
+		let mut images = vec![Vec::with_capacity(levels as usize); images_count];
+		for level in 0..levels as usize {
+			let entry = bytes.get(HEADER_SIZE + level * LEVEL_INDEX_ENTRY_SIZE..).and_then(|entry| entry.get(..16)).ok_or(Ktx2Error::UnexpectedEnd)?;
+			let offset = u64::from_le_bytes(entry[..8].try_into().unwrap());
+			let length = u64::from_le_bytes(entry[8..].try_into().unwrap());
+			let data = usize::try_from(offset)
+				.ok()
+				.zip(usize::try_from(length).ok())
+				.and_then(|(offset, length)| bytes.get(offset..offset.checked_add(length)?))
+				.ok_or(Ktx2Error::UnexpectedEnd)?;
+
+			// smaller than the first level, which fits in the file
+			let image_size = format.size((width >> level).max(1), (height >> level).max(1)) as usize;
+			let level_images = if kind == TextureKind::Texture3D { (depth as usize >> level).max(1) } else { images_count };
+			if data.len() < image_size * level_images {
+				return Err(Ktx2Error::UnexpectedEnd);
+			}
+
+			for (i, mipmaps) in images.iter_mut().enumerate() {
+				mipmaps.push(if i < level_images { &data[i * image_size..][..image_size] } else { &[][..] });
+			}
+		}
+
+		let params = TextureParams {
+			kind,
+			format,
+			width,
+			height,
+			depth,
+			mipmap_filter: if levels > 1 { MipmapFilterMode::Linear } else { MipmapFilterMode::None },
+			allocate_mipmaps: levels > 1,
+			..Default::default()
+		};
+		Ok(Ktx2 { params, images })
+	}
+
+	/// Create a static texture with all the mip levels of the file.
+	pub fn new_texture(&self, ctx: &mut dyn RenderingBackend) -> Result<TextureId, GraphicsError> {
+		let images = self.images.iter().map(|mipmaps| mipmaps.as_slice()).collect::<Vec<_>>();
+		ctx.try_new_texture(TextureAccess::Static, TextureSource::Array(&images), self.params)
+	}
+}
+
+/// `TextureFormat` of a VkFormat, sRGB variants only exist for `SRGB8A8`.
+fn texture_format(vk_format: u32) -> Option<TextureFormat> {
+	Some(match vk_format {
+		9 => TextureFormat::R8,
+		16 => TextureFormat::RG8,
+		23 => TextureFormat::RGB8,
+		37 => TextureFormat::RGBA8,
+		43 => TextureFormat::SRGB8A8,
+		76 => TextureFormat::R16F,
+		83 => TextureFormat::RG16F,
+		97 => TextureFormat::RGBA16F,
+		100 => TextureFormat::R32F,
+		109 => TextureFormat::RGBA32F,
+		133 => TextureFormat::BC1,
+		137 => TextureFormat::BC3,
+		147 => TextureFormat::ETC2RGB8,
+		151 => TextureFormat::ETC2RGBA8,
+		157 => TextureFormat::ASTC4x4,
+		165 => TextureFormat::ASTC6x6,
+		171 => TextureFormat::ASTC8x8,
+		_ => return None,
+	})
+}
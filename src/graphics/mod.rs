@@ -1,10 +1,31 @@
 use crate::native::gl::*;
+use crate::ResourceId;
 
 use std::{error::Error, fmt::Display};
 
+mod capture;
+mod diagnostic;
 mod gl;
-
+#[cfg(feature = "image-decode")]
+mod image;
+mod ktx2;
+mod null;
+mod preprocess;
+mod reflection;
+#[cfg(not(target_arch = "wasm32"))]
+mod watcher;
+
+pub use capture::{CaptureContext, CaptureError, CapturedBufferSource, CapturedCall, CapturedTextureSource, CapturedVertexAttribute, FrameCapture, ReplayError};
+pub use diagnostic::ShaderDiagnostic;
 pub use gl::{raw_gl, GlContext};
+#[cfg(feature = "image-decode")]
+pub use image::{decode_image, DecodedImage, ImageError};
+pub use ktx2::{Ktx2, Ktx2Error};
+pub use null::{NullCommand, NullContext};
+pub use preprocess::{GlslVersion, PreprocessError, PreprocessedShader, ShaderPreprocessor, SourceLocation};
+pub use reflection::{AttributeDesc, ShaderMismatch, ShaderReflection};
+#[cfg(not(target_arch = "wasm32"))]
+pub use watcher::ShaderWatcher;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum UniformType {
@@ -43,20 +64,94 @@ impl UniformType {
 			UniformType::Mat4 => 64,
 		}
 	}
+
+	/// Base alignment in a std140 uniform block
+	pub fn std140_align(&self) -> usize {
+		match self {
+			UniformType::Float1 | UniformType::Int1 => 4,
+			UniformType::Float2 | UniformType::Int2 => 8,
+			_ => 16,
+		}
+	}
 }
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct UniformDesc {
 	pub name: String,
 	pub uniform_type: UniformType,
 	pub array_count: usize,
 }
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Default)]
 pub struct UniformBlockLayout {
 	pub uniforms: Vec<UniformDesc>,
 }
 
+impl UniformBlockLayout {
+	/// Byte offsets of the uniforms in a `layout(std140)` uniform block.
+	///
+	/// ```
+	/// # use miniquad_wasm_bindgen::*;
+	/// let layout = UniformBlockLayout {
+	///     uniforms: vec![
+	///         UniformDesc::new("mvp", UniformType::Mat4),
+	///         UniformDesc::new("tint", UniformType::Float3),
+	///         UniformDesc::new("time", UniformType::Float1),
+	///         UniformDesc::new("offsets", UniformType::Float2).array(2),
+	///     ],
+	/// };
+	/// assert_eq!(layout.std140_offsets(), [0, 64, 76, 80]);
+	/// assert_eq!(layout.std140_size(), 112);
+	/// ```
+	pub fn std140_offsets(&self) -> Vec<usize> {
+		self.std140().0
+	}
+
+	/// Size of a `layout(std140)` uniform block with these uniforms, padded to 16 bytes.
+	/// The minimum `size` for `apply_uniform_buffer`.
+	pub fn std140_size(&self) -> usize {
+		self.std140().1
+	}
+
+	fn std140(&self) -> (Vec<usize>, usize) {
+		let round_up = |value: usize, align: usize| value.div_ceil(align) * align;
+
+		let mut offsets = Vec::with_capacity(self.uniforms.len());
+		let mut end = 0;
+		for uniform in &self.uniforms {
+			// array elements are padded to vec4
+			let (align, size) = match uniform.array_count {
+				1 => (uniform.uniform_type.std140_align(), uniform.uniform_type.size()),
+				count => (16, round_up(uniform.uniform_type.size(), 16) * count),
+			};
+			let offset = round_up(end, align);
+			offsets.push(offset);
+			end = offset + size;
+		}
+		(offsets, round_up(end, 16))
+	}
+}
+
+/// A `layout(std140)` uniform block of a shader, filled from a `BufferType::UniformBuffer` buffer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UniformBlockDesc {
+	/// Block name in GLSL: `layout(std140) uniform Name { ... };`
+	pub name: String,
+	/// Binding point, the `binding` argument of `apply_uniform_buffer`
+	pub binding: u32,
+	pub layout: UniformBlockLayout,
+}
+
+impl UniformBlockDesc {
+	pub fn new(name: &str, binding: u32, uniforms: Vec<UniformDesc>) -> UniformBlockDesc {
+		UniformBlockDesc {
+			name: name.to_string(),
+			binding,
+			layout: UniformBlockLayout { uniforms },
+		}
+	}
+}
+
 impl UniformDesc {
 	pub fn new(name: &str, uniform_type: UniformType) -> UniformDesc {
 		UniformDesc {
@@ -71,10 +166,86 @@ impl UniformDesc {
 	}
 }
 
-#[derive(Clone, PartialEq)]
+/// How a shader samples an image, the GLSL sampler type has to match the bound texture.
+///
+/// ```
+/// # use miniquad_wasm_bindgen::*;
+/// let mut ctx = NullContext::new();
+/// # let vertex_buffer = ctx.new_buffer(BufferType::VertexBuffer, BufferUsage::Immutable, BufferSource::slice(&[0f32; 6]));
+/// # let index_buffer = ctx.new_buffer(BufferType::IndexBuffer, BufferUsage::Immutable, BufferSource::slice(&[0u16, 1, 2]));
+/// // uniform sampler2DShadow shadow_map;
+/// let meta = ShaderMeta {
+///     images: vec![ImageDesc::new("shadow_map", ImageType::Shadow)],
+///     ..Default::default()
+/// };
+/// # let pipeline = ctx.new_test_pipeline(meta, PipelineParams::default());
+/// let shadow_map = ctx.new_render_texture(TextureParams {
+///     width: 1024,
+///     height: 1024,
+///     format: TextureFormat::Depth32,
+///     compare: Some(Comparison::LessOrEqual),
+///     ..Default::default()
+/// });
+///
+/// ctx.begin_default_pass(PassAction::Nothing);
+/// ctx.apply_pipeline(&pipeline);
+/// ctx.apply_bindings(&Bindings { vertex_buffers: vec![vertex_buffer], index_buffer: Some(index_buffer), images: vec![shadow_map], samplers: vec![] });
+/// ctx.end_render_pass();
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageType {
+	/// `sampler2D`, `samplerCube`, `sampler2DArray` or `sampler3D`
+	Float,
+	/// `sampler2DShadow`, `samplerCubeShadow` or `sampler2DArrayShadow`: a depth texture with
+	/// `TextureParams::compare` or a `SamplerParams::compare` sampler.
+	/// With `FilterMode::Linear` the comparison is filtered over 2x2 texels (hardware PCF).
+	Shadow,
+}
+
+/// A sampler uniform of a shader.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImageDesc {
+	pub name: String,
+	pub image_type: ImageType,
+}
+
+impl ImageDesc {
+	pub fn new(name: &str, image_type: ImageType) -> ImageDesc {
+		ImageDesc { name: name.to_string(), image_type }
+	}
+}
+
+/// A `sampler2D`, `samplerCube`, `sampler2DArray` or `sampler3D`, the images of a `ShaderMeta` before `ImageType` existed.
+///
+/// ```
+/// # use miniquad_wasm_bindgen::*;
+/// let meta = ShaderMeta {
+///     images: vec!["albedo".into(), "normals".into()],
+///     ..Default::default()
+/// };
+/// assert_eq!(meta.images[1], ImageDesc::new("normals", ImageType::Float));
+/// ```
+impl From<&str> for ImageDesc {
+	fn from(name: &str) -> ImageDesc {
+		ImageDesc::new(name, ImageType::Float)
+	}
+}
+
+impl From<String> for ImageDesc {
+	fn from(name: String) -> ImageDesc {
+		ImageDesc { name, image_type: ImageType::Float }
+	}
+}
+
+#[derive(Clone, Debug, PartialEq, Default)]
 pub struct ShaderMeta {
 	pub uniforms: UniformBlockLayout,
-	pub images: Vec<String>,
+	/// Sampler uniforms, in `Bindings::images` order.
+	/// The sampler dimension matches the `TextureKind` of the bound texture.
+	pub images: Vec<ImageDesc>,
+	/// Uniform blocks, supplied with `apply_uniform_buffer` instead of `apply_uniforms`.
+	/// Requires GL3.1+, GLES3 or WebGL2, see `ContextInfo::uniform_buffer_offset_alignment`.
+	pub uniform_blocks: Vec<UniformBlockDesc>,
 }
 
 #[derive(Clone, Copy, PartialEq, Debug)]
@@ -212,18 +383,18 @@ impl Default for BufferLayout {
 }
 
 #[derive(Clone, Debug)]
-pub struct VertexAttribute {
-	pub name: &'static str,
+pub struct VertexAttribute<'a> {
+	pub name: &'a str,
 	pub format: VertexFormat,
 	pub buffer_index: usize,
 }
 
-impl VertexAttribute {
-	pub const fn new(name: &'static str, format: VertexFormat) -> VertexAttribute {
+impl<'a> VertexAttribute<'a> {
+	pub const fn new(name: &'a str, format: VertexFormat) -> VertexAttribute<'a> {
 		Self::with_buffer(name, format, 0)
 	}
 
-	pub const fn with_buffer(name: &'static str, format: VertexFormat, buffer_index: usize) -> VertexAttribute {
+	pub const fn with_buffer(name: &'a str, format: VertexFormat, buffer_index: usize) -> VertexAttribute<'a> {
 		VertexAttribute { name, format, buffer_index }
 	}
 }
@@ -231,10 +402,10 @@ impl VertexAttribute {
 #[derive(Clone, Debug)]
 pub struct PipelineLayout {
 	pub buffers: &'static [BufferLayout],
-	pub attributes: &'static [VertexAttribute],
+	pub attributes: &'static [VertexAttribute<'static>],
 }
 
-#[derive(Clone, Debug, Copy)]
+#[derive(Clone, Debug, Copy, PartialEq, Eq)]
 pub enum ShaderType {
 	Vertex,
 	Fragment,
@@ -249,6 +420,8 @@ pub enum ShaderError {
 	LinkError(String),
 	/// Shader strings should never contains \00 in the middle
 	FFINulError(std::ffi::NulError),
+	/// The `ShaderMeta` does not describe the shader, see `ShaderReflection::validate`
+	MetaMismatch(Vec<ShaderMismatch>),
 }
 
 impl From<std::ffi::NulError> for ShaderError {
@@ -269,8 +442,37 @@ impl Error for ShaderError {
 	}
 }
 
+/// Error returned by the `try_*` functions of `RenderingBackend`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GraphicsError {
+	/// The driver could not allocate the resource
+	OutOfMemory,
+	/// `glCheckFramebufferStatus` result for a render pass that can not be rendered to
+	IncompleteFramebuffer(u32),
+	/// The format is not supported by the driver, or can not be used this way
+	UnsupportedFormat(TextureFormat),
+	/// The resource was deleted, `kind` is the type of the handle
+	StaleId { kind: &'static str, id: ResourceId },
+	/// Index buffer element size in bytes, only `u8`, `u16` and `u32` indices are supported
+	UnsupportedIndexSize(usize),
+	/// The bytes of a texture, or of one of its mip levels, do not match `TextureFormat::size`
+	TextureDataSize { expected: usize, found: usize },
+	/// Texture or pipeline parameters that do not go together, or exceed the context limits
+	InvalidParams(&'static str),
+}
+
+impl Display for GraphicsError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{:?}", self) // Display the same way as Debug
+	}
+}
+
+impl Error for GraphicsError {}
+
 /// List of all the possible formats of input data when uploading to texture.
-/// The list is built by intersection of texture formats supported by 3.3 core profile and webgl1.
+/// The list is built by intersection of texture formats supported by 3.3 core profile and GLES3/WebGL2.
+///
+/// 16 bit float formats take half floats, 32 bit float formats take `f32`s.
 #[repr(u8)]
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub enum TextureFormat {
@@ -280,20 +482,77 @@ pub enum TextureFormat {
 	Depth,
 	Depth32,
 	Alpha,
+	R8,
+	RG8,
+	R16F,
+	RG16F,
+	R32F,
+	RGBA32F,
+	/// RGBA8 with sRGB encoded color, converted to linear when sampled and back when rendered to
+	SRGB8A8,
+	/// Depth and stencil, attached to both the depth and the stencil attachment points of a `RenderPass`
+	Depth24Stencil8,
+	/// ETC2 RGB, 8 bytes per 4x4 block. Check `ContextInfo::texture_compression` for the compressed formats.
+	ETC2RGB8,
+	/// ETC2 RGBA with EAC alpha, 16 bytes per 4x4 block
+	ETC2RGBA8,
+	/// ASTC LDR, 16 bytes per 4x4 block
+	ASTC4x4,
+	/// ASTC LDR, 16 bytes per 6x6 block
+	ASTC6x6,
+	/// ASTC LDR, 16 bytes per 8x8 block
+	ASTC8x8,
+	/// S3TC DXT1 RGBA, 8 bytes per 4x4 block
+	BC1,
+	/// S3TC DXT5, 16 bytes per 4x4 block
+	BC3,
 }
 impl TextureFormat {
 	/// Returns the size in bytes of texture with `dimensions`.
+	/// Compressed formats round the dimensions up to whole blocks.
 	pub fn size(self, width: u32, height: u32) -> u32 {
-		let square = width * height;
+		self.checked_size(width, height).expect("Texture size overflows u32")
+	}
+
+	/// `size`, or `None` when it does not fit in a `u32`, for sizes read from files
+	pub(crate) fn checked_size(self, width: u32, height: u32) -> Option<u32> {
+		let pixels = |bytes: u32| width.checked_mul(height)?.checked_mul(bytes);
+		let blocks = |block_width: u32, block_height: u32, bytes: u32| width.div_ceil(block_width).checked_mul(height.div_ceil(block_height))?.checked_mul(bytes);
 		match self {
-			TextureFormat::RGB8 => 3 * square,
-			TextureFormat::RGBA8 => 4 * square,
-			TextureFormat::RGBA16F => 8 * square,
-			TextureFormat::Depth => 2 * square,
-			TextureFormat::Depth32 => 4 * square,
-			TextureFormat::Alpha => square,
+			TextureFormat::RGB8 => pixels(3),
+			TextureFormat::RGBA8 => pixels(4),
+			TextureFormat::RGBA16F => pixels(8),
+			TextureFormat::Depth => pixels(2),
+			TextureFormat::Depth32 => pixels(4),
+			TextureFormat::Alpha => pixels(1),
+			TextureFormat::R8 => pixels(1),
+			TextureFormat::RG8 => pixels(2),
+			TextureFormat::R16F => pixels(2),
+			TextureFormat::RG16F => pixels(4),
+			TextureFormat::R32F => pixels(4),
+			TextureFormat::RGBA32F => pixels(16),
+			TextureFormat::SRGB8A8 => pixels(4),
+			TextureFormat::Depth24Stencil8 => pixels(4),
+			TextureFormat::ETC2RGB8 | TextureFormat::BC1 => blocks(4, 4, 8),
+			TextureFormat::ETC2RGBA8 | TextureFormat::ASTC4x4 | TextureFormat::BC3 => blocks(4, 4, 16),
+			TextureFormat::ASTC6x6 => blocks(6, 6, 16),
+			TextureFormat::ASTC8x8 => blocks(8, 8, 16),
 		}
 	}
+
+	/// Block compressed formats, uploaded as is and sampled only: they can not be render targets,
+	/// layered textures, updated, resized or read back.
+	pub fn is_compressed(self) -> bool {
+		matches!(
+			self,
+			TextureFormat::ETC2RGB8 | TextureFormat::ETC2RGBA8 | TextureFormat::ASTC4x4 | TextureFormat::ASTC6x6 | TextureFormat::ASTC8x8 | TextureFormat::BC1 | TextureFormat::BC3
+		)
+	}
+
+	/// Formats for the depth attachment of a `RenderPass`.
+	pub fn is_depth(self) -> bool {
+		matches!(self, TextureFormat::Depth | TextureFormat::Depth32 | TextureFormat::Depth24Stencil8)
+	}
 }
 
 /// Sets the wrap parameter for texture.
@@ -305,6 +564,9 @@ pub enum TextureWrap {
 	Mirror,
 	/// Samples at coord x + 1 map to coord 1.
 	Clamp,
+	/// Samples outside of the texture get `SamplerParams::border_color`, transparent black for textures.
+	/// Falls back to `Clamp` without `Features::clamp_to_border`.
+	ClampToBorder,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Hash)]
@@ -332,9 +594,19 @@ pub enum TextureAccess {
 pub enum TextureKind {
 	Texture2D,
 	CubeMap,
+	/// `TextureParams::depth` layers of 2D images, sampled with `sampler2DArray`. Requires GL3 or WebGL2.
+	Texture2DArray,
+	/// `TextureParams::depth` slices deep volume, sampled with `sampler3D`. Requires GL3 or WebGL2.
+	Texture3D,
 }
 
-#[derive(Debug, Copy, Clone)]
+impl TextureKind {
+	pub(crate) fn is_layered(self) -> bool {
+		matches!(self, TextureKind::Texture2DArray | TextureKind::Texture3D)
+	}
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub struct TextureParams {
 	pub kind: TextureKind,
 	pub format: TextureFormat,
@@ -344,12 +616,17 @@ pub struct TextureParams {
 	pub mipmap_filter: MipmapFilterMode,
 	pub width: u32,
 	pub height: u32,
+	/// Layers of a `Texture2DArray`, slices of a `Texture3D`. Ignored by the other kinds.
+	pub depth: u32,
 	// All miniquad_wasm_bindgen API could work without this flag being explicit.
 	// We can decide if mipmaps are required by the data provided
 	// And reallocate non-mipmapped texture(on metal) on generateMipmaps call
 	// But! Reallocating cubemaps is too much struggle, so leave it for later.
 	pub allocate_mipmaps: bool,
 	pub sample_count: i32,
+	/// Depth formats only: compare the texture with the reference coordinate instead of returning the depth,
+	/// for `ImageType::Shadow` images.
+	pub compare: Option<Comparison>,
 }
 
 impl Default for TextureParams {
@@ -363,19 +640,60 @@ impl Default for TextureParams {
 			mipmap_filter: MipmapFilterMode::None,
 			width: 0,
 			height: 0,
+			depth: 1,
 			allocate_mipmaps: false,
 			sample_count: 0,
+			compare: None,
+		}
+	}
+}
+
+/// Filtering, wrapping and comparison of texture lookups, shared by any number of textures.
+/// Overrides the texture's own parameters where bound with `Bindings::samplers`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SamplerParams {
+	pub min_filter: FilterMode,
+	pub mag_filter: FilterMode,
+	pub mipmap_filter: MipmapFilterMode,
+	pub wrap_x: TextureWrap,
+	pub wrap_y: TextureWrap,
+	/// Only used by `Texture3D`
+	pub wrap_z: TextureWrap,
+	/// RGBA color for `TextureWrap::ClampToBorder`
+	pub border_color: [f32; 4],
+	/// Compare the depth texture with the reference coordinate instead of returning it, for `ImageType::Shadow` images.
+	/// Like the other parameters, replaces `TextureParams::compare` of the textures it is bound with.
+	pub compare: Option<Comparison>,
+	/// 1 disables anisotropic filtering. Clamped to `Limits::max_anisotropy`, ignored without `Features::anisotropic_filtering`.
+	pub max_anisotropy: u32,
+}
+
+impl Default for SamplerParams {
+	fn default() -> Self {
+		SamplerParams {
+			min_filter: FilterMode::Linear,
+			mag_filter: FilterMode::Linear,
+			mipmap_filter: MipmapFilterMode::None,
+			wrap_x: TextureWrap::Clamp,
+			wrap_y: TextureWrap::Clamp,
+			wrap_z: TextureWrap::Clamp,
+			border_color: [0.0; 4],
+			compare: None,
+			max_anisotropy: 1,
 		}
 	}
 }
 
 #[derive(Clone, Debug, Copy, PartialEq, Eq, Hash)]
-pub struct ShaderId(usize);
+pub struct SamplerId(ResourceId);
+
+#[derive(Clone, Debug, Copy, PartialEq, Eq, Hash)]
+pub struct ShaderId(ResourceId);
 
 // Inner hence we can't have private data in enum fields
 #[derive(Clone, Debug, Copy, PartialEq, Eq, Hash)]
 pub(crate) enum TextureIdInner {
-	Managed(usize),
+	Managed(ResourceId),
 	Raw(RawId),
 }
 
@@ -488,6 +806,7 @@ pub enum CompareFunc {
 
 type ColorMask = (bool, bool, bool, bool);
 
+#[derive(Debug, Clone, PartialEq)]
 pub enum PassAction {
 	Nothing,
 	Clear {
@@ -495,6 +814,93 @@ pub enum PassAction {
 		depth: Option<f32>,
 		stencil: Option<i32>,
 	},
+	/// Clear every color attachment of the pass to its own value, `None` keeps the attachment's contents.
+	/// ```
+	/// # use miniquad_wasm_bindgen::*;
+	/// let action = PassAction::ClearAttachments {
+	///     // albedo, normals, emission
+	///     colors: vec![Some((0.0, 0.0, 0.0, 1.0)), Some((0.5, 0.5, 1.0, 0.0)), None],
+	///     depth: Some(1.0),
+	///     stencil: None,
+	/// };
+	/// ```
+	ClearAttachments {
+		colors: Vec<Option<(f32, f32, f32, f32)>>,
+		depth: Option<f32>,
+		stencil: Option<i32>,
+	},
+	/// Load and store actions of every attachment, missing color attachments are loaded and stored.
+	/// Telling the driver what is not needed saves tile-based mobile GPUs the memory traffic.
+	/// ```
+	/// # use miniquad_wasm_bindgen::*;
+	/// # let mut ctx = NullContext::new();
+	/// # let color = ctx.new_render_texture(TextureParams { width: 4, height: 4, sample_count: 4, ..Default::default() });
+	/// # let resolve = ctx.new_render_texture(TextureParams { width: 4, height: 4, ..Default::default() });
+	/// # let depth = ctx.new_render_texture(TextureParams { width: 4, height: 4, format: TextureFormat::Depth, sample_count: 4, ..Default::default() });
+	/// let pass = ctx.new_render_pass_mrt(&[color], &[resolve], Some(depth));
+	/// // only the resolved image outlives the pass
+	/// ctx.begin_pass(
+	///     Some(pass),
+	///     PassAction::Attachments {
+	///         colors: vec![AttachmentAction { load: LoadOp::Clear((0.0, 0.0, 0.0, 1.0)), store: StoreOp::Discard }],
+	///         depth: AttachmentAction { load: LoadOp::Clear(1.0), store: StoreOp::Discard },
+	///         stencil: AttachmentAction { load: LoadOp::DontCare, store: StoreOp::Discard },
+	///     },
+	/// );
+	/// ctx.end_render_pass();
+	/// ```
+	Attachments {
+		colors: Vec<AttachmentAction<(f32, f32, f32, f32)>>,
+		depth: AttachmentAction<f32>,
+		stencil: AttachmentAction<i32>,
+	},
+}
+
+/// What happens to the contents of an attachment at `begin_pass`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LoadOp<T> {
+	/// Keep the contents from before the pass.
+	Load,
+	Clear(T),
+	/// The previous contents are not needed, the whole attachment is going to be drawn over.
+	DontCare,
+}
+
+impl<T> LoadOp<T> {
+	pub(crate) fn clear_value(self) -> Option<T> {
+		match self {
+			LoadOp::Clear(value) => Some(value),
+			LoadOp::Load | LoadOp::DontCare => None,
+		}
+	}
+}
+
+/// What happens to the contents of an attachment at `end_render_pass`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StoreOp {
+	/// Keep the contents, multisampled color attachments are resolved into their resolve textures.
+	#[default]
+	Store,
+	/// Keep the multisampled contents without resolving them, for a pass that is continued by a later `begin_pass`.
+	StoreUnresolved,
+	/// The contents are not needed after the pass. Multisampled color attachments are still resolved.
+	Discard,
+}
+
+/// Load and store action of a single attachment, see `PassAction::Attachments`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AttachmentAction<T> {
+	pub load: LoadOp<T>,
+	pub store: StoreOp,
+}
+
+impl<T> Default for AttachmentAction<T> {
+	fn default() -> AttachmentAction<T> {
+		AttachmentAction {
+			load: LoadOp::Load,
+			store: StoreOp::Store,
+		}
+	}
 }
 
 impl PassAction {
@@ -517,23 +923,74 @@ impl Default for PassAction {
 	}
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
-pub struct RenderPass(usize);
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RenderPass(ResourceId);
 
 pub const MAX_VERTEX_ATTRIBUTES: usize = 16;
 pub const MAX_SHADERSTAGE_IMAGES: usize = 12;
+pub const MAX_COLOR_ATTACHMENTS: usize = 8;
 
+/// Optional capabilities of the context, see `ContextInfo::features`.
 #[derive(Clone, Debug)]
 pub struct Features {
+	/// `num_instances` above 1: GL3, GLES3 and WebGL2, ARB_instanced_arrays, and ANGLE_instanced_arrays or EXT_instanced_arrays on GLES2 and WebGL1.
 	pub instancing: bool,
+	/// `R16F`, `RG16F`, `R32F` and `RGBA32F` render targets: core in desktop GL3, EXT_color_buffer_float on GLES3 and WebGL2.
+	pub float_render_targets: bool,
+	/// EXT_texture_filter_anisotropic, up to `Limits::max_anisotropy`.
+	pub anisotropic_filtering: bool,
+	/// Sampler objects for `new_sampler`: GL3.3, GLES3 and WebGL2.
+	pub samplers: bool,
+	/// `TextureWrap::ClampToBorder`: desktop GL, GLES3.2 and EXT_texture_border_clamp. Not in WebGL2.
+	pub clamp_to_border: bool,
+	/// Different `PipelineParams::color_targets` per color attachment: GL4, GLES3.2 and OES_draw_buffers_indexed on WebGL2.
+	pub independent_blend: bool,
+	/// `draw_base_vertex`: GL3.2, GLES3.2 and WEBGL_draw_instanced_base_vertex_base_instance on WebGL2.
+	pub base_vertex: bool,
 }
 
 impl Default for Features {
 	fn default() -> Features {
-		Features { instancing: true }
+		Features {
+			instancing: true,
+			float_render_targets: false,
+			anisotropic_filtering: false,
+			samplers: false,
+			clamp_to_border: false,
+			independent_blend: false,
+			base_vertex: false,
+		}
 	}
 }
 
+/// Implementation limits of the context, queried once on creation.
+/// Low-end WebGL2 devices are often right at the minimums the spec allows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limits {
+	/// GL_MAX_TEXTURE_SIZE, the largest width and height of a `Texture2D`.
+	pub max_texture_size: u32,
+	/// GL_MAX_CUBE_MAP_TEXTURE_SIZE
+	pub max_cube_map_texture_size: u32,
+	/// GL_MAX_3D_TEXTURE_SIZE, the largest size of a `Texture3D` on any axis.
+	pub max_3d_texture_size: u32,
+	/// GL_MAX_ARRAY_TEXTURE_LAYERS, the most layers of a `Texture2DArray`.
+	pub max_array_texture_layers: u32,
+	/// The most color images of a render pass, the smaller of GL_MAX_COLOR_ATTACHMENTS and GL_MAX_DRAW_BUFFERS.
+	pub max_color_attachments: u32,
+	/// GL_MAX_SAMPLES, the largest `TextureParams::sample_count`.
+	pub max_samples: u32,
+	/// GL_MAX_VERTEX_ATTRIBS, capped at `MAX_VERTEX_ATTRIBUTES`.
+	pub max_vertex_attributes: u32,
+	/// GL_MAX_VERTEX_UNIFORM_VECTORS, vec4s of uniforms the vertex shader can use.
+	pub max_vertex_uniform_vectors: u32,
+	/// GL_MAX_FRAGMENT_UNIFORM_VECTORS, vec4s of uniforms the fragment shader can use.
+	pub max_fragment_uniform_vectors: u32,
+	/// GL_MAX_TEXTURE_IMAGE_UNITS, capped at `MAX_SHADERSTAGE_IMAGES`.
+	pub max_texture_units: u32,
+	/// GL_MAX_TEXTURE_MAX_ANISOTROPY_EXT, 1 without `Features::anisotropic_filtering`.
+	pub max_anisotropy: u32,
+}
+
 /// Specify whether front- or back-facing polygons can be culled.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum CullFace {
@@ -615,7 +1072,12 @@ pub enum BlendFactor {
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum PrimitiveType {
 	Triangles,
+	TriangleStrip,
+	TriangleFan,
 	Lines,
+	LineStrip,
+	/// Same as LineStrip, with the last vertex connected back to the first one
+	LineLoop,
 	Points,
 }
 
@@ -623,7 +1085,11 @@ impl From<PrimitiveType> for GLenum {
 	fn from(primitive_type: PrimitiveType) -> Self {
 		match primitive_type {
 			PrimitiveType::Triangles => GL_TRIANGLES,
+			PrimitiveType::TriangleStrip => GL_TRIANGLE_STRIP,
+			PrimitiveType::TriangleFan => GL_TRIANGLE_FAN,
 			PrimitiveType::Lines => GL_LINES,
+			PrimitiveType::LineStrip => GL_LINE_STRIP,
+			PrimitiveType::LineLoop => GL_LINE_LOOP,
 			PrimitiveType::Points => GL_POINTS,
 		}
 	}
@@ -674,11 +1140,18 @@ pub struct PipelineParams {
 	pub alpha_blend: Option<BlendState>,
 	pub stencil_test: Option<StencilState>,
 	pub color_write: ColorMask,
+	/// Blend state and write mask of each color attachment of the render pass,
+	/// `None` uses `color_blend`, `alpha_blend` and `color_write`.
+	/// Attachments with different states need `Features::independent_blend`.
+	pub color_targets: [Option<ColorTargetState>; MAX_COLOR_ATTACHMENTS],
 	pub primitive_type: PrimitiveType,
+	/// Size `PrimitiveType::Points` with `gl_PointSize` written by the vertex shader.
+	/// Otherwise points are 1 pixel big on desktop GL, GLES and WebGL always use `gl_PointSize`.
+	pub program_point_size: bool,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
-pub struct Pipeline(usize);
+pub struct Pipeline(ResourceId);
 
 impl Default for PipelineParams {
 	fn default() -> PipelineParams {
@@ -692,11 +1165,85 @@ impl Default for PipelineParams {
 			alpha_blend: None,
 			stencil_test: None,
 			color_write: (true, true, true, true),
+			color_targets: [None; MAX_COLOR_ATTACHMENTS],
 			primitive_type: PrimitiveType::Triangles,
+			program_point_size: false,
+		}
+	}
+}
+
+impl PipelineParams {
+	/// The state used for the color attachment, `color_targets` or the pipeline wide one.
+	pub fn color_target(&self, attachment: usize) -> ColorTargetState {
+		self.color_targets.get(attachment).copied().flatten().unwrap_or(ColorTargetState {
+			color_blend: self.color_blend,
+			alpha_blend: self.alpha_blend,
+			color_write: self.color_write,
+		})
+	}
+
+	/// `GraphicsError::InvalidParams` unless a context with these limits and features can draw with the blend states
+	pub(crate) fn check(&self, limits: &Limits, features: &Features) -> Result<(), GraphicsError> {
+		let count = (limits.max_color_attachments as usize).min(MAX_COLOR_ATTACHMENTS);
+		if self.color_targets[count..].iter().any(Option::is_some) {
+			return Err(GraphicsError::InvalidParams("color_targets past Limits::max_color_attachments"));
+		}
+		let targets: Vec<ColorTargetState> = (0..count).map(|i| self.color_target(i)).collect();
+		if targets.iter().any(|target| target.color_blend.is_none() && target.alpha_blend.is_some()) {
+			return Err(GraphicsError::InvalidParams("AlphaBlend without ColorBlend"));
+		}
+		if !features.independent_blend && targets.iter().any(|target| *target != targets[0]) {
+			return Err(GraphicsError::InvalidParams("Different color_targets per attachment need Features::independent_blend"));
+		}
+		Ok(())
+	}
+}
+
+/// Blend state and write mask of a single color attachment, see `PipelineParams::color_targets`.
+/// ```
+/// # use miniquad_wasm_bindgen::*;
+/// let mut ctx = NullContext::new();
+/// # let shader = ctx.new_test_shader(ShaderMeta::default());
+/// # let albedo = ctx.new_render_texture(TextureParams { width: 4, height: 4, ..Default::default() });
+/// # let light = ctx.new_render_texture(TextureParams { width: 4, height: 4, ..Default::default() });
+/// let additive = BlendState::new(Equation::Add, BlendFactor::One, BlendFactor::One);
+/// let mut color_targets = [None; MAX_COLOR_ATTACHMENTS];
+/// // accumulate lighting into the second attachment, keep the first one read-only
+/// color_targets[0] = Some(ColorTargetState { color_write: (false, false, false, false), ..Default::default() });
+/// color_targets[1] = Some(ColorTargetState { color_blend: Some(additive), ..Default::default() });
+///
+/// assert!(ctx.info().features.independent_blend);
+/// let pipeline = ctx.new_pipeline(&[], &[], shader, PipelineParams { color_targets, ..Default::default() });
+///
+/// let pass = ctx.new_render_pass_mrt(&[albedo, light], &[], None);
+/// ctx.begin_pass(Some(pass), PassAction::ClearAttachments { colors: vec![None, Some((0.0, 0.0, 0.0, 0.0))], depth: None, stencil: None });
+/// ctx.apply_pipeline(&pipeline);
+/// ctx.end_render_pass();
+/// ```
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct ColorTargetState {
+	pub color_blend: Option<BlendState>,
+	pub alpha_blend: Option<BlendState>,
+	pub color_write: ColorMask,
+}
+
+impl Default for ColorTargetState {
+	fn default() -> ColorTargetState {
+		ColorTargetState {
+			color_blend: None,
+			alpha_blend: None,
+			color_write: (true, true, true, true),
 		}
 	}
 }
 
+/// A slice of the index buffer, see `RenderingBackend::draw_multi`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DrawRange {
+	pub base_element: i32,
+	pub num_elements: i32,
+}
+
 /// Geometry bindings
 #[derive(Clone, Debug)]
 pub struct Bindings {
@@ -710,16 +1257,23 @@ pub struct Bindings {
 	/// Index buffer which instructs the GPU in which order to draw vertices
 	/// from a vertex buffer, with each subsequent 3 indices forming a
 	/// triangle.
-	pub index_buffer: BufferId,
+	///
+	/// Without one `draw` takes the vertices in order, as `draw_arrays`.
+	pub index_buffer: Option<BufferId>,
 	/// Textures to be used with when drawing the geometry in the fragment
 	/// shader.
 	pub images: Vec<TextureId>,
+	/// Samplers of the `images` with the same index, `None` or a missing entry samples
+	/// the texture with its own `TextureParams`.
+	pub samplers: Vec<Option<SamplerId>>,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum BufferType {
 	VertexBuffer,
 	IndexBuffer,
+	/// Data for `ShaderMeta::uniform_blocks`, see `apply_uniform_buffer`
+	UniformBuffer,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -733,6 +1287,7 @@ fn gl_buffer_target(buffer_type: &BufferType) -> GLenum {
 	match buffer_type {
 		BufferType::VertexBuffer => GL_ARRAY_BUFFER,
 		BufferType::IndexBuffer => GL_ELEMENT_ARRAY_BUFFER,
+		BufferType::UniformBuffer => GL_UNIFORM_BUFFER,
 	}
 }
 
@@ -745,7 +1300,11 @@ fn gl_usage(usage: &BufferUsage) -> GLenum {
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
-pub struct BufferId(usize);
+pub struct BufferId(ResourceId);
+
+/// Handle of a readback started with `texture_read_pixels_async` or `default_pass_read_pixels_async`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct ReadbackId(ResourceId);
 
 /// `ElapsedQuery` is used to measure duration of GPU operations.
 ///
@@ -886,11 +1445,78 @@ pub struct Arg<'a> {
 #[derive(Debug)]
 pub enum TextureSource<'a> {
 	Empty,
+	/// For `Texture2DArray` and `Texture3D`, all the layers one after another.
 	Bytes(&'a [u8]),
-	/// Array of `[cubemap_face][mipmap_level][bytes]`
+	/// Array of `[cubemap_face][mipmap_level][bytes]`, or `[layer][mipmap_level][bytes]` for `Texture2DArray` and `Texture3D`.
+	/// Mip levels of a `Texture3D` have less slices, level `n` takes its `depth >> n` slices from the first layers.
 	Array(&'a [&'a [&'a [u8]]]),
 }
 
+impl TextureSource<'_> {
+	pub(crate) fn size(&self) -> usize {
+		match self {
+			TextureSource::Empty => 0,
+			TextureSource::Bytes(bytes) => bytes.len(),
+			TextureSource::Array(array) => array.iter().flat_map(|mips| mips.iter()).map(|mip| mip.len()).sum(),
+		}
+	}
+
+	/// `GraphicsError::InvalidParams` unless a texture `access` allows can be made of the source and `params`,
+	/// then `check_size`
+	pub(crate) fn check(&self, access: TextureAccess, params: &TextureParams) -> Result<(), GraphicsError> {
+		let invalid = |condition: bool, reason| if condition { Err(GraphicsError::InvalidParams(reason)) } else { Ok(()) };
+		invalid(access != TextureAccess::RenderTarget && params.sample_count != 0, "Multisampling is only supported for render textures")?;
+		invalid(params.compare.is_some() && !params.format.is_depth(), "Only depth textures can be compared")?;
+		invalid(access == TextureAccess::RenderTarget && params.kind.is_layered(), "Texture2DArray and Texture3D can not be render targets")?;
+		invalid(params.format.is_compressed() && matches!(self, TextureSource::Empty), "Compressed textures can not be empty")?;
+		match self {
+			TextureSource::Empty => {}
+			TextureSource::Bytes(_) => invalid(params.kind == TextureKind::CubeMap, "Cubemaps require TextureSource::Array of 6 textures")?,
+			TextureSource::Array(array) => {
+				let layers = match params.kind {
+					TextureKind::Texture2D => 1,
+					TextureKind::CubeMap => 6,
+					TextureKind::Texture2DArray | TextureKind::Texture3D => params.depth as usize,
+				};
+				invalid(array.len() != layers, "TextureSource::Array needs 1 texture, 6 cubemap faces or `depth` layers")?;
+				invalid(
+					array.iter().any(|mipmaps| mipmaps.is_empty() || mipmaps.len() != array[0].len()),
+					"All the layers should have the same mip levels, at least one",
+				)?;
+			}
+		}
+		self.check_size(params)
+	}
+
+	/// `GraphicsError::TextureDataSize` unless the bytes match the texture
+	fn check_size(&self, params: &TextureParams) -> Result<(), GraphicsError> {
+		let check = |expected: u32, found: usize| match expected as usize == found {
+			true => Ok(()),
+			false => Err(GraphicsError::TextureDataSize { expected: expected as usize, found }),
+		};
+		let mip_size = |size: u32, level: usize| (size >> level).max(1);
+		match self {
+			TextureSource::Empty => Ok(()),
+			TextureSource::Bytes(bytes) => {
+				let layers = if params.kind.is_layered() { params.depth } else { 1 };
+				check(params.format.size(params.width, params.height) * layers, bytes.len())
+			}
+			TextureSource::Array(array) => {
+				for (layer, mipmaps) in array.iter().enumerate() {
+					for (level, bytes) in mipmaps.iter().enumerate() {
+						// mip levels of a Texture3D have less slices, the rest is ignored
+						if params.kind == TextureKind::Texture3D && layer as u32 >= mip_size(params.depth, level) {
+							continue;
+						}
+						check(params.format.size(mip_size(params.width, level), mip_size(params.height, level)), bytes.len())?;
+					}
+				}
+				Ok(())
+			}
+		}
+	}
+}
+
 pub enum BufferSource<'a> {
 	Slice(Arg<'a>),
 	Empty { size: usize, element_size: usize },
@@ -901,7 +1527,7 @@ impl<'a> BufferSource<'a> {
 	/// Platform specific note, OpenGL:
 	/// For VertexBuffer T could be anything, it is only used to calculate total size,
 	/// but for IndexBuffers T should be either u8, u16 or u32, other
-	/// types are not supported.
+	/// types fail with `GraphicsError::UnsupportedIndexSize`.
 	///
 	/// For vertex buffers it is OK to use `empty::<u8>(byte_size);`
 	pub fn empty<T>(size: usize) -> BufferSource<'a> {
@@ -912,6 +1538,7 @@ impl<'a> BufferSource<'a> {
 		}
 	}
 
+	/// For IndexBuffers the size of `T` is the index type, so indices should be a flat slice of u8, u16 or u32.
 	pub fn slice<T>(data: &'a [T]) -> BufferSource<'a> {
 		BufferSource::Slice(Arg {
 			ptr: data.as_ptr() as _,
@@ -970,6 +1597,29 @@ pub struct GlslSupport {
 	pub v100: bool,
 }
 
+/// Compressed texture formats supported by the context.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TextureCompression {
+	/// `ETC2RGB8` and `ETC2RGBA8`: core in GLES3, WEBGL_compressed_texture_etc on the web.
+	pub etc2: bool,
+	/// `ASTC4x4`, `ASTC6x6` and `ASTC8x8`: KHR_texture_compression_astc_ldr, mostly mobile.
+	pub astc: bool,
+	/// `BC1` and `BC3`: EXT_texture_compression_s3tc, mostly desktop.
+	pub s3tc: bool,
+}
+
+impl TextureCompression {
+	/// Uncompressed formats are always supported.
+	pub fn supports(&self, format: TextureFormat) -> bool {
+		match format {
+			TextureFormat::ETC2RGB8 | TextureFormat::ETC2RGBA8 => self.etc2,
+			TextureFormat::ASTC4x4 | TextureFormat::ASTC6x6 | TextureFormat::ASTC8x8 => self.astc,
+			TextureFormat::BC1 | TextureFormat::BC3 => self.s3tc,
+			_ => true,
+		}
+	}
+}
+
 #[derive(Clone, Debug)]
 pub struct ContextInfo {
 	/// GL_VERSION_STRING from OpenGL.
@@ -980,13 +1630,94 @@ pub struct ContextInfo {
 	///
 	/// miniquad_wasm_bindgen will take a guess based on GL_VERSION_STRING, current platform and implementation details.
 	pub glsl_support: GlslSupport,
+	/// GL_UNIFORM_BUFFER_OFFSET_ALIGNMENT, `apply_uniform_buffer` offsets should be a multiple of it.
+	/// 0 when uniform buffers are not supported (GL2 and GLES2).
+	pub uniform_buffer_offset_alignment: usize,
+	/// Compressed formats `new_texture` accepts, the others fail with `GraphicsError::UnsupportedFormat`.
+	pub texture_compression: TextureCompression,
+	pub features: Features,
+	pub limits: Limits,
+	/// Extensions supported by the context, with their "GL_" prefix on desktop and GLES.
+	pub extensions: Vec<String>,
+}
+
+/// Counters of one frame, see `RenderingBackend::frame_stats`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FrameStats {
+	/// Draw calls sent to the driver, a `draw_multi` drawn with one multi draw call counts once
+	pub draw_calls: u32,
+	/// `apply_pipeline` calls with another pipeline than the applied one
+	pub pipeline_switches: u32,
+	/// Pipeline state, viewport, scissor and bindings sent to GL, each piece of state counts once however many calls it takes
+	pub gl_calls_issued: u32,
+	/// Pipeline state, viewport, scissor and bindings left out because GL already had them
+	pub gl_calls_skipped: u32,
+	/// Buffer and texture data uploaded
+	pub bytes_uploaded: usize,
 }
 
 pub trait RenderingBackend: Send {
 	fn info(&self) -> ContextInfo;
 	/// If in doubt, _most_ OpenGL contexts support "#version 100" glsl shaders.
 	fn new_shader(&mut self, shader: ShaderSource, meta: ShaderMeta) -> Result<ShaderId, ShaderError>;
-	fn new_texture(&mut self, access: TextureAccess, data: TextureSource, params: TextureParams) -> TextureId;
+	/// Compile and link `shader` only to read back its active uniforms, images, uniform blocks and attributes.
+	/// The result's `meta` can be passed to `new_shader`, or check a handwritten one with `ShaderReflection::validate`.
+	/// Fails with `ShaderError::MetaMismatch` listing the uniforms and attributes of types `ShaderMeta` can not describe.
+	///
+	/// ```
+	/// # use miniquad_wasm_bindgen::*;
+	/// # let mut ctx = NullContext::new();
+	/// let vertex = "#version 300 es
+	/// in vec2 in_pos;
+	/// uniform vec2 offset;
+	/// layout(std140) uniform Camera { mat4 view; vec4 viewport; };
+	/// void main() { gl_Position = view * vec4(in_pos + offset, 0.0, 1.0); }";
+	/// # let fragment = "";
+	/// let source = ShaderSource::new(vertex, fragment);
+	/// let reflection = ctx.reflect_shader(source).unwrap();
+	/// assert_eq!(reflection.meta.uniforms.uniforms, [UniformDesc::new("offset", UniformType::Float2)]);
+	/// assert_eq!(reflection.meta.uniform_blocks[0].layout.uniforms[1], UniformDesc::new("viewport", UniformType::Float4));
+	/// assert_eq!(reflection.attributes[0], AttributeDesc { name: "in_pos".to_string(), format: VertexFormat::Float2 });
+	/// let shader = ctx.new_shader(source, reflection.meta).unwrap();
+	/// ```
+	fn reflect_shader(&mut self, shader: ShaderSource) -> Result<ShaderReflection, ShaderError>;
+	/// Compile `shader` and swap it in for the program of `id`, pipelines using `id` draw with it right away.
+	/// On failure the old program is kept and the error returned, so a typo does not break a running app.
+	///
+	/// Attributes keep the locations of the old program: pipelines are not rebuilt and only know the
+	/// attributes they were made with. See `ShaderWatcher` to reload shaders when their files change.
+	///
+	/// ```
+	/// # use miniquad_wasm_bindgen::*;
+	/// # let mut ctx = NullContext::new();
+	/// # let (vertex, fragment, edited) = ("", "", "");
+	/// # let meta = ShaderMeta::default();
+	/// let shader = ctx.new_shader(ShaderSource::new(vertex, fragment), meta.clone()).unwrap();
+	/// let pipeline = ctx.new_pipeline(&[BufferLayout::default()], &[VertexAttribute::new("in_pos", VertexFormat::Float2)], shader, PipelineParams::default());
+	/// if let Err(err) = ctx.reload_shader(shader, ShaderSource::new(vertex, edited), meta) {
+	///     eprintln!("{}", err); // still drawing with the old program
+	/// }
+	/// ctx.apply_pipeline(&pipeline);
+	/// ```
+	fn reload_shader(&mut self, id: ShaderId, shader: ShaderSource, meta: ShaderMeta) -> Result<(), ShaderError>;
+	fn new_texture(&mut self, access: TextureAccess, data: TextureSource, params: TextureParams) -> TextureId {
+		self.try_new_texture(access, data, params).unwrap_or_else(|err| panic!("new_texture failed: {}", err))
+	}
+	/// Same as "new_texture", but reports running out of memory, an unsupported format,
+	/// invalid parameters or bytes not matching the size of the texture instead of panicking.
+	///
+	/// ```
+	/// # use miniquad_wasm_bindgen::*;
+	/// # let mut ctx = NullContext::new();
+	/// let params = TextureParams { width: 2, height: 2, ..Default::default() };
+	/// let result = ctx.try_new_texture(TextureAccess::Static, TextureSource::Bytes(&[0; 5]), params);
+	/// assert_eq!(result.unwrap_err(), GraphicsError::TextureDataSize { expected: 16, found: 5 });
+	///
+	/// let compared = TextureParams { compare: Some(Comparison::Less), ..params };
+	/// let result = ctx.try_new_texture(TextureAccess::Static, TextureSource::Empty, compared);
+	/// assert_eq!(result.unwrap_err(), GraphicsError::InvalidParams("Only depth textures can be compared"));
+	/// ```
+	fn try_new_texture(&mut self, access: TextureAccess, data: TextureSource, params: TextureParams) -> Result<TextureId, GraphicsError>;
 	fn new_render_texture(&mut self, params: TextureParams) -> TextureId {
 		self.new_texture(TextureAccess::RenderTarget, TextureSource::Empty, params)
 	}
@@ -1002,6 +1733,7 @@ pub trait RenderingBackend: Send {
 				kind: TextureKind::Texture2D,
 				width: width as _,
 				height: height as _,
+				depth: 1,
 				sample_count: 0,
 				format: TextureFormat::RGBA8,
 				wrap: TextureWrap::Clamp,
@@ -1009,9 +1741,26 @@ pub trait RenderingBackend: Send {
 				mag_filter: FilterMode::Linear,
 				mipmap_filter: MipmapFilterMode::None,
 				allocate_mipmaps: false,
+				compare: None,
 			},
 		)
 	}
+	/// Decode a PNG or QOI image with `decode_image` and create a static RGBA8 texture from it.
+	///
+	/// ```
+	/// # use miniquad_wasm_bindgen::*;
+	/// let mut ctx = NullContext::new();
+	/// // 1x1 QOI image: header, a single red RGBA pixel, end marker
+	/// let qoi = [b"qoif", &[0, 0, 0, 1, 0, 0, 0, 1, 4, 0][..], &[0xFF, 255, 0, 0, 255], &[0, 0, 0, 0, 0, 0, 0, 1]].concat();
+	///
+	/// let texture = ctx.new_texture_from_encoded(&qoi).unwrap();
+	/// assert_eq!(ctx.texture_size(texture), (1, 1));
+	/// ```
+	#[cfg(feature = "image-decode")]
+	fn new_texture_from_encoded(&mut self, bytes: &[u8]) -> Result<TextureId, ImageError> {
+		let image = decode_image(bytes)?;
+		Ok(self.new_texture_from_data_and_format(&image.bytes, image.params))
+	}
 	fn texture_params(&self, texture: TextureId) -> TextureParams;
 	fn texture_size(&self, texture: TextureId) -> (u32, u32) {
 		let params = self.texture_params(texture);
@@ -1036,13 +1785,113 @@ pub trait RenderingBackend: Send {
 	fn texture_set_wrap(&mut self, texture: TextureId, wrap_x: TextureWrap, wrap_y: TextureWrap);
 	fn texture_generate_mipmaps(&mut self, texture: TextureId);
 	fn texture_resize(&mut self, texture: TextureId, width: u32, height: u32, bytes: Option<&[u8]>);
+	/// Sample textures in `Bindings::samplers` with these parameters instead of their own.
+	/// Requires `Features::samplers`.
+	///
+	/// ```
+	/// # use miniquad_wasm_bindgen::*;
+	/// let mut ctx = NullContext::new();
+	/// # let vertex_buffer = ctx.new_buffer(BufferType::VertexBuffer, BufferUsage::Immutable, BufferSource::slice(&[0f32; 6]));
+	/// # let index_buffer = ctx.new_buffer(BufferType::IndexBuffer, BufferUsage::Immutable, BufferSource::slice(&[0u16, 1, 2]));
+	/// # let meta = ShaderMeta { images: vec![ImageDesc::new("sharp", ImageType::Float), ImageDesc::new("smooth", ImageType::Float)], ..Default::default() };
+	/// # let pipeline = ctx.new_test_pipeline(meta, PipelineParams::default());
+	/// let texture = ctx.new_texture_from_rgba8(1, 1, &[255; 4]);
+	/// let nearest = ctx.new_sampler(SamplerParams {
+	///     min_filter: FilterMode::Nearest,
+	///     mag_filter: FilterMode::Nearest,
+	///     wrap_x: TextureWrap::Repeat,
+	///     wrap_y: TextureWrap::Repeat,
+	///     ..Default::default()
+	/// });
+	///
+	/// // the same texture sampled two ways, the second image uses the texture's own parameters
+	/// ctx.begin_default_pass(PassAction::Nothing);
+	/// ctx.apply_pipeline(&pipeline);
+	/// ctx.apply_bindings(&Bindings {
+	///     vertex_buffers: vec![vertex_buffer],
+	///     index_buffer: Some(index_buffer),
+	///     images: vec![texture, texture],
+	///     samplers: vec![Some(nearest)],
+	/// });
+	/// ctx.end_render_pass();
+	///
+	/// ctx.delete_sampler(nearest);
+	/// assert!(ctx.try_delete_sampler(nearest).is_err());
+	/// ```
+	fn new_sampler(&mut self, params: SamplerParams) -> SamplerId;
+	/// Read the texture into `bytes`, the first layer of a `Texture2DArray` or `Texture3D`.
+	/// Pixels are in the texture's format, `bytes` should be `TextureFormat::size` long.
+	/// Depth formats can not be read back, GLES3 and WebGL2 only read color attachments.
 	fn texture_read_pixels(&mut self, texture: TextureId, bytes: &mut [u8]);
+	/// Read the default framebuffer (the window, or the offscreen surface with
+	/// `LinuxBackend::Headless`) into `bytes` as RGBA8, rows bottom to top.
+	/// `bytes` should be at least `screen_width * screen_height * 4` long.
+	fn default_pass_read_pixels(&mut self, bytes: &mut [u8]);
+	/// Same as `texture_read_pixels`, but does not wait for the GPU to finish rendering into the texture.
+	/// The pixels are copied aside on the GPU, poll the returned handle with `readback_poll` in the next frames.
+	///
+	/// GL2 and GLES2 have no pixel buffers and fences, the pixels are read right away there.
+	fn texture_read_pixels_async(&mut self, texture: TextureId) -> ReadbackId;
+	/// Same as `default_pass_read_pixels`, but does not wait for the GPU, see `texture_read_pixels_async`.
+	fn default_pass_read_pixels_async(&mut self) -> ReadbackId;
+	/// Size of the readback's pixels in bytes, the smallest `bytes` `readback_poll` accepts.
+	fn readback_size(&self, readback: ReadbackId) -> usize;
+	/// Copy the pixels into `bytes` and delete the readback if the GPU is done with it,
+	/// otherwise return false and keep it pending.
+	///
+	/// ```
+	/// # use miniquad_wasm_bindgen::*;
+	/// # let mut ctx = NullContext::new();
+	/// let texture = ctx.new_texture_from_rgba8(1, 1, &[255, 0, 0, 255]);
+	/// let readback = ctx.texture_read_pixels_async(texture);
+	///
+	/// let mut pixels = vec![0; ctx.readback_size(readback)];
+	/// while !ctx.readback_poll(readback, &mut pixels) {
+	///     // render the next frame
+	///     ctx.commit_frame();
+	/// }
+	/// assert_eq!(pixels, [255, 0, 0, 255]);
+	/// ```
+	fn readback_poll(&mut self, readback: ReadbackId, bytes: &mut [u8]) -> bool;
+	/// Drop a readback without waiting for it to finish.
+	fn delete_readback(&mut self, readback: ReadbackId);
 	fn texture_update_part(&mut self, texture: TextureId, x_offset: i32, y_offset: i32, width: i32, height: i32, bytes: &[u8]);
+	/// Same as `texture_update_part`, but for one layer of a `Texture2DArray` or one slice of a `Texture3D`.
+	/// `texture_update_part` updates the layer 0.
+	fn texture_update_layer_part(&mut self, texture: TextureId, layer: i32, x_offset: i32, y_offset: i32, width: i32, height: i32, bytes: &[u8]);
+	/// Copy the `(x, y, width, height)` rectangle of `src` to `dst_offset` in `dst` on the GPU, with `glCopyTexSubImage2D`.
+	/// Both textures should be `Texture2D`s of the same color format, `src` should not be multisampled.
+	///
+	/// ```
+	/// # use miniquad_wasm_bindgen::*;
+	/// # let mut ctx = NullContext::new();
+	/// let atlas = ctx.new_texture_from_rgba8(2, 1, &[255, 0, 0, 255, 0, 255, 0, 255]);
+	/// let sprite = ctx.new_texture_from_rgba8(1, 1, &[0; 4]);
+	/// ctx.copy_texture_region(atlas, sprite, (1, 0, 1, 1), (0, 0));
+	///
+	/// let mut pixels = [0; 4];
+	/// ctx.texture_read_pixels(sprite, &mut pixels);
+	/// assert_eq!(pixels, [0, 255, 0, 255]);
+	/// ```
+	fn copy_texture_region(&mut self, src: TextureId, dst: TextureId, src_rect: (i32, i32, i32, i32), dst_offset: (i32, i32));
 	fn new_render_pass(&mut self, color_img: TextureId, depth_img: Option<TextureId>) -> RenderPass {
 		self.new_render_pass_mrt(&[color_img], &[], depth_img)
 	}
 	/// Same as "new_render_pass", but allows multiple color attachments.
-	fn new_render_pass_mrt(&mut self, color_img: &[TextureId], resolve_img: &[TextureId], depth_img: Option<TextureId>) -> RenderPass;
+	fn new_render_pass_mrt(&mut self, color_img: &[TextureId], resolve_img: &[TextureId], depth_img: Option<TextureId>) -> RenderPass {
+		self.try_new_render_pass_mrt(color_img, resolve_img, depth_img)
+			.unwrap_or_else(|err| panic!("new_render_pass failed: {}", err))
+	}
+	/// Same as "new_render_pass_mrt", but reports an incomplete framebuffer or deleted attachments instead of panicking.
+	///
+	/// ```
+	/// # use miniquad_wasm_bindgen::*;
+	/// # let mut ctx = NullContext::new();
+	/// let depth = ctx.new_render_texture(TextureParams { format: TextureFormat::Depth, ..Default::default() });
+	/// // GL_FRAMEBUFFER_INCOMPLETE_ATTACHMENT
+	/// assert_eq!(ctx.try_new_render_pass_mrt(&[depth], &[], None).unwrap_err(), GraphicsError::IncompleteFramebuffer(0x8CD6));
+	/// ```
+	fn try_new_render_pass_mrt(&mut self, color_img: &[TextureId], resolve_img: &[TextureId], depth_img: Option<TextureId>) -> Result<RenderPass, GraphicsError>;
 	/// panics for depth-only or multiple color attachment render pass
 	/// This function is, mostly, legacy. Using "render_pass_color_attachments"
 	/// is recommended instead.
@@ -1058,10 +1907,50 @@ pub trait RenderingBackend: Send {
 	}
 	/// For depth-only render pass returns empty slice.
 	fn render_pass_color_attachments(&self, render_pass: RenderPass) -> &[TextureId];
-	fn delete_render_pass(&mut self, render_pass: RenderPass);
-	fn new_pipeline(&mut self, buffer_layout: &[BufferLayout], attributes: &[VertexAttribute], shader: ShaderId, params: PipelineParams) -> Pipeline;
+	fn delete_render_pass(&mut self, render_pass: RenderPass) {
+		self.try_delete_render_pass(render_pass).unwrap_or_else(|err| panic!("delete_render_pass failed: {}", err))
+	}
+	/// Delete the pass together with its color and depth attachments.
+	///
+	/// ```
+	/// # use miniquad_wasm_bindgen::*;
+	/// # let mut ctx = NullContext::new();
+	/// let color = ctx.new_render_texture(TextureParams { width: 4, height: 4, ..Default::default() });
+	/// let depth = ctx.new_render_texture(TextureParams { width: 4, height: 4, format: TextureFormat::Depth, ..Default::default() });
+	/// let pass = ctx.new_render_pass(color, Some(depth));
+	///
+	/// ctx.try_delete_render_pass(pass).unwrap();
+	/// assert!(ctx.try_delete_texture(color).is_err());
+	/// assert!(ctx.try_delete_texture(depth).is_err());
+	/// ```
+	fn try_delete_render_pass(&mut self, render_pass: RenderPass) -> Result<(), GraphicsError>;
+	/// Copy the `(x, y, width, height)` rectangle of the first color attachment of `src` to `dst_rect` of the color attachments of `dst`
+	/// with `glBlitFramebuffer`, scaling with `filter` if the sizes differ. `None` is the default framebuffer.
+	///
+	/// Called outside of render passes. A multisampled `src` is resolved, which needs both rectangles of the same size.
+	/// Multisampled `dst`s can not be blitted into, that includes the default framebuffer with `Conf::sample_count` above 1 on most drivers.
+	///
+	/// ```
+	/// # use miniquad_wasm_bindgen::*;
+	/// # let mut ctx = NullContext::new();
+	/// let scene = ctx.new_render_texture(TextureParams { width: 320, height: 180, ..Default::default() });
+	/// let scene_pass = ctx.new_render_pass(scene, None);
+	/// // render the scene at a low resolution, then scale it up to the window
+	/// ctx.begin_pass(Some(scene_pass), PassAction::clear_color(0.0, 0.0, 0.0, 1.0));
+	/// ctx.end_render_pass();
+	/// ctx.blit_pass(Some(scene_pass), None, (0, 0, 320, 180), (0, 0, 1280, 720), FilterMode::Nearest);
+	/// ```
+	fn blit_pass(&mut self, src: Option<RenderPass>, dst: Option<RenderPass>, src_rect: (i32, i32, i32, i32), dst_rect: (i32, i32, i32, i32), filter: FilterMode);
+	fn new_pipeline(&mut self, buffer_layout: &[BufferLayout], attributes: &[VertexAttribute], shader: ShaderId, params: PipelineParams) -> Pipeline {
+		self.try_new_pipeline(buffer_layout, attributes, shader, params).unwrap_or_else(|err| panic!("new_pipeline failed: {}", err))
+	}
+	/// Same as "new_pipeline", but reports a deleted shader or invalid parameters instead of panicking.
+	fn try_new_pipeline(&mut self, buffer_layout: &[BufferLayout], attributes: &[VertexAttribute], shader: ShaderId, params: PipelineParams) -> Result<Pipeline, GraphicsError>;
 	fn apply_pipeline(&mut self, pipeline: &Pipeline);
-	fn delete_pipeline(&mut self, pipeline: Pipeline);
+	fn delete_pipeline(&mut self, pipeline: Pipeline) {
+		self.try_delete_pipeline(pipeline).unwrap_or_else(|err| panic!("delete_pipeline failed: {}", err))
+	}
+	fn try_delete_pipeline(&mut self, pipeline: Pipeline) -> Result<(), GraphicsError>;
 
 	/// Create a buffer resource object.
 	/// ```ignore
@@ -1082,7 +1971,19 @@ pub trait RenderingBackend: Send {
 	///        BufferSource::slice(&vertices),
 	///    );
 	/// ```
-	fn new_buffer(&mut self, type_: BufferType, usage: BufferUsage, data: BufferSource) -> BufferId;
+	fn new_buffer(&mut self, type_: BufferType, usage: BufferUsage, data: BufferSource) -> BufferId {
+		self.try_new_buffer(type_, usage, data).unwrap_or_else(|err| panic!("new_buffer failed: {}", err))
+	}
+	/// Same as "new_buffer", but reports running out of memory or an index size that is not 1, 2 or 4 bytes instead of panicking.
+	///
+	/// ```
+	/// # use miniquad_wasm_bindgen::*;
+	/// # let mut ctx = NullContext::new();
+	/// let triangles = [[0u16, 1, 2], [2, 3, 0]];
+	/// let result = ctx.try_new_buffer(BufferType::IndexBuffer, BufferUsage::Immutable, BufferSource::slice(&triangles));
+	/// assert!(matches!(result, Err(GraphicsError::UnsupportedIndexSize(6))));
+	/// ```
+	fn try_new_buffer(&mut self, type_: BufferType, usage: BufferUsage, data: BufferSource) -> Result<BufferId, GraphicsError>;
 	fn buffer_update(&mut self, buffer: BufferId, data: BufferSource);
 
 	/// Size of buffer in bytes.
@@ -1094,27 +1995,56 @@ pub trait RenderingBackend: Send {
 	/// More high-level code on top of miniquad_wasm_bindgen probably is going to call this in Drop implementation of some
 	/// more RAII buffer object.
 	///
-	/// There is no protection against using deleted buffers later. However its not an UB in OpenGl and thats why
-	/// this function is not marked as unsafe
-	fn delete_buffer(&mut self, buffer: BufferId);
+	/// Handles are generational: using the buffer after deletion panics (or returns `GraphicsError::StaleId`
+	/// from the `try_*` functions) instead of touching whatever was created in its place.
+	fn delete_buffer(&mut self, buffer: BufferId) {
+		self.try_delete_buffer(buffer).unwrap_or_else(|err| panic!("delete_buffer failed: {}", err))
+	}
+	/// Same as "delete_buffer", but reports an already deleted buffer instead of panicking.
+	///
+	/// ```
+	/// # use miniquad_wasm_bindgen::*;
+	/// let mut ctx = NullContext::new();
+	/// let buffer = ctx.new_buffer(BufferType::VertexBuffer, BufferUsage::Immutable, BufferSource::slice(&[0u8; 4]));
+	///
+	/// ctx.delete_buffer(buffer);
+	/// assert!(matches!(ctx.try_delete_buffer(buffer), Err(GraphicsError::StaleId { kind: "BufferId", .. })));
+	/// ```
+	fn try_delete_buffer(&mut self, buffer: BufferId) -> Result<(), GraphicsError>;
 
 	/// Delete GPU texture, leaving handle unmodified.
 	///
 	/// More high-level code on top of miniquad_wasm_bindgen probably is going to call this in Drop implementation of some
 	/// more RAII buffer object.
 	///
-	/// There is no protection against using deleted textures later. However its not a CPU-level UB and thats why
-	/// this function is not marked as unsafe
-	fn delete_texture(&mut self, texture: TextureId);
+	/// Handles are generational: using the texture after deletion panics (or returns `GraphicsError::StaleId`
+	/// from the `try_*` functions) instead of touching whatever was created in its place.
+	/// Textures made with `TextureId::from_raw_id` are not tracked.
+	fn delete_texture(&mut self, texture: TextureId) {
+		self.try_delete_texture(texture).unwrap_or_else(|err| panic!("delete_texture failed: {}", err))
+	}
+	/// Same as "delete_texture", but reports an already deleted texture instead of panicking.
+	fn try_delete_texture(&mut self, texture: TextureId) -> Result<(), GraphicsError>;
 
 	/// Delete GPU program, leaving handle unmodified.
 	///
 	/// More high-level code on top of miniquad probably is going to call this in Drop implementation of some
 	/// more RAII buffer object.
 	///
-	/// There is no protection against using deleted programs later. However its not a CPU-level Porgram and thats why
-	/// this function is not marked as unsafe
-	fn delete_shader(&mut self, program: ShaderId);
+	/// Handles are generational: using the program after deletion panics (or returns `GraphicsError::StaleId`
+	/// from the `try_*` functions) instead of touching whatever was created in its place.
+	fn delete_shader(&mut self, program: ShaderId) {
+		self.try_delete_shader(program).unwrap_or_else(|err| panic!("delete_shader failed: {}", err))
+	}
+	/// Same as "delete_shader", but reports an already deleted shader instead of panicking.
+	fn try_delete_shader(&mut self, program: ShaderId) -> Result<(), GraphicsError>;
+
+	/// Delete a sampler made with `new_sampler`, textures using it go back to their own parameters.
+	fn delete_sampler(&mut self, sampler: SamplerId) {
+		self.try_delete_sampler(sampler).unwrap_or_else(|err| panic!("delete_sampler failed: {}", err))
+	}
+	/// Same as "delete_sampler", but reports an already deleted sampler instead of panicking.
+	fn try_delete_sampler(&mut self, sampler: SamplerId) -> Result<(), GraphicsError>;
 
 	/// Set a new viewport rectangle.
 	/// Should be applied after begin_pass.
@@ -1124,16 +2054,23 @@ pub trait RenderingBackend: Send {
 	/// Should be applied after begin_pass.
 	fn apply_scissor_rect(&mut self, x: i32, y: i32, w: i32, h: i32);
 
-	fn apply_bindings_from_slice(&mut self, vertex_buffers: &[BufferId], index_buffer: BufferId, textures: &[TextureId]);
+	fn apply_bindings_from_slice(&mut self, vertex_buffers: &[BufferId], index_buffer: Option<BufferId>, textures: &[TextureId], samplers: &[Option<SamplerId>]);
 
 	fn apply_bindings(&mut self, bindings: &Bindings) {
-		self.apply_bindings_from_slice(&bindings.vertex_buffers, bindings.index_buffer, &bindings.images);
+		self.apply_bindings_from_slice(&bindings.vertex_buffers, bindings.index_buffer, &bindings.images, &bindings.samplers);
 	}
 
 	fn apply_uniforms(&mut self, uniforms: UniformsSource) {
 		self.apply_uniforms_from_bytes(uniforms.0.ptr as _, uniforms.0.size)
 	}
 	fn apply_uniforms_from_bytes(&mut self, uniform_ptr: *const u8, size: usize);
+	/// Use `size` bytes of a `BufferType::UniformBuffer` buffer, starting at `offset`, for the uniform block
+	/// declared with this `binding` in `ShaderMeta::uniform_blocks`.
+	///
+	/// `size` should cover `UniformBlockLayout::std140_size` of the block and `offset` should be a multiple of
+	/// `ContextInfo::uniform_buffer_offset_alignment`, so one big buffer can hold the uniforms of many draws.
+	/// Should be applied after apply_pipeline.
+	fn apply_uniform_buffer(&mut self, binding: u32, buffer: BufferId, offset: usize, size: usize);
 
 	fn clear(&mut self, color: Option<(f32, f32, f32, f32)>, depth: Option<f32>, stencil: Option<i32>);
 	/// start rendering to the default frame buffer
@@ -1144,6 +2081,29 @@ pub trait RenderingBackend: Send {
 	fn end_render_pass(&mut self);
 
 	fn commit_frame(&mut self);
+	/// Counters of the last frame ended with `commit_frame`, for a performance overlay.
+	///
+	/// ```
+	/// # use miniquad_wasm_bindgen::*;
+	/// # let mut ctx = NullContext::new();
+	/// let pipeline = ctx.new_test_pipeline(ShaderMeta::default(), PipelineParams::default());
+	/// let vertex_buffer = ctx.new_buffer(BufferType::VertexBuffer, BufferUsage::Immutable, BufferSource::slice(&[0.0f32; 6]));
+	/// ctx.begin_default_pass(PassAction::Nothing);
+	/// let bindings = Bindings { vertex_buffers: vec![vertex_buffer], index_buffer: None, images: vec![], samplers: vec![] };
+	/// ctx.apply_pipeline(&pipeline);
+	/// ctx.apply_bindings(&bindings);
+	/// ctx.draw_arrays(0, 3, 1);
+	/// // already applied, not a switch
+	/// ctx.apply_pipeline(&pipeline);
+	/// ctx.apply_bindings(&bindings);
+	/// ctx.draw_arrays(0, 3, 1);
+	/// ctx.end_render_pass();
+	/// ctx.commit_frame();
+	///
+	/// let stats = ctx.frame_stats();
+	/// assert_eq!((stats.draw_calls, stats.pipeline_switches, stats.bytes_uploaded), (2, 1, 24));
+	/// ```
+	fn frame_stats(&self) -> FrameStats;
 
 	/// Draw elements using currently applied bindings and pipeline.
 	///
@@ -1153,5 +2113,48 @@ pub trait RenderingBackend: Send {
 	///
 	/// NOTE: num_instances > 1 might be not supported by the GPU (gl2.1 and gles2).
 	/// `features.instancing` check is required.
+	///
+	/// Without an index buffer in the bindings the elements are the vertices themselves:
+	///
+	/// ```
+	/// # use miniquad_wasm_bindgen::*;
+	/// # let mut ctx = NullContext::new();
+	/// # let pipeline = ctx.new_test_pipeline(ShaderMeta::default(), PipelineParams::default());
+	/// // a fullscreen triangle
+	/// let vertices = [-1f32, -1., 3., -1., -1., 3.];
+	/// let vertex_buffer = ctx.new_buffer(BufferType::VertexBuffer, BufferUsage::Immutable, BufferSource::slice(&vertices));
+	/// ctx.begin_default_pass(PassAction::Nothing);
+	/// ctx.apply_pipeline(&pipeline);
+	/// ctx.apply_bindings(&Bindings { vertex_buffers: vec![vertex_buffer], index_buffer: None, images: vec![], samplers: vec![] });
+	/// ctx.draw(0, 3, 1);
+	/// ctx.end_render_pass();
+	/// ```
 	fn draw(&self, base_element: i32, num_elements: i32, num_instances: i32);
+	/// Same as `draw`, but `base_vertex` is added to every index before the vertex is fetched,
+	/// so meshes packed into one vertex buffer keep their own 16 bit indices.
+	/// Requires `Features::base_vertex` and an index buffer.
+	fn draw_base_vertex(&self, base_element: i32, num_elements: i32, num_instances: i32, base_vertex: i32);
+	/// Draw `num_vertices` vertices from `first_vertex` on, the index buffer is not used.
+	fn draw_arrays(&self, first_vertex: i32, num_vertices: i32, num_instances: i32);
+	/// Draw every range of the index buffer, with a single `glMultiDrawElements` or WEBGL_multi_draw call
+	/// where available and a `draw` per range otherwise.
+	///
+	/// ```
+	/// # use miniquad_wasm_bindgen::*;
+	/// # let mut ctx = NullContext::new();
+	/// # let vertex_buffer = ctx.new_buffer(BufferType::VertexBuffer, BufferUsage::Immutable, BufferSource::slice(&[0f32; 16]));
+	/// # let index_buffer = ctx.new_buffer(BufferType::IndexBuffer, BufferUsage::Immutable, BufferSource::slice(&[0u16, 1, 2, 2, 3, 0, 4, 5, 6, 6, 7, 4]));
+	/// # let pipeline = ctx.new_test_pipeline(ShaderMeta::default(), PipelineParams::default());
+	/// ctx.begin_default_pass(PassAction::Nothing);
+	/// ctx.apply_pipeline(&pipeline);
+	/// ctx.apply_bindings(&Bindings { vertex_buffers: vec![vertex_buffer], index_buffer: Some(index_buffer), images: vec![], samplers: vec![] });
+	/// // two quads out of a sprite batch
+	/// ctx.draw_multi(&[DrawRange { base_element: 0, num_elements: 6 }, DrawRange { base_element: 6, num_elements: 6 }]);
+	/// ctx.end_render_pass();
+	/// ```
+	fn draw_multi(&self, ranges: &[DrawRange]) {
+		for range in ranges {
+			self.draw(range.base_element, range.num_elements, 1);
+		}
+	}
 }
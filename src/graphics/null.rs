@@ -0,0 +1,1297 @@
+//! A `RenderingBackend` that does not talk to any GPU.
+//!
+//! Resources are tracked on the CPU, every call is validated the same way a
+//! GL driver (or `GlContext` assertions) would do, and recorded into a command log.
+
+use std::{
+	cell::{Cell, RefCell},
+	collections::HashMap,
+};
+
+use crate::ResourceManager;
+
+use super::reflection::{attribute_format, image_type, uniform_type};
+use super::*;
+
+/// One recorded `RenderingBackend` call, see `NullContext::commands`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NullCommand {
+	NewShader(ShaderId),
+	ReloadShader(ShaderId),
+	NewTexture {
+		texture: TextureId,
+		params: TextureParams,
+	},
+	NewRenderPass(RenderPass),
+	NewPipeline(Pipeline),
+	NewBuffer {
+		buffer: BufferId,
+		type_: BufferType,
+		usage: BufferUsage,
+		size: usize,
+	},
+	BufferUpdate {
+		buffer: BufferId,
+		size: usize,
+	},
+	TextureUpdatePart {
+		texture: TextureId,
+		x_offset: i32,
+		y_offset: i32,
+		width: i32,
+		height: i32,
+	},
+	TextureUpdateLayerPart {
+		texture: TextureId,
+		layer: i32,
+		x_offset: i32,
+		y_offset: i32,
+		width: i32,
+		height: i32,
+	},
+	TextureResize {
+		texture: TextureId,
+		width: u32,
+		height: u32,
+	},
+	TextureSetMinFilter {
+		texture: TextureId,
+		filter: FilterMode,
+		mipmap_filter: MipmapFilterMode,
+	},
+	TextureSetMagFilter {
+		texture: TextureId,
+		filter: FilterMode,
+	},
+	TextureSetWrap {
+		texture: TextureId,
+		wrap_x: TextureWrap,
+		wrap_y: TextureWrap,
+	},
+	TextureGenerateMipmaps(TextureId),
+	CopyTextureRegion {
+		src: TextureId,
+		dst: TextureId,
+		src_rect: (i32, i32, i32, i32),
+		dst_offset: (i32, i32),
+	},
+	NewSampler {
+		sampler: SamplerId,
+		params: SamplerParams,
+	},
+	TextureReadPixels(TextureId),
+	DefaultPassReadPixels,
+	TextureReadPixelsAsync {
+		texture: TextureId,
+		readback: ReadbackId,
+	},
+	DefaultPassReadPixelsAsync(ReadbackId),
+	ReadbackPoll {
+		readback: ReadbackId,
+		done: bool,
+	},
+	DeleteReadback(ReadbackId),
+	DeleteShader(ShaderId),
+	DeleteTexture(TextureId),
+	DeleteRenderPass(RenderPass),
+	BlitPass {
+		src: Option<RenderPass>,
+		dst: Option<RenderPass>,
+		src_rect: (i32, i32, i32, i32),
+		dst_rect: (i32, i32, i32, i32),
+		filter: FilterMode,
+	},
+	DeletePipeline(Pipeline),
+	DeleteBuffer(BufferId),
+	DeleteSampler(SamplerId),
+	ApplyViewport {
+		x: i32,
+		y: i32,
+		w: i32,
+		h: i32,
+	},
+	ApplyScissorRect {
+		x: i32,
+		y: i32,
+		w: i32,
+		h: i32,
+	},
+	ApplyPipeline(Pipeline),
+	ApplyBindings {
+		vertex_buffers: Vec<BufferId>,
+		index_buffer: Option<BufferId>,
+		images: Vec<TextureId>,
+		samplers: Vec<Option<SamplerId>>,
+	},
+	/// Raw bytes of the uniforms struct
+	ApplyUniforms(Vec<u8>),
+	ApplyUniformBuffer {
+		binding: u32,
+		buffer: BufferId,
+		offset: usize,
+		size: usize,
+	},
+	Clear {
+		color: Option<(f32, f32, f32, f32)>,
+		depth: Option<f32>,
+		stencil: Option<i32>,
+	},
+	BeginPass {
+		pass: Option<RenderPass>,
+		action: PassAction,
+	},
+	EndRenderPass,
+	CommitFrame,
+	Draw {
+		base_element: i32,
+		num_elements: i32,
+		num_instances: i32,
+	},
+	DrawBaseVertex {
+		base_element: i32,
+		num_elements: i32,
+		num_instances: i32,
+		base_vertex: i32,
+	},
+	DrawArrays {
+		first_vertex: i32,
+		num_vertices: i32,
+		num_instances: i32,
+	},
+}
+
+struct NullBuffer {
+	buffer_type: BufferType,
+	index_type: Option<usize>,
+	data: Vec<u8>,
+}
+
+struct NullTexture {
+	params: TextureParams,
+	/// Only the first cubemap face and mip level are kept, all the layers of a `Texture2DArray` or `Texture3D`
+	data: Vec<u8>,
+}
+
+impl NullTexture {
+	fn layers(&self) -> u32 {
+		if self.params.kind.is_layered() {
+			self.params.depth
+		} else {
+			1
+		}
+	}
+
+	fn layer_size(&self) -> usize {
+		self.params.format.size(self.params.width, self.params.height) as usize
+	}
+
+	fn update_layer_part(&mut self, layer: i32, x_offset: i32, y_offset: i32, width: i32, height: i32, bytes: &[u8]) {
+		let format = self.params.format;
+		assert!(!format.is_compressed(), "Compressed textures can not be updated");
+		assert!(x_offset >= 0 && y_offset >= 0 && width >= 0 && height >= 0, "Negative texture region");
+		assert_eq!(format.size(width as _, height as _) as usize, bytes.len(), "Texture data size does not match TextureFormat::size");
+		assert!(x_offset + width <= self.params.width as _, "Texture region is out of bounds");
+		assert!(y_offset + height <= self.params.height as _, "Texture region is out of bounds");
+		assert!(layer >= 0 && (layer as u32) < self.layers(), "Layer {} is out of bounds", layer);
+
+		let pixel_size = format.size(1, 1) as usize;
+		let row_size = width as usize * pixel_size;
+		let texture_row_size = self.params.width as usize * pixel_size;
+		let layer_start = layer as usize * self.layer_size();
+		for row in 0..height as usize {
+			let start = layer_start + (y_offset as usize + row) * texture_row_size + x_offset as usize * pixel_size;
+			self.data[start..start + row_size].copy_from_slice(&bytes[row * row_size..(row + 1) * row_size]);
+		}
+	}
+}
+
+struct NullPipeline {
+	shader: ShaderId,
+	/// (stride, bytes used by the attributes, step function, step rate) for each buffer
+	buffers: Vec<(usize, usize, VertexStep, usize)>,
+}
+
+struct NullRenderPass {
+	color_textures: Vec<TextureId>,
+	depth_texture: Option<TextureId>,
+}
+
+struct NullReadback {
+	pixels: Vec<u8>,
+	/// `commit_frame` calls before the readback was started
+	frame: u64,
+}
+
+/// Software-only `RenderingBackend` for unit tests: no GL function pointers
+/// and no window are required.
+///
+/// Invalid arguments panic with a message, like the debug assertions of `GlContext` do,
+/// but are checked much more thoroughly: buffer sizes against the pipeline's `BufferLayout`
+/// and the indices actually drawn, uniform sizes and bound uniform blocks against `ShaderMeta`, texture byte counts
+/// against `TextureFormat::size`, use of deleted resources.
+///
+/// Async readbacks finish on the next `commit_frame`, so the still pending case gets exercised too.
+/// Nothing is rasterized: the default framebuffer reads back as the last color it was cleared to.
+///
+/// `reflect_shader` reads the declarations from the GLSL source without compiling it,
+/// so unused uniforms are reported too and preprocessor directives are ignored.
+///
+/// ```
+/// # use miniquad_wasm_bindgen::*;
+/// let mut ctx = NullContext::new();
+///
+/// let vertices: [f32; 6] = [0., 0., 1., 0., 0., 1.];
+/// let vertex_buffer = ctx.new_buffer(BufferType::VertexBuffer, BufferUsage::Immutable, BufferSource::slice(&vertices));
+/// let index_buffer = ctx.new_buffer(BufferType::IndexBuffer, BufferUsage::Immutable, BufferSource::slice(&[0u16, 1, 2]));
+///
+/// let meta = ShaderMeta {
+///     images: vec![],
+///     uniforms: UniformBlockLayout { uniforms: vec![UniformDesc::new("offset", UniformType::Float2)] },
+///     uniform_blocks: vec![],
+/// };
+/// let shader = ctx.new_shader(ShaderSource::new("", ""), meta).unwrap();
+/// let pipeline = ctx.new_pipeline(&[BufferLayout::default()], &[VertexAttribute::new("in_pos", VertexFormat::Float2)], shader, PipelineParams::default());
+///
+/// ctx.begin_default_pass(PassAction::Nothing);
+/// ctx.apply_pipeline(&pipeline);
+/// ctx.apply_bindings(&Bindings { vertex_buffers: vec![vertex_buffer], index_buffer: Some(index_buffer), images: vec![], samplers: vec![] });
+/// ctx.apply_uniforms(UniformsSource::table(&[0.5f32, 0.5]));
+/// ctx.draw(0, 3, 1);
+/// ctx.end_render_pass();
+///
+/// assert!(ctx.commands().contains(&NullCommand::Draw { base_element: 0, num_elements: 3, num_instances: 1 }));
+/// ```
+pub struct NullContext {
+	shaders: ResourceManager<ShaderMeta>,
+	pipelines: ResourceManager<NullPipeline>,
+	passes: ResourceManager<NullRenderPass>,
+	buffers: ResourceManager<NullBuffer>,
+	textures: ResourceManager<NullTexture>,
+	readbacks: ResourceManager<NullReadback>,
+	samplers: ResourceManager<SamplerParams>,
+	frame: u64,
+	screen_size: (u32, u32),
+	/// RGBA8 color of the last clear of the default pass, draws are not rasterized
+	screen_color: [u8; 4],
+	features: Features,
+	in_pass: bool,
+	cur_pipeline: Option<Pipeline>,
+	cur_bindings: Option<(Vec<BufferId>, Option<BufferId>)>,
+	/// binding point -> (buffer, bound bytes), the bindings outlive pipelines like in GL
+	uniform_buffers: HashMap<u32, (BufferId, usize)>,
+	// draw takes &self
+	commands: RefCell<Vec<NullCommand>>,
+	stats: Cell<FrameStats>,
+	/// Stats of the last committed frame
+	frame_stats: FrameStats,
+}
+
+impl Default for NullContext {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl NullContext {
+	pub fn new() -> NullContext {
+		NullContext {
+			shaders: ResourceManager::new("ShaderId"),
+			pipelines: ResourceManager::new("Pipeline"),
+			passes: ResourceManager::new("RenderPass"),
+			buffers: ResourceManager::new("BufferId"),
+			textures: ResourceManager::new("TextureId"),
+			readbacks: ResourceManager::new("ReadbackId"),
+			samplers: ResourceManager::new("SamplerId"),
+			frame: 0,
+			screen_size: (800, 600),
+			screen_color: [0; 4],
+			features: Features {
+				instancing: true,
+				float_render_targets: true,
+				anisotropic_filtering: true,
+				samplers: true,
+				clamp_to_border: true,
+				independent_blend: true,
+				base_vertex: true,
+			},
+			in_pass: false,
+			cur_pipeline: None,
+			cur_bindings: None,
+			uniform_buffers: HashMap::new(),
+			commands: RefCell::new(vec![]),
+			stats: Cell::new(FrameStats::default()),
+			frame_stats: FrameStats::default(),
+		}
+	}
+
+	/// Size of the default framebuffer, there is no window to ask. 800x600 by default.
+	///
+	/// ```
+	/// # use miniquad_wasm_bindgen::*;
+	/// let mut ctx = NullContext::new();
+	/// ctx.set_screen_size(2, 1);
+	/// ctx.begin_default_pass(PassAction::clear_color(1.0, 0.0, 0.0, 1.0));
+	/// ctx.end_render_pass();
+	///
+	/// let mut bytes = [0; 8];
+	/// ctx.default_pass_read_pixels(&mut bytes);
+	/// assert_eq!(bytes, [255, 0, 0, 255, 255, 0, 0, 255]);
+	/// ```
+	pub fn set_screen_size(&mut self, width: u32, height: u32) {
+		self.screen_size = (width, height);
+	}
+
+	/// A shader described by `meta`, without source since NullContext does not compile shaders.
+	pub fn new_test_shader(&mut self, meta: ShaderMeta) -> ShaderId {
+		self.new_shader(ShaderSource::new("", ""), meta).unwrap()
+	}
+
+	/// The pipeline most examples draw with: a `new_test_shader` reading a `Float2` "in_pos" from one vertex buffer.
+	pub fn new_test_pipeline(&mut self, meta: ShaderMeta, params: PipelineParams) -> Pipeline {
+		let shader = self.new_test_shader(meta);
+		self.new_pipeline(&[BufferLayout::default()], &[VertexAttribute::new("in_pos", VertexFormat::Float2)], shader, params)
+	}
+
+	/// Features reported by `info` and required by the calls that depend on them. All of them by default,
+	/// turn them off to test the fallbacks for GL2 and WebGL1.
+	///
+	/// ```
+	/// # use miniquad_wasm_bindgen::*;
+	/// let mut ctx = NullContext::new();
+	/// ctx.set_features(Features { independent_blend: false, ..ctx.info().features });
+	/// # let shader = ctx.new_test_shader(ShaderMeta::default());
+	///
+	/// let mut color_targets = [None; MAX_COLOR_ATTACHMENTS];
+	/// color_targets[1] = Some(ColorTargetState { color_write: (true, true, true, false), ..Default::default() });
+	/// let result = ctx.try_new_pipeline(&[], &[], shader, PipelineParams { color_targets, ..Default::default() });
+	/// assert_eq!(result.unwrap_err(), GraphicsError::InvalidParams("Different color_targets per attachment need Features::independent_blend"));
+	/// ```
+	pub fn set_features(&mut self, features: Features) {
+		self.features = features;
+	}
+
+	/// All the calls recorded so far.
+	pub fn commands(&mut self) -> &[NullCommand] {
+		self.commands.get_mut()
+	}
+
+	/// Return the recorded calls and start a new, empty log.
+	pub fn take_commands(&mut self) -> Vec<NullCommand> {
+		std::mem::take(self.commands.get_mut())
+	}
+
+	fn record(&self, command: NullCommand) {
+		self.commands.borrow_mut().push(command);
+	}
+
+	fn count(&self, f: impl FnOnce(&mut FrameStats)) {
+		let mut stats = self.stats.get();
+		f(&mut stats);
+		self.stats.set(stats);
+	}
+
+	fn texture(&self, texture: TextureId) -> &NullTexture {
+		match texture.0 {
+			TextureIdInner::Managed(id) => self.textures.get(id).unwrap_or_else(|| panic!("{:?} is deleted", texture)),
+			TextureIdInner::Raw(_) => panic!("NullContext can not use raw textures"),
+		}
+	}
+
+	fn try_texture(&self, texture: TextureId) -> Result<&NullTexture, GraphicsError> {
+		match texture.0 {
+			TextureIdInner::Managed(id) => self.textures.get(id).ok_or(GraphicsError::StaleId { kind: "TextureId", id }),
+			TextureIdInner::Raw(_) => panic!("NullContext can not use raw textures"),
+		}
+	}
+
+	fn texture_mut(&mut self, texture: TextureId) -> &mut NullTexture {
+		match texture.0 {
+			TextureIdInner::Managed(id) => self.textures.get_mut(id).unwrap_or_else(|| panic!("{:?} is deleted", texture)),
+			TextureIdInner::Raw(_) => panic!("NullContext can not use raw textures"),
+		}
+	}
+
+	fn buffer(&self, buffer: BufferId) -> &NullBuffer {
+		self.buffers.get(buffer.0).unwrap_or_else(|| panic!("{:?} is deleted", buffer))
+	}
+
+	fn shader(&self, shader: ShaderId) -> &ShaderMeta {
+		self.shaders.get(shader.0).unwrap_or_else(|| panic!("{:?} is deleted", shader))
+	}
+
+	fn pipeline(&self, pipeline: Pipeline) -> &NullPipeline {
+		self.pipelines.get(pipeline.0).unwrap_or_else(|| panic!("{:?} is deleted", pipeline))
+	}
+
+	/// Checks the bound index buffer holds the drawn elements and returns the largest index among them,
+	/// without an index buffer the elements are the vertices
+	fn validate_indices(&self, base_element: i32, num_elements: i32, num_instances: i32) -> Option<usize> {
+		assert!(self.in_pass, "Drawing outside of a render pass");
+		assert!(base_element >= 0 && num_elements >= 0 && num_instances >= 0, "Negative draw arguments");
+		let (_, index_buffer) = self.cur_bindings.as_ref().expect("Drawing without applied bindings");
+		let Some(index_buffer) = index_buffer else {
+			return (num_elements > 0).then(|| (base_element + num_elements - 1) as usize);
+		};
+
+		let index_buffer = self.buffer(*index_buffer);
+		let index_size = index_buffer.index_type.unwrap();
+		let start = base_element as usize * index_size;
+		let end = start + num_elements as usize * index_size;
+		assert!(
+			end <= index_buffer.data.len(),
+			"Drawing elements {}..{}, but the index buffer has only {}",
+			base_element,
+			base_element + num_elements,
+			index_buffer.data.len() / index_size
+		);
+
+		index_buffer.data[start..end]
+			.chunks_exact(index_size)
+			.map(|index| match index_size {
+				1 => index[0] as usize,
+				2 => u16::from_ne_bytes([index[0], index[1]]) as usize,
+				_ => u32::from_ne_bytes([index[0], index[1], index[2], index[3]]) as usize,
+			})
+			.max()
+	}
+
+	/// Checks the uniform blocks are bound and the vertex buffers hold every vertex up to `max_vertex` and every instance
+	fn validate_vertices(&self, max_vertex: Option<usize>, num_instances: i32) {
+		assert!(num_instances <= 1 || self.features.instancing, "Instanced drawing is not supported by this context, see Features::instancing");
+		let pipeline = self.pipeline(self.cur_pipeline.expect("Drawing without bound pipeline"));
+		let (vertex_buffers, _) = self.cur_bindings.as_ref().expect("Drawing without applied bindings");
+
+		for block in &self.shader(pipeline.shader).uniform_blocks {
+			let (buffer, size) = *self
+				.uniform_buffers
+				.get(&block.binding)
+				.unwrap_or_else(|| panic!("No uniform buffer applied for uniform block {}", block.name));
+			self.buffer(buffer);
+			assert!(
+				size >= block.layout.std140_size(),
+				"Uniform block {} takes {} bytes, but only {} bytes are bound",
+				block.name,
+				block.layout.std140_size(),
+				size
+			);
+		}
+
+		for (n, &(stride, attributes_size, step_func, step_rate)) in pipeline.buffers.iter().enumerate() {
+			let last = match step_func {
+				VertexStep::PerVertex => max_vertex,
+				VertexStep::PerInstance => (num_instances as usize).checked_sub(1).map(|i| i / step_rate),
+			};
+			if let Some(last) = last {
+				let required = last * stride + attributes_size;
+				let size = self.buffer(vertex_buffers[n]).data.len();
+				assert!(required <= size, "Vertex buffer {} is {} bytes, but the draw reads {} bytes from it", n, size, required);
+			}
+		}
+	}
+
+	fn pass(&self, pass: RenderPass) -> &NullRenderPass {
+		self.passes.get(pass.0).unwrap_or_else(|| panic!("{:?} is deleted", pass))
+	}
+
+	fn readback(&self, readback: ReadbackId) -> &NullReadback {
+		self.readbacks.get(readback.0).unwrap_or_else(|| panic!("{:?} is deleted", readback))
+	}
+
+	fn sampler(&self, sampler: SamplerId) -> &SamplerParams {
+		self.samplers.get(sampler.0).unwrap_or_else(|| panic!("{:?} is deleted", sampler))
+	}
+}
+
+/// The largest alignment allowed by the spec, the strictest check.
+const UNIFORM_BUFFER_OFFSET_ALIGNMENT: usize = 256;
+
+/// `glCheckFramebufferStatus` results
+const FRAMEBUFFER_INCOMPLETE_ATTACHMENT: u32 = 0x8CD6;
+const FRAMEBUFFER_INCOMPLETE_DIMENSIONS: u32 = 0x8CD9;
+
+/// GL type enum of a GLSL type name, 0 for structs and anything else unknown
+fn glsl_type(name: &str) -> GLenum {
+	match name {
+		"float" => GL_FLOAT,
+		"vec2" => GL_FLOAT_VEC2,
+		"vec3" => GL_FLOAT_VEC3,
+		"vec4" => GL_FLOAT_VEC4,
+		"int" => GL_INT,
+		"ivec2" => GL_INT_VEC2,
+		"ivec3" => GL_INT_VEC3,
+		"ivec4" => GL_INT_VEC4,
+		"uint" => GL_UNSIGNED_INT,
+		"bool" => 0x8B56,
+		"mat2" => 0x8B5A,
+		"mat3" => 0x8B5B,
+		"mat4" => GL_FLOAT_MAT4,
+		"sampler2D" => GL_SAMPLER_2D,
+		"sampler3D" => GL_SAMPLER_3D,
+		"samplerCube" => GL_SAMPLER_CUBE,
+		"sampler2DArray" => GL_SAMPLER_2D_ARRAY,
+		"sampler2DShadow" => GL_SAMPLER_2D_SHADOW,
+		"samplerCubeShadow" => GL_SAMPLER_CUBE_SHADOW,
+		"sampler2DArrayShadow" => GL_SAMPLER_2D_ARRAY_SHADOW,
+		_ => 0,
+	}
+}
+
+/// Identifiers, numbers and single punctuation characters, without comments and preprocessor lines
+fn glsl_tokens(source: &str) -> Vec<&str> {
+	let mut tokens = vec![];
+	let mut rest = source;
+	while let Some(c) = rest.chars().next() {
+		let len = if rest.starts_with("//") || c == '#' {
+			rest.find('\n').unwrap_or(rest.len())
+		} else if rest.starts_with("/*") {
+			rest.find("*/").map_or(rest.len(), |end| end + 2)
+		} else if c.is_ascii_alphanumeric() || c == '_' {
+			let len = rest.find(|c: char| !c.is_ascii_alphanumeric() && c != '_' && c != '.').unwrap_or(rest.len());
+			tokens.push(&rest[..len]);
+			len
+		} else {
+			if !c.is_whitespace() {
+				tokens.push(&rest[..c.len_utf8()]);
+			}
+			c.len_utf8()
+		};
+		rest = &rest[len..];
+	}
+	tokens
+}
+
+/// `type name[N], name, ...` with the qualifiers already stripped, as `(name, gl_type, array_count)`
+fn glsl_declarations<'a>(tokens: &[&'a str]) -> Vec<(&'a str, GLenum, usize)> {
+	let Some((type_name, names)) = tokens.split_first() else { return vec![] };
+	names
+		.split(|token| *token == ",")
+		.filter_map(|declaration| {
+			let array_count = match declaration {
+				[_, "[", count, "]", ..] => count.trim_end_matches(['u', 'U']).parse().unwrap_or(1),
+				_ => 1,
+			};
+			declaration.first().map(|name| (*name, glsl_type(type_name), array_count))
+		})
+		.collect()
+}
+
+/// `reflect_shader` of NullContext: the uniforms, uniform blocks and attributes declared at the top level of the source
+fn glsl_reflect(vertex: &str, fragment: &str) -> (ShaderMeta, Vec<AttributeDesc>, Vec<ShaderMismatch>) {
+	let mut meta = ShaderMeta {
+		uniforms: UniformBlockLayout { uniforms: vec![] },
+		images: vec![],
+		uniform_blocks: vec![],
+	};
+	let mut attributes = vec![];
+	let mut unsupported = vec![];
+	let uniform = |name: &str, gl_type, array_count, uniforms: &mut Vec<UniformDesc>, unsupported: &mut Vec<ShaderMismatch>| match uniform_type(gl_type) {
+		Some(uniform_type) => uniforms.push(UniformDesc::new(name, uniform_type).array(array_count)),
+		None => unsupported.push(ShaderMismatch::UnsupportedType { name: name.to_string(), gl_type }),
+	};
+
+	for (source, is_vertex) in [(vertex, true), (fragment, false)] {
+		let tokens = glsl_tokens(source);
+		let mut statement: Vec<&str> = vec![];
+		let mut i = 0;
+		while i < tokens.len() {
+			let token = tokens[i];
+			i += 1;
+			if token != ";" && token != "{" {
+				statement.push(token);
+				continue;
+			}
+
+			// layout(...) and precision qualifiers do not change what is declared
+			let mut declaration = vec![];
+			let mut parens = 0;
+			for token in statement.drain(..) {
+				match token {
+					"layout" | "lowp" | "mediump" | "highp" | "flat" | "smooth" | "centroid" | "invariant" => {}
+					"(" => parens += 1,
+					")" => parens -= 1,
+					_ if parens == 0 => declaration.push(token),
+					_ => {}
+				}
+			}
+
+			// everything up to the matching "}", a uniform block's members or a function body
+			let mut body = vec![];
+			if token == "{" {
+				let mut depth = 1;
+				while i < tokens.len() && depth > 0 {
+					depth += match tokens[i] {
+						"{" => 1,
+						"}" => -1,
+						_ => 0,
+					};
+					if depth > 0 {
+						body.push(tokens[i]);
+					}
+					i += 1;
+				}
+			}
+
+			match declaration.as_slice() {
+				["uniform", name] if token == "{" => {
+					let mut members = vec![];
+					for member in body.split(|token| *token == ";") {
+						let member: Vec<&str> = member.iter().copied().filter(|token| !matches!(*token, "lowp" | "mediump" | "highp")).collect();
+						for (name, gl_type, array_count) in glsl_declarations(&member) {
+							uniform(name, gl_type, array_count, &mut members, &mut unsupported);
+						}
+					}
+					meta.uniform_blocks.push(UniformBlockDesc::new(name, 0, members));
+					// the instance name and ";" after the "}"
+					while i < tokens.len() && tokens[i] != ";" {
+						i += 1;
+					}
+					i += 1;
+				}
+				["uniform", declaration @ ..] if token == ";" => {
+					for (name, gl_type, array_count) in glsl_declarations(declaration) {
+						match image_type(gl_type) {
+							Some(image_type) if !meta.images.iter().any(|image| image.name == name) => meta.images.push(ImageDesc::new(name, image_type)),
+							Some(_) => {}
+							// both stages may declare the same uniform
+							None if meta.uniforms.uniforms.iter().any(|uniform| uniform.name == name) => {}
+							None => uniform(name, gl_type, array_count, &mut meta.uniforms.uniforms, &mut unsupported),
+						}
+					}
+				}
+				["attribute" | "in", declaration @ ..] if is_vertex && token == ";" => {
+					for (name, gl_type, _) in glsl_declarations(declaration) {
+						match attribute_format(gl_type) {
+							Some(format) => attributes.push(AttributeDesc { name: name.to_string(), format }),
+							None => unsupported.push(ShaderMismatch::UnsupportedType { name: name.to_string(), gl_type }),
+						}
+					}
+				}
+				_ => {}
+			}
+		}
+	}
+
+	(meta, attributes, unsupported)
+}
+
+impl RenderingBackend for NullContext {
+	fn info(&self) -> ContextInfo {
+		ContextInfo {
+			gl_version_string: "NullContext".to_string(),
+			glsl_support: GlslSupport {
+				v130: true,
+				v330: true,
+				v300es: true,
+				v100_ext: true,
+				v100: true,
+			},
+			uniform_buffer_offset_alignment: UNIFORM_BUFFER_OFFSET_ALIGNMENT,
+			texture_compression: TextureCompression { etc2: true, astc: true, s3tc: true },
+			features: self.features.clone(),
+			// the smallest limits GLES3 and WebGL2 allow
+			limits: Limits {
+				max_texture_size: 2048,
+				max_cube_map_texture_size: 2048,
+				max_3d_texture_size: 256,
+				max_array_texture_layers: 256,
+				max_color_attachments: 4,
+				max_samples: 4,
+				max_vertex_attributes: 16,
+				max_vertex_uniform_vectors: 256,
+				max_fragment_uniform_vectors: 224,
+				max_texture_units: 12,
+				max_anisotropy: 2,
+			},
+			extensions: vec![],
+		}
+	}
+
+	fn new_shader(&mut self, _shader: ShaderSource, meta: ShaderMeta) -> Result<ShaderId, ShaderError> {
+		let shader = ShaderId(self.shaders.add(meta));
+		self.record(NullCommand::NewShader(shader));
+		Ok(shader)
+	}
+
+	fn reload_shader(&mut self, id: ShaderId, _shader: ShaderSource, meta: ShaderMeta) -> Result<(), ShaderError> {
+		*self.shaders.get_mut(id.0).unwrap_or_else(|| panic!("reload_shader failed: {:?} is deleted", id)) = meta;
+		self.record(NullCommand::ReloadShader(id));
+		Ok(())
+	}
+
+	fn reflect_shader(&mut self, shader: ShaderSource) -> Result<ShaderReflection, ShaderError> {
+		let (meta, attributes, unsupported) = glsl_reflect(shader.vertex, shader.fragment);
+		if !unsupported.is_empty() {
+			return Err(ShaderError::MetaMismatch(unsupported));
+		}
+		Ok(ShaderReflection::new(meta, attributes, shader.vertex, shader.fragment))
+	}
+
+	fn try_new_texture(&mut self, access: TextureAccess, source: TextureSource, params: TextureParams) -> Result<TextureId, GraphicsError> {
+		if params.format.is_compressed() && (access == TextureAccess::RenderTarget || params.kind.is_layered()) {
+			return Err(GraphicsError::UnsupportedFormat(params.format));
+		}
+		source.check(access, &params)?;
+		let layers = if params.kind.is_layered() { params.depth } else { 1 };
+		let size = params.format.size(params.width, params.height) as usize;
+		let data = match source {
+			TextureSource::Empty => vec![0; size * layers as usize],
+			TextureSource::Bytes(bytes) => bytes.to_vec(),
+			TextureSource::Array(array) => array[..layers as usize].iter().flat_map(|mipmaps| mipmaps[0].iter().copied()).collect(),
+		};
+
+		self.count(|stats| stats.bytes_uploaded += source.size());
+		let texture = TextureId(TextureIdInner::Managed(self.textures.add(NullTexture { params, data })));
+		self.record(NullCommand::NewTexture { texture, params });
+		Ok(texture)
+	}
+
+	fn texture_params(&self, texture: TextureId) -> TextureParams {
+		self.texture(texture).params
+	}
+
+	unsafe fn texture_raw_id(&self, texture: TextureId) -> RawId {
+		match texture.0 {
+			TextureIdInner::Managed(id) => {
+				self.texture(texture);
+				RawId(id.index)
+			}
+			TextureIdInner::Raw(raw) => raw,
+		}
+	}
+
+	fn texture_set_min_filter(&mut self, texture: TextureId, filter: FilterMode, mipmap_filter: MipmapFilterMode) {
+		let t = self.texture_mut(texture);
+		t.params.min_filter = filter;
+		t.params.mipmap_filter = mipmap_filter;
+		self.record(NullCommand::TextureSetMinFilter { texture, filter, mipmap_filter });
+	}
+
+	fn texture_set_mag_filter(&mut self, texture: TextureId, filter: FilterMode) {
+		self.texture_mut(texture).params.mag_filter = filter;
+		self.record(NullCommand::TextureSetMagFilter { texture, filter });
+	}
+
+	fn texture_set_wrap(&mut self, texture: TextureId, wrap_x: TextureWrap, wrap_y: TextureWrap) {
+		self.texture(texture);
+		self.record(NullCommand::TextureSetWrap { texture, wrap_x, wrap_y });
+	}
+
+	fn texture_generate_mipmaps(&mut self, texture: TextureId) {
+		assert!(!self.texture(texture).params.format.is_compressed(), "Mipmaps of compressed textures can not be generated");
+		self.record(NullCommand::TextureGenerateMipmaps(texture));
+	}
+
+	fn texture_resize(&mut self, texture: TextureId, width: u32, height: u32, bytes: Option<&[u8]>) {
+		let t = self.texture_mut(texture);
+		assert!(!t.params.format.is_compressed(), "Compressed textures can not be resized");
+		let size = t.params.format.size(width, height) as usize * t.layers() as usize;
+		t.params.width = width;
+		t.params.height = height;
+		t.data = match bytes {
+			Some(bytes) => {
+				assert_eq!(bytes.len(), size, "Texture data size does not match TextureFormat::size");
+				bytes.to_vec()
+			}
+			None => vec![0; size],
+		};
+		self.count(|stats| stats.bytes_uploaded += bytes.map_or(0, <[u8]>::len));
+		self.record(NullCommand::TextureResize { texture, width, height });
+	}
+
+	fn new_sampler(&mut self, params: SamplerParams) -> SamplerId {
+		assert!(self.features.samplers, "Sampler objects are not supported by this context");
+		let sampler = SamplerId(self.samplers.add(params));
+		self.record(NullCommand::NewSampler { sampler, params });
+		sampler
+	}
+
+	fn texture_read_pixels(&mut self, texture: TextureId, bytes: &mut [u8]) {
+		let t = self.texture(texture);
+		assert!(!t.params.format.is_compressed(), "Compressed textures can not be read back");
+		let size = t.layer_size();
+		assert!(bytes.len() >= size, "bytes is too small for the texture");
+		bytes[..size].copy_from_slice(&t.data[..size]);
+		self.record(NullCommand::TextureReadPixels(texture));
+	}
+
+	fn default_pass_read_pixels(&mut self, bytes: &mut [u8]) {
+		let (width, height) = self.screen_size;
+		let size = (width * height * 4) as usize;
+		assert!(bytes.len() >= size, "bytes is too small for the default framebuffer");
+		for pixel in bytes[..size].chunks_exact_mut(4) {
+			pixel.copy_from_slice(&self.screen_color);
+		}
+		self.record(NullCommand::DefaultPassReadPixels);
+	}
+
+	fn texture_read_pixels_async(&mut self, texture: TextureId) -> ReadbackId {
+		let t = self.texture(texture);
+		assert!(!t.params.format.is_compressed(), "Compressed textures can not be read back");
+		let pixels = t.data[..t.layer_size()].to_vec();
+		let readback = ReadbackId(self.readbacks.add(NullReadback { pixels, frame: self.frame }));
+		self.record(NullCommand::TextureReadPixelsAsync { texture, readback });
+		readback
+	}
+
+	fn default_pass_read_pixels_async(&mut self) -> ReadbackId {
+		let (width, height) = self.screen_size;
+		let pixels = self.screen_color.repeat((width * height) as usize);
+		let readback = ReadbackId(self.readbacks.add(NullReadback { pixels, frame: self.frame }));
+		self.record(NullCommand::DefaultPassReadPixelsAsync(readback));
+		readback
+	}
+
+	fn readback_size(&self, readback: ReadbackId) -> usize {
+		self.readback(readback).pixels.len()
+	}
+
+	fn readback_poll(&mut self, readback: ReadbackId, bytes: &mut [u8]) -> bool {
+		let r = self.readback(readback);
+		assert!(bytes.len() >= r.pixels.len(), "bytes is too small for the readback");
+
+		let done = r.frame < self.frame;
+		if done {
+			let r = self.readbacks.remove(readback.0).unwrap();
+			bytes[..r.pixels.len()].copy_from_slice(&r.pixels);
+		}
+		self.record(NullCommand::ReadbackPoll { readback, done });
+		done
+	}
+
+	fn delete_readback(&mut self, readback: ReadbackId) {
+		self.readbacks.remove(readback.0).unwrap_or_else(|| panic!("{:?} is deleted", readback));
+		self.record(NullCommand::DeleteReadback(readback));
+	}
+
+	fn texture_update_part(&mut self, texture: TextureId, x_offset: i32, y_offset: i32, width: i32, height: i32, bytes: &[u8]) {
+		self.texture_mut(texture).update_layer_part(0, x_offset, y_offset, width, height, bytes);
+		self.count(|stats| stats.bytes_uploaded += bytes.len());
+		self.record(NullCommand::TextureUpdatePart {
+			texture,
+			x_offset,
+			y_offset,
+			width,
+			height,
+		});
+	}
+
+	fn texture_update_layer_part(&mut self, texture: TextureId, layer: i32, x_offset: i32, y_offset: i32, width: i32, height: i32, bytes: &[u8]) {
+		self.texture_mut(texture).update_layer_part(layer, x_offset, y_offset, width, height, bytes);
+		self.count(|stats| stats.bytes_uploaded += bytes.len());
+		self.record(NullCommand::TextureUpdateLayerPart {
+			texture,
+			layer,
+			x_offset,
+			y_offset,
+			width,
+			height,
+		});
+	}
+
+	fn copy_texture_region(&mut self, src: TextureId, dst: TextureId, src_rect: (i32, i32, i32, i32), dst_offset: (i32, i32)) {
+		let (x, y, width, height) = src_rect;
+		let source = self.texture(src);
+		let params = source.params;
+		let dst_params = self.texture(dst).params;
+		assert!(params.kind == TextureKind::Texture2D && dst_params.kind == TextureKind::Texture2D, "Only Texture2Ds can be copied");
+		assert!(params.sample_count == 0, "Multisampled textures can not be copied, use blit_pass to resolve them");
+		assert!(params.format == dst_params.format && !params.format.is_depth(), "Copying between different or depth formats");
+		assert!(x >= 0 && y >= 0 && width >= 0 && height >= 0, "Negative texture region");
+		assert!(x + width <= params.width as _ && y + height <= params.height as _, "Source region is out of bounds");
+
+		let pixel_size = params.format.size(1, 1) as usize;
+		let row_size = width as usize * pixel_size;
+		let mut bytes = Vec::with_capacity(row_size * height as usize);
+		for row in y as usize..(y + height) as usize {
+			let start = (row * params.width as usize + x as usize) * pixel_size;
+			bytes.extend_from_slice(&source.data[start..start + row_size]);
+		}
+		self.texture_mut(dst).update_layer_part(0, dst_offset.0, dst_offset.1, width, height, &bytes);
+		self.record(NullCommand::CopyTextureRegion { src, dst, src_rect, dst_offset });
+	}
+
+	fn try_new_render_pass_mrt(&mut self, color_img: &[TextureId], resolve_img: &[TextureId], depth_img: Option<TextureId>) -> Result<RenderPass, GraphicsError> {
+		assert!(!color_img.is_empty() || depth_img.is_some(), "Render pass should have at least one target");
+
+		// the glCheckFramebufferStatus results GlContext reports
+		let incomplete = |complete: bool, status: u32| match complete {
+			true => Ok(()),
+			false => Err(GraphicsError::IncompleteFramebuffer(status)),
+		};
+		let size = |texture: &NullTexture| (texture.params.width, texture.params.height);
+		let pass_size = size(self.try_texture(color_img.first().copied().or(depth_img).unwrap())?);
+		for color in color_img {
+			let t = self.try_texture(*color)?;
+			incomplete(!t.params.format.is_depth(), FRAMEBUFFER_INCOMPLETE_ATTACHMENT)?;
+			incomplete(size(t) == pass_size, FRAMEBUFFER_INCOMPLETE_DIMENSIONS)?;
+		}
+		assert!(resolve_img.len() <= color_img.len(), "More resolve textures than color attachments");
+		for (color, resolve) in color_img.iter().zip(resolve_img) {
+			let t = self.try_texture(*resolve)?;
+			assert!(self.texture(*color).params.sample_count != 0, "Resolving a texture without multisampling");
+			incomplete(t.params.sample_count == 0, FRAMEBUFFER_INCOMPLETE_ATTACHMENT)?;
+			incomplete(size(t) == pass_size, FRAMEBUFFER_INCOMPLETE_DIMENSIONS)?;
+		}
+		if let Some(depth) = depth_img {
+			let t = self.try_texture(depth)?;
+			incomplete(t.params.format.is_depth(), FRAMEBUFFER_INCOMPLETE_ATTACHMENT)?;
+			incomplete(size(t) == pass_size, FRAMEBUFFER_INCOMPLETE_DIMENSIONS)?;
+		}
+
+		let pass = RenderPass(self.passes.add(NullRenderPass {
+			color_textures: color_img.to_vec(),
+			depth_texture: depth_img,
+		}));
+		self.record(NullCommand::NewRenderPass(pass));
+		Ok(pass)
+	}
+
+	fn render_pass_color_attachments(&self, render_pass: RenderPass) -> &[TextureId] {
+		&self.pass(render_pass).color_textures
+	}
+
+	fn try_delete_render_pass(&mut self, render_pass: RenderPass) -> Result<(), GraphicsError> {
+		let pass = self.passes.remove(render_pass.0).ok_or(GraphicsError::StaleId { kind: "RenderPass", id: render_pass.0 })?;
+		self.record(NullCommand::DeleteRenderPass(render_pass));
+
+		// the attachments go with the pass, like on GlContext, they may have been deleted by the user already
+		for texture in pass.color_textures.into_iter().chain(pass.depth_texture) {
+			let _ = self.try_delete_texture(texture);
+		}
+		Ok(())
+	}
+
+	fn blit_pass(&mut self, src: Option<RenderPass>, dst: Option<RenderPass>, src_rect: (i32, i32, i32, i32), dst_rect: (i32, i32, i32, i32), filter: FilterMode) {
+		assert!(!self.in_pass, "blit_pass inside of a render pass");
+		// whether the default framebuffer is multisampled is up to the window, only passes are checked
+		let color_params = |pass: Option<RenderPass>| {
+			pass.map(|pass| {
+				let texture = *self.pass(pass).color_textures.first().expect("Blitting a depth-only render pass");
+				self.texture(texture).params
+			})
+		};
+		let src_params = color_params(src);
+		let dst_params = color_params(dst);
+		assert!(dst_params.is_none_or(|params| params.sample_count == 0), "Blitting into a multisampled render pass");
+		if let Some(src_params) = src_params.filter(|params| params.sample_count != 0) {
+			assert_eq!((src_rect.2, src_rect.3), (dst_rect.2, dst_rect.3), "Resolving blits need rectangles of the same size");
+			assert!(dst_params.is_none_or(|params| params.format == src_params.format), "Resolving blits need the same format");
+		}
+		self.record(NullCommand::BlitPass { src, dst, src_rect, dst_rect, filter });
+	}
+
+	fn try_new_pipeline(&mut self, buffer_layout: &[BufferLayout], attributes: &[VertexAttribute], shader: ShaderId, params: PipelineParams) -> Result<Pipeline, GraphicsError> {
+		self.shaders.get(shader.0).ok_or(GraphicsError::StaleId { kind: "ShaderId", id: shader.0 })?;
+		let info = self.info();
+		params.check(&info.limits, &info.features)?;
+
+		let mut buffers: Vec<(usize, usize, VertexStep, usize)> = buffer_layout.iter().map(|layout| (0, 0, layout.step_func, layout.step_rate.max(1) as usize)).collect();
+		for attribute in attributes {
+			let buffer = buffers
+				.get_mut(attribute.buffer_index)
+				.ok_or(GraphicsError::InvalidParams("VertexAttribute::buffer_index past the buffer layouts"))?;
+			buffer.1 += attribute.format.size_bytes() as usize;
+		}
+		for (layout, buffer) in buffer_layout.iter().zip(&mut buffers) {
+			assert!(layout.stride >= 0, "Negative BufferLayout::stride");
+			if layout.stride == 0 {
+				buffer.0 = buffer.1;
+			} else {
+				assert!(buffer.1 <= layout.stride as usize, "Attributes take {} bytes, but BufferLayout::stride is {}", buffer.1, layout.stride);
+				buffer.0 = layout.stride as usize;
+			}
+		}
+
+		let pipeline = Pipeline(self.pipelines.add(NullPipeline { shader, buffers }));
+		self.record(NullCommand::NewPipeline(pipeline));
+		Ok(pipeline)
+	}
+
+	fn apply_pipeline(&mut self, pipeline: &Pipeline) {
+		let shader = self.pipeline(*pipeline).shader;
+		self.shader(shader);
+		if self.cur_pipeline != Some(*pipeline) {
+			self.count(|stats| stats.pipeline_switches += 1);
+		}
+		self.cur_pipeline = Some(*pipeline);
+		self.cur_bindings = None;
+		self.record(NullCommand::ApplyPipeline(*pipeline));
+	}
+
+	fn try_delete_pipeline(&mut self, pipeline: Pipeline) -> Result<(), GraphicsError> {
+		self.pipelines.remove(pipeline.0).ok_or(GraphicsError::StaleId { kind: "Pipeline", id: pipeline.0 })?;
+		if self.cur_pipeline == Some(pipeline) {
+			self.cur_pipeline = None;
+		}
+		self.record(NullCommand::DeletePipeline(pipeline));
+		Ok(())
+	}
+
+	fn try_new_buffer(&mut self, type_: BufferType, usage: BufferUsage, data: BufferSource) -> Result<BufferId, GraphicsError> {
+		let (data, element_size) = match data {
+			BufferSource::Slice(data) => {
+				assert!(data.is_slice, "BufferSource should be a slice");
+				self.count(|stats| stats.bytes_uploaded += data.size);
+				(unsafe { std::slice::from_raw_parts(data.ptr as *const u8, data.size) }.to_vec(), data.element_size)
+			}
+			BufferSource::Empty { size, element_size } => (vec![0; size], element_size),
+		};
+
+		let index_type = match type_ {
+			BufferType::IndexBuffer if element_size == 1 || element_size == 2 || element_size == 4 => Some(element_size),
+			BufferType::IndexBuffer => return Err(GraphicsError::UnsupportedIndexSize(element_size)),
+			BufferType::VertexBuffer | BufferType::UniformBuffer => None,
+		};
+
+		let size = data.len();
+		let buffer = BufferId(self.buffers.add(NullBuffer { buffer_type: type_, index_type, data }));
+		self.record(NullCommand::NewBuffer { buffer, type_, usage, size });
+		Ok(buffer)
+	}
+
+	fn buffer_update(&mut self, buffer: BufferId, data: BufferSource) {
+		let data = match data {
+			BufferSource::Slice(data) => data,
+			_ => panic!("buffer_update expects BufferSource::slice"),
+		};
+		assert!(data.is_slice, "BufferSource should be a slice");
+
+		self.buffer(buffer);
+		let b = self.buffers.get_mut(buffer.0).unwrap();
+		if let Some(index_type) = b.index_type {
+			assert!(
+				data.element_size == index_type,
+				"Index buffer of {} byte indices updated with {} byte elements",
+				index_type,
+				data.element_size
+			);
+		}
+		assert!(data.size <= b.data.len(), "Updating {} bytes of a {} bytes buffer", data.size, b.data.len());
+
+		b.data[..data.size].copy_from_slice(unsafe { std::slice::from_raw_parts(data.ptr as *const u8, data.size) });
+		self.count(|stats| stats.bytes_uploaded += data.size);
+		self.record(NullCommand::BufferUpdate { buffer, size: data.size });
+	}
+
+	fn buffer_size(&mut self, buffer: BufferId) -> usize {
+		self.buffer(buffer).data.len()
+	}
+
+	fn try_delete_buffer(&mut self, buffer: BufferId) -> Result<(), GraphicsError> {
+		self.buffers.remove(buffer.0).ok_or(GraphicsError::StaleId { kind: "BufferId", id: buffer.0 })?;
+		self.record(NullCommand::DeleteBuffer(buffer));
+		Ok(())
+	}
+
+	fn try_delete_texture(&mut self, texture: TextureId) -> Result<(), GraphicsError> {
+		self.try_texture(texture)?;
+		if let TextureIdInner::Managed(id) = texture.0 {
+			self.textures.remove(id);
+		}
+		self.record(NullCommand::DeleteTexture(texture));
+		Ok(())
+	}
+
+	fn try_delete_shader(&mut self, program: ShaderId) -> Result<(), GraphicsError> {
+		self.shaders.remove(program.0).ok_or(GraphicsError::StaleId { kind: "ShaderId", id: program.0 })?;
+		self.record(NullCommand::DeleteShader(program));
+		Ok(())
+	}
+
+	fn try_delete_sampler(&mut self, sampler: SamplerId) -> Result<(), GraphicsError> {
+		self.samplers.remove(sampler.0).ok_or(GraphicsError::StaleId { kind: "SamplerId", id: sampler.0 })?;
+		self.record(NullCommand::DeleteSampler(sampler));
+		Ok(())
+	}
+
+	fn apply_viewport(&mut self, x: i32, y: i32, w: i32, h: i32) {
+		assert!(w >= 0 && h >= 0, "Negative viewport size");
+		self.record(NullCommand::ApplyViewport { x, y, w, h });
+	}
+
+	fn apply_scissor_rect(&mut self, x: i32, y: i32, w: i32, h: i32) {
+		assert!(w >= 0 && h >= 0, "Negative scissor rect size");
+		self.record(NullCommand::ApplyScissorRect { x, y, w, h });
+	}
+
+	fn apply_bindings_from_slice(&mut self, vertex_buffers: &[BufferId], index_buffer: Option<BufferId>, textures: &[TextureId], samplers: &[Option<SamplerId>]) {
+		let pipeline = self.pipeline(self.cur_pipeline.expect("apply_bindings without applied pipeline"));
+		let shader = self.shader(pipeline.shader);
+
+		assert!(textures.len() >= shader.images.len(), "Image count in bindings and shader did not match!");
+		for texture in textures {
+			self.texture(*texture);
+		}
+		assert!(samplers.len() <= textures.len(), "More samplers than images in bindings");
+		for (n, (image, texture)) in shader.images.iter().zip(textures).enumerate() {
+			let texture = self.texture(*texture);
+			let compare = match samplers.get(n).copied().flatten() {
+				Some(sampler) => self.sampler(sampler).compare,
+				None => texture.params.compare,
+			};
+			assert!(compare.is_none() || texture.params.format.is_depth(), "Image {} compares a texture without a depth format", n);
+			assert!(
+				compare.is_some() == (image.image_type == ImageType::Shadow),
+				"Image {} is {:?}, the bound texture and sampler should {}compare",
+				n,
+				image.image_type,
+				if compare.is_some() { "not " } else { "" }
+			);
+		}
+
+		assert!(
+			vertex_buffers.len() >= pipeline.buffers.len(),
+			"Pipeline uses {} vertex buffers, but only {} are bound",
+			pipeline.buffers.len(),
+			vertex_buffers.len()
+		);
+		for vertex_buffer in vertex_buffers {
+			assert!(self.buffer(*vertex_buffer).buffer_type == BufferType::VertexBuffer, "{:?} is not a vertex buffer", vertex_buffer);
+		}
+		if let Some(index_buffer) = index_buffer {
+			assert!(self.buffer(index_buffer).buffer_type == BufferType::IndexBuffer, "{:?} is not an index buffer", index_buffer);
+		}
+
+		self.cur_bindings = Some((vertex_buffers.to_vec(), index_buffer));
+		self.record(NullCommand::ApplyBindings {
+			vertex_buffers: vertex_buffers.to_vec(),
+			index_buffer,
+			images: textures.to_vec(),
+			samplers: samplers.to_vec(),
+		});
+	}
+
+	// the trait takes a raw pointer, same as GlContext we trust it to point to `size` bytes
+	#[allow(clippy::not_unsafe_ptr_arg_deref)]
+	fn apply_uniforms_from_bytes(&mut self, uniform_ptr: *const u8, size: usize) {
+		let pipeline = self.pipeline(self.cur_pipeline.expect("apply_uniforms without applied pipeline"));
+		let shader = self.shader(pipeline.shader);
+
+		let expected: usize = shader.uniforms.uniforms.iter().map(|u| u.uniform_type.size() * u.array_count).sum();
+		assert_eq!(size, expected, "Uniforms struct does not match shader uniforms layout");
+
+		let bytes = unsafe { std::slice::from_raw_parts(uniform_ptr, size) }.to_vec();
+		self.record(NullCommand::ApplyUniforms(bytes));
+	}
+
+	fn apply_uniform_buffer(&mut self, binding: u32, buffer: BufferId, offset: usize, size: usize) {
+		let pipeline = self.pipeline(self.cur_pipeline.expect("apply_uniform_buffer without applied pipeline"));
+		let shader = self.shader(pipeline.shader);
+
+		let b = self.buffer(buffer);
+		assert!(b.buffer_type == BufferType::UniformBuffer, "{:?} is not a uniform buffer", buffer);
+		assert!(
+			offset + size <= b.data.len(),
+			"Uniform buffer range {}..{} is outside of the {} bytes buffer",
+			offset,
+			offset + size,
+			b.data.len()
+		);
+		assert!(
+			offset.is_multiple_of(UNIFORM_BUFFER_OFFSET_ALIGNMENT),
+			"Uniform buffer offset {} is not a multiple of {}",
+			offset,
+			UNIFORM_BUFFER_OFFSET_ALIGNMENT
+		);
+		if let Some(block) = shader.uniform_blocks.iter().find(|block| block.binding == binding) {
+			let required = block.layout.std140_size();
+			assert!(size >= required, "Uniform block {} takes {} bytes, but only {} bytes are bound", block.name, required, size);
+		}
+
+		self.uniform_buffers.insert(binding, (buffer, size));
+		self.record(NullCommand::ApplyUniformBuffer { binding, buffer, offset, size });
+	}
+
+	fn clear(&mut self, color: Option<(f32, f32, f32, f32)>, depth: Option<f32>, stencil: Option<i32>) {
+		self.record(NullCommand::Clear { color, depth, stencil });
+	}
+
+	fn begin_default_pass(&mut self, action: PassAction) {
+		self.begin_pass(None, action);
+	}
+
+	fn begin_pass(&mut self, pass: Option<RenderPass>, action: PassAction) {
+		assert!(!self.in_pass, "begin_pass without end_render_pass for the previous pass");
+		let color_attachments = pass.map_or(1, |pass| self.pass(pass).color_textures.len());
+		let colors = match &action {
+			PassAction::ClearAttachments { colors, .. } => colors.len(),
+			PassAction::Attachments { colors, .. } => colors.len(),
+			PassAction::Nothing | PassAction::Clear { .. } => 0,
+		};
+		assert!(colors <= color_attachments, "Actions for {} color attachments of a pass with {}", colors, color_attachments);
+		if pass.is_none() {
+			let clear = match &action {
+				PassAction::Nothing => None,
+				PassAction::Clear { color, .. } => *color,
+				PassAction::ClearAttachments { colors, .. } => colors.first().copied().flatten(),
+				PassAction::Attachments { colors, .. } => colors.first().and_then(|color| color.load.clear_value()),
+			};
+			if let Some((r, g, b, a)) = clear {
+				self.screen_color = [r, g, b, a].map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+			}
+		}
+		self.in_pass = true;
+		self.record(NullCommand::BeginPass { pass, action });
+	}
+
+	fn end_render_pass(&mut self) {
+		assert!(self.in_pass, "end_render_pass without begin_pass");
+		self.in_pass = false;
+		self.cur_bindings = None;
+		self.record(NullCommand::EndRenderPass);
+	}
+
+	fn commit_frame(&mut self) {
+		assert!(!self.in_pass, "commit_frame inside of a render pass");
+		self.frame += 1;
+		self.frame_stats = self.stats.take();
+		self.record(NullCommand::CommitFrame);
+	}
+
+	fn frame_stats(&self) -> FrameStats {
+		self.frame_stats
+	}
+
+	fn draw(&self, base_element: i32, num_elements: i32, num_instances: i32) {
+		let max_index = self.validate_indices(base_element, num_elements, num_instances);
+		self.validate_vertices(max_index, num_instances);
+		self.count(|stats| stats.draw_calls += 1);
+		self.record(NullCommand::Draw {
+			base_element,
+			num_elements,
+			num_instances,
+		});
+	}
+
+	fn draw_base_vertex(&self, base_element: i32, num_elements: i32, num_instances: i32, base_vertex: i32) {
+		assert!(self.features.base_vertex, "draw_base_vertex is not supported by this context, see Features::base_vertex");
+		let max_index = self.validate_indices(base_element, num_elements, num_instances);
+		assert!(matches!(self.cur_bindings, Some((_, Some(_)))), "draw_base_vertex without an index buffer");
+		let max_vertex = max_index.map(|index| {
+			let vertex = index as i64 + base_vertex as i64;
+			assert!(vertex >= 0, "Vertex {} with base_vertex {} is negative", index, base_vertex);
+			vertex as usize
+		});
+		self.validate_vertices(max_vertex, num_instances);
+		self.count(|stats| stats.draw_calls += 1);
+		self.record(NullCommand::DrawBaseVertex {
+			base_element,
+			num_elements,
+			num_instances,
+			base_vertex,
+		});
+	}
+
+	fn draw_arrays(&self, first_vertex: i32, num_vertices: i32, num_instances: i32) {
+		assert!(self.in_pass, "Drawing outside of a render pass");
+		assert!(first_vertex >= 0 && num_vertices >= 0 && num_instances >= 0, "Negative draw arguments");
+		let max_vertex = (num_vertices > 0).then(|| (first_vertex + num_vertices - 1) as usize);
+		self.validate_vertices(max_vertex, num_instances);
+		self.count(|stats| stats.draw_calls += 1);
+		self.record(NullCommand::DrawArrays {
+			first_vertex,
+			num_vertices,
+			num_instances,
+		});
+	}
+}
@@ -0,0 +1,521 @@
+//! GLSL preprocessing: `#include`, injected `#define`s and rewriting one source
+//! for the GLSL version the context supports.
+
+use std::collections::{HashMap, HashSet};
+
+use super::reflection::find_word;
+use super::*;
+
+/// GLSL versions `ShaderPreprocessor` can target, see `GlslSupport::best`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GlslVersion {
+	/// GLSL ES 1.00: `attribute`, `varying` and `gl_FragColor`
+	V100,
+	/// GLSL 1.30, desktop GL3.0-3.2
+	V130,
+	/// GLSL ES 3.00, GLES3 and WebGL2
+	V300Es,
+	/// GLSL 3.30, desktop GL3.3+
+	V330,
+}
+
+impl GlslVersion {
+	/// The `#version` line
+	pub fn header(&self) -> &'static str {
+		match self {
+			GlslVersion::V100 => "#version 100",
+			GlslVersion::V130 => "#version 130",
+			GlslVersion::V300Es => "#version 300 es",
+			GlslVersion::V330 => "#version 330",
+		}
+	}
+
+	fn is_es(&self) -> bool {
+		matches!(self, GlslVersion::V100 | GlslVersion::V300Es)
+	}
+}
+
+impl GlslSupport {
+	/// The newest version supported, the one `ShaderPreprocessor` should target.
+	pub fn best(&self) -> GlslVersion {
+		if self.v330 {
+			GlslVersion::V330
+		} else if self.v300es {
+			GlslVersion::V300Es
+		} else if self.v130 {
+			GlslVersion::V130
+		} else {
+			GlslVersion::V100
+		}
+	}
+}
+
+/// Where a line of `PreprocessedShader::source` comes from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceLocation {
+	/// The `#include` name, `None` for the source given to `ShaderPreprocessor::process`
+	pub file: Option<String>,
+	/// 1-based
+	pub line: u32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PreprocessError {
+	/// `#include` of a file not registered with `ShaderPreprocessor::include`
+	MissingInclude { name: String, at: SourceLocation },
+	/// A file including itself, directly or through other files
+	IncludeCycle { name: String, at: SourceLocation },
+	/// Something GLSL 1.00 can not express, like more than one fragment shader output
+	Unsupported(String),
+}
+
+impl Display for PreprocessError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{:?}", self) // Display the same way as Debug
+	}
+}
+
+impl Error for PreprocessError {}
+
+/// The output of `ShaderPreprocessor::process`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PreprocessedShader {
+	pub source: String,
+	/// Origin of every line of `source`, `None` for the generated ones
+	lines: Vec<Option<SourceLocation>>,
+}
+
+impl PreprocessedShader {
+	/// Where the 1-based `line` of `source` comes from, to map compiler errors back to the files.
+	/// `None` for lines the preprocessor generated, like `#version` and the `#define`s.
+	pub fn origin(&self, line: u32) -> Option<&SourceLocation> {
+		self.lines.get((line as usize).checked_sub(1)?)?.as_ref()
+	}
+
+	/// Point a diagnostic of this shader at the file and line it comes from.
+	/// Diagnostics on generated lines lose their line, the source has nothing to point at.
+	///
+	/// ```
+	/// # use miniquad_wasm_bindgen::*;
+	/// let preprocessor = ShaderPreprocessor::new().include("light.glsl", "float light() {\n\treturn colour;\n}");
+	/// let shader = preprocessor.process("#include \"light.glsl\"\nvoid main() {}", ShaderType::Vertex, GlslVersion::V100).unwrap();
+	///
+	/// // compiling `shader.source` fails with
+	/// let error = ShaderError::CompilationError {
+	///     shader_type: ShaderType::Vertex,
+	///     error_message: "0:3(9): error: `colour' undeclared".to_string(),
+	/// };
+	/// let mut diagnostic = error.diagnostics().remove(0);
+	/// shader.remap(&mut diagnostic);
+	/// assert_eq!((diagnostic.file.as_deref(), diagnostic.line, diagnostic.column), (Some("light.glsl"), Some(2), Some(9)));
+	/// ```
+	pub fn remap(&self, diagnostic: &mut ShaderDiagnostic) {
+		let Some(line) = diagnostic.line else { return };
+		match self.origin(line) {
+			Some(origin) => {
+				diagnostic.file = origin.file.clone();
+				diagnostic.line = Some(origin.line);
+			}
+			None => {
+				diagnostic.line = None;
+				diagnostic.column = None;
+			}
+		}
+	}
+}
+
+/// Resolves `#include "name"` from in-memory files, prepends `#define`s and rewrites the source
+/// to the requested GLSL version, so one source works on every context.
+///
+/// Sources may be written for GLSL 1.00 or for 1.30+, by their own `#version` line.
+/// Going between the two the preprocessor rewrites
+/// + `attribute`/`varying` to `in`/`out` and back,
+/// + `gl_FragColor` to an `out vec4` and back, only one fragment output can be rewritten to GLSL 1.00,
+/// + `texture2D`, `textureCube` and their `Lod` variants to `texture` and `textureLod` and back,
+/// + `#extension` lines of `GL_OES_standard_derivatives` and `GL_EXT_shader_texture_lod`, core in 1.30+,
+///   to nothing, with `#ifdef`s and `defined()`s over them taken as true,
+///
+/// drops `layout(...)` qualifiers of inputs and outputs the target does not allow and
+/// adds `precision mediump` statements GLSL ES fragment shaders need.
+/// Conditionals are left for the driver, so `#ifdef`s over the injected defines work as usual.
+///
+/// ```
+/// # use miniquad_wasm_bindgen::*;
+/// let preprocessor = ShaderPreprocessor::new().include("tint.glsl", "uniform vec4 tint;").define("SHADOWS", "1");
+///
+/// let fragment = "#version 100
+/// precision mediump float;
+/// #include \"tint.glsl\"
+/// varying vec2 uv;
+/// uniform sampler2D tex;
+/// void main() { gl_FragColor = texture2D(tex, uv) * tint; }";
+///
+/// let shader = preprocessor.process(fragment, ShaderType::Fragment, GlslVersion::V300Es).unwrap();
+/// assert_eq!(
+///     shader.source,
+///     "#version 300 es
+/// #define SHADOWS 1
+/// precision mediump float;
+/// out vec4 miniquad_FragColor;
+/// uniform vec4 tint;
+/// in vec2 uv;
+/// uniform sampler2D tex;
+/// void main() { miniquad_FragColor = texture(tex, uv) * tint; }"
+/// );
+/// assert_eq!(shader.origin(5), Some(&SourceLocation { file: Some("tint.glsl".to_string()), line: 1 }));
+/// assert_eq!(shader.origin(6), Some(&SourceLocation { file: None, line: 4 }));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ShaderPreprocessor {
+	files: HashMap<String, String>,
+	defines: Vec<(String, String)>,
+}
+
+/// Output name of `gl_FragColor` for GLSL 1.30+
+const FRAG_COLOR: &str = "miniquad_FragColor";
+
+impl ShaderPreprocessor {
+	pub fn new() -> ShaderPreprocessor {
+		ShaderPreprocessor::default()
+	}
+
+	/// Register a file for `#include "name"` or `#include <name>`.
+	/// Files with `#pragma once` are only included the first time.
+	pub fn include(mut self, name: &str, source: &str) -> ShaderPreprocessor {
+		self.files.insert(name.to_string(), source.to_string());
+		self
+	}
+
+	/// `#define name value`, right after the `#version` line.
+	pub fn define(mut self, name: &str, value: &str) -> ShaderPreprocessor {
+		self.defines.push((name.to_string(), value.to_string()));
+		self
+	}
+
+	/// Expand the includes and rewrite `source` for `version`.
+	///
+	/// ```
+	/// # use miniquad_wasm_bindgen::*;
+	/// let fragment = "#ifdef GL_OES_standard_derivatives
+	/// #extension GL_OES_standard_derivatives : enable
+	/// #endif
+	/// precision mediump float;
+	/// varying float edge;
+	/// void main() {
+	/// #if defined(GL_OES_standard_derivatives)
+	///     gl_FragColor = vec4(fwidth(edge));
+	/// #else
+	///     gl_FragColor = vec4(edge);
+	/// #endif
+	/// }";
+	///
+	/// let shader = ShaderPreprocessor::new().process(fragment, ShaderType::Fragment, GlslVersion::V300Es).unwrap();
+	/// assert_eq!(
+	///     shader.source,
+	///     "#version 300 es
+	/// #if 1
+	/// #endif
+	/// precision mediump float;
+	/// out vec4 miniquad_FragColor;
+	/// in float edge;
+	/// void main() {
+	/// #if 1
+	///     miniquad_FragColor = vec4(fwidth(edge));
+	/// #else
+	///     miniquad_FragColor = vec4(edge);
+	/// #endif
+	/// }"
+	/// );
+	/// ```
+	pub fn process(&self, source: &str, shader_type: ShaderType, version: GlslVersion) -> Result<PreprocessedShader, PreprocessError> {
+		let mut lines = vec![];
+		let mut source_version = None;
+		self.expand(source, None, &mut vec![], &mut HashSet::new(), &mut source_version, &mut lines)?;
+
+		let legacy_source = source_version.is_none_or(|version| version < 130);
+		let fragment = matches!(shader_type, ShaderType::Fragment);
+		let lines = match (legacy_source, version == GlslVersion::V100) {
+			(true, false) => upgrade(lines, fragment),
+			(false, true) => downgrade(lines, fragment)?,
+			_ => lines,
+		};
+		let mut lines = strip_layouts(lines, fragment, version);
+
+		// declarations go after the leading directives and precisions, #extension has to come before any of them
+		let mut declarations = vec![];
+		if fragment && version.is_es() {
+			let text = lines.iter().map(|(line, _)| line.as_str()).collect::<Vec<_>>().join("\n");
+			let precisions = text.lines().filter(|line| line.trim_start().starts_with("precision")).collect::<Vec<_>>();
+			for type_name in ["float", "sampler3D", "sampler2DArray", "sampler2DShadow", "samplerCubeShadow", "sampler2DArrayShadow"] {
+				let declared = precisions.iter().any(|line| line.split(|c: char| !is_ident(c)).any(|word| word == type_name));
+				if !declared && (type_name == "float" || find_word(&text, type_name).is_some()) {
+					declarations.push(format!("precision mediump {};", type_name));
+				}
+			}
+		}
+		if fragment && version != GlslVersion::V100 && lines.iter().any(|(line, _)| find_word(line, FRAG_COLOR).is_some()) {
+			declarations.push(format!("out vec4 {};", FRAG_COLOR));
+		}
+		let position = lines
+			.iter()
+			.position(|(line, _)| {
+				let line = line.trim_start();
+				!line.is_empty() && !line.starts_with('#') && !line.starts_with("//") && !line.starts_with("precision")
+			})
+			.unwrap_or(lines.len());
+		lines.splice(position..position, declarations.into_iter().map(|line| (line, None)));
+
+		let mut header = vec![(version.header().to_string(), None)];
+		header.extend(self.defines.iter().map(|(name, value)| (format!("#define {} {}", name, value), None)));
+		if version == GlslVersion::V100 && fragment && lines.iter().any(|(line, _)| find_word(line, "texture2DLodEXT").is_some() || find_word(line, "textureCubeLodEXT").is_some()) {
+			header.push(("#extension GL_EXT_shader_texture_lod : enable".to_string(), None));
+		}
+		lines.splice(0..0, header);
+
+		Ok(PreprocessedShader {
+			source: lines.iter().map(|(line, _)| line.as_str()).collect::<Vec<_>>().join("\n"),
+			lines: lines.into_iter().map(|(_, origin)| origin).collect(),
+		})
+	}
+
+	/// Inlines the includes and drops `#version`, recording the first one
+	fn expand(
+		&self,
+		source: &str,
+		file: Option<&str>,
+		stack: &mut Vec<String>,
+		once: &mut HashSet<String>,
+		version: &mut Option<u32>,
+		out: &mut Vec<(String, Option<SourceLocation>)>,
+	) -> Result<(), PreprocessError> {
+		for (n, line) in source.lines().enumerate() {
+			let at = SourceLocation {
+				file: file.map(|file| file.to_string()),
+				line: n as u32 + 1,
+			};
+			let directive = directive(line);
+
+			if let Some(include) = directive.and_then(|directive| directive.strip_prefix("include")) {
+				let name = include.trim().trim_matches(['"', '<', '>']).to_string();
+				if stack.contains(&name) {
+					return Err(PreprocessError::IncludeCycle { name, at });
+				}
+				if once.contains(&name) {
+					continue;
+				}
+				let Some(included) = self.files.get(&name) else {
+					return Err(PreprocessError::MissingInclude { name, at });
+				};
+				stack.push(name.clone());
+				self.expand(included, Some(&name), stack, once, version, out)?;
+				stack.pop();
+			} else if directive.is_some_and(|directive| directive.starts_with("pragma") && directive.split_whitespace().nth(1) == Some("once")) {
+				once.extend(file.map(|file| file.to_string()));
+			} else if let Some(number) = directive.and_then(|directive| directive.strip_prefix("version")) {
+				version.get_or_insert(number.split_whitespace().next().and_then(|number| number.parse().ok()).unwrap_or(100));
+			} else {
+				out.push((line.to_string(), Some(at)));
+			}
+		}
+		Ok(())
+	}
+}
+
+type Lines = Vec<(String, Option<SourceLocation>)>;
+
+/// GLSL 1.00 to 1.30+
+fn upgrade(lines: Lines, fragment: bool) -> Lines {
+	let mut words = vec![
+		("attribute", "in"),
+		("varying", if fragment { "in" } else { "out" }),
+		("texture2D", "texture"),
+		("textureCube", "texture"),
+		("texture2DLod", "textureLod"),
+		("textureCubeLod", "textureLod"),
+		("texture2DLodEXT", "textureLod"),
+		("textureCubeLodEXT", "textureLod"),
+	];
+	if fragment {
+		words.push(("gl_FragColor", FRAG_COLOR));
+	}
+
+	lines
+		.into_iter()
+		.filter(|(line, _)| !(directive(line).is_some_and(|directive| directive.starts_with("extension")) && CORE_EXTENSIONS.iter().any(|name| find_word(line, name).is_some())))
+		.map(|(line, origin)| (words.iter().fold(resolve_core_extensions(line), |line, (from, to)| replace_word(&line, from, to)), origin))
+		.collect()
+}
+
+/// GLSL 1.00 extensions that are core since GLSL ES 3.00, their `#extension` lines are dropped by `upgrade`
+const CORE_EXTENSIONS: [&str; 2] = ["GL_EXT_shader_texture_lod", "GL_OES_standard_derivatives"];
+
+/// The macros of core extensions are not defined anymore, conditionals over them become constants
+fn resolve_core_extensions(line: String) -> String {
+	let mut words = directive(&line).unwrap_or("").split_whitespace();
+	match (words.next(), words.next()) {
+		(Some("ifdef"), Some(name)) if CORE_EXTENSIONS.contains(&name) => "#if 1".to_string(),
+		(Some("ifndef"), Some(name)) if CORE_EXTENSIONS.contains(&name) => "#if 0".to_string(),
+		(Some("if" | "elif"), _) => CORE_EXTENSIONS
+			.iter()
+			.fold(line, |line, name| line.replace(&format!("defined({})", name), "1").replace(&format!("defined {}", name), "1")),
+		_ => line,
+	}
+}
+
+/// `line` after the `#`, `None` if it is not a preprocessor directive
+fn directive(line: &str) -> Option<&str> {
+	line.trim_start().strip_prefix('#').map(|directive| directive.trim_start())
+}
+
+/// GLSL 1.30+ to GLSL 1.00
+fn downgrade(lines: Lines, fragment: bool) -> Result<Lines, PreprocessError> {
+	let cube_samplers: Vec<String> = global_declarations(&lines)
+		.filter_map(|(_, words)| match words.as_slice() {
+			["uniform", .., "samplerCube", name] => Some(name.to_string()),
+			_ => None,
+		})
+		.collect();
+	let outputs: Vec<String> = match fragment {
+		true => global_declarations(&lines)
+			.filter_map(|(_, words)| match words.as_slice() {
+				["out", .., name] => Some(name.to_string()),
+				_ => None,
+			})
+			.collect(),
+		false => vec![],
+	};
+	if outputs.len() > 1 {
+		return Err(PreprocessError::Unsupported(format!(
+			"GLSL 100 fragment shaders have one output, this one has {}: {}",
+			outputs.len(),
+			outputs.join(", ")
+		)));
+	}
+	let lod_suffix = if fragment { "EXT" } else { "" };
+	let declarations: HashSet<usize> = global_declarations(&lines).map(|(i, _)| i).collect();
+
+	let mut result = vec![];
+	for (i, (line, origin)) in lines.into_iter().enumerate() {
+		let mut line = line;
+		if declarations.contains(&i) {
+			let stripped = strip_layout(&line);
+			match (stripped.split_whitespace().next(), fragment) {
+				(Some("out"), true) => continue,
+				(Some("in"), false) => line = replace_word(&stripped, "in", "attribute"),
+				(Some("in"), true) | (Some("out"), false) => line = replace_word(&stripped, if fragment { "in" } else { "out" }, "varying"),
+				_ => {}
+			}
+		}
+		for output in &outputs {
+			line = replace_word(&line, output, "gl_FragColor");
+		}
+		line = replace_calls(&line, "texture", |sampler| {
+			if cube_samplers.iter().any(|name| name == sampler) {
+				"textureCube".to_string()
+			} else {
+				"texture2D".to_string()
+			}
+		});
+		line = replace_calls(&line, "textureLod", |sampler| {
+			let function = if cube_samplers.iter().any(|name| name == sampler) { "textureCubeLod" } else { "texture2DLod" };
+			format!("{}{}", function, lod_suffix)
+		});
+		result.push((line, origin));
+	}
+	Ok(result)
+}
+
+/// Drops `layout(...)` of inputs and outputs where the target does not allow it:
+/// all of them on GLSL 1.00 and 1.30, vertex outputs and fragment inputs on GLSL ES 3.00
+fn strip_layouts(mut lines: Lines, fragment: bool, version: GlslVersion) -> Lines {
+	let strip: Vec<usize> = global_declarations(&lines)
+		.filter(|(_, words)| {
+			matches!(
+				(version, words.first().copied(), fragment),
+				(GlslVersion::V100 | GlslVersion::V130, Some("in" | "out"), _) | (GlslVersion::V300Es, Some("out"), false) | (GlslVersion::V300Es, Some("in"), true)
+			)
+		})
+		.map(|(i, _)| i)
+		.collect();
+	for i in strip {
+		lines[i].0 = strip_layout(&lines[i].0);
+	}
+	lines
+}
+
+/// Lines declaring something at the top level, with their words minus `layout(...)` and precisions
+fn global_declarations(lines: &Lines) -> impl Iterator<Item = (usize, Vec<&str>)> {
+	let mut depth = 0i32;
+	lines.iter().enumerate().filter_map(move |(i, (line, _))| {
+		let global = depth == 0;
+		for c in line.split("//").next().unwrap().chars() {
+			match c {
+				'{' | '(' => depth += 1,
+				'}' | ')' => depth -= 1,
+				_ => {}
+			}
+		}
+		let line = line.trim_start();
+		if !global || line.starts_with('#') {
+			return None;
+		}
+		let line = match line.strip_prefix("layout") {
+			Some(rest) => rest.trim_start().strip_prefix('(').and_then(|rest| rest.split_once(')')).map_or(rest, |(_, rest)| rest),
+			None => line,
+		};
+		let words: Vec<&str> = line.split(|c: char| !is_ident(c)).filter(|word| !word.is_empty() && !matches!(*word, "lowp" | "mediump" | "highp")).collect();
+		(!words.is_empty() && line.contains(';')).then_some((i, words))
+	})
+}
+
+fn strip_layout(line: &str) -> String {
+	let indent = &line[..line.len() - line.trim_start().len()];
+	match line.trim_start().strip_prefix("layout").and_then(|rest| rest.split_once(')')) {
+		Some((_, rest)) => format!("{}{}", indent, rest.trim_start()),
+		None => line.to_string(),
+	}
+}
+
+fn is_ident(c: char) -> bool {
+	c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Replaces whole identifiers only, `in` but not `int`
+fn replace_word(line: &str, from: &str, to: &str) -> String {
+	let mut result = String::with_capacity(line.len());
+	let mut rest = line;
+	while let Some(i) = rest.find(from) {
+		let before = rest[..i].chars().next_back().or_else(|| result.chars().next_back());
+		let after = rest[i + from.len()..].chars().next();
+		result.push_str(&rest[..i]);
+		if before.is_some_and(is_ident) || after.is_some_and(is_ident) {
+			result.push_str(from);
+		} else {
+			result.push_str(to);
+		}
+		rest = &rest[i + from.len()..];
+	}
+	result.push_str(rest);
+	result
+}
+
+/// `function(sampler, ...)` to `rename(sampler)(sampler, ...)`
+fn replace_calls(line: &str, function: &str, rename: impl Fn(&str) -> String) -> String {
+	let mut result = String::with_capacity(line.len());
+	let mut rest = line;
+	while let Some(i) = rest.find(function) {
+		let before = rest[..i].chars().next_back().or_else(|| result.chars().next_back());
+		let after = rest[i + function.len()..].trim_start();
+		result.push_str(&rest[..i]);
+		rest = &rest[i + function.len()..];
+		match after.strip_prefix('(') {
+			Some(arguments) if !before.is_some_and(is_ident) => {
+				let sampler = arguments.trim_start().split(|c: char| !is_ident(c)).next().unwrap_or("");
+				result.push_str(&rename(sampler));
+			}
+			_ => result.push_str(function),
+		}
+	}
+	result.push_str(rest);
+	result
+}
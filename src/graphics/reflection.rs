@@ -0,0 +1,229 @@
+//! Shader introspection, see `RenderingBackend::reflect_shader`.
+
+use super::*;
+
+/// An active vertex shader input.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AttributeDesc {
+	pub name: String,
+	/// `Float1`-`Float4`, `Int1`-`Int4` or `Mat4`
+	pub format: VertexFormat,
+}
+
+/// What a linked program uses, returned by `RenderingBackend::reflect_shader`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShaderReflection {
+	/// Uniforms, images and uniform blocks in declaration order, ready for `new_shader`.
+	/// Uniform blocks are bound to their index in `uniform_blocks`.
+	pub meta: ShaderMeta,
+	/// Vertex shader inputs in declaration order.
+	pub attributes: Vec<AttributeDesc>,
+	/// Both stages, names are looked up in it
+	source: String,
+}
+
+/// A difference between a `ShaderMeta` and the shader it describes, see `ShaderReflection::validate`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ShaderMismatch {
+	/// Used by the shader, but missing from `ShaderMeta::uniforms`
+	MissingUniform(UniformDesc),
+	/// Used by the shader, but missing from `ShaderMeta::images`
+	MissingImage(ImageDesc),
+	/// Used by the shader, but missing from `ShaderMeta::uniform_blocks`
+	MissingUniformBlock(String),
+	/// Listed in the meta, but not declared in either stage
+	UnknownName(String),
+	/// Declared with another type or array length, as `(uniform_type, array_count)`
+	UniformType {
+		name: String,
+		meta: (UniformType, usize),
+		shader: (UniformType, usize),
+	},
+	ImageType {
+		name: String,
+		meta: ImageType,
+		shader: ImageType,
+	},
+	/// The block members differ in type, array length or order
+	UniformBlockLayout {
+		name: String,
+		meta: UniformBlockLayout,
+		shader: UniformBlockLayout,
+	},
+	/// Declared as a sampler, plain uniform or uniform block, but listed as another one in the meta
+	Misplaced(String),
+	/// A uniform or attribute of a type `ShaderMeta` can not describe, like `mat3` or `uint`
+	UnsupportedType {
+		name: String,
+		gl_type: u32,
+	},
+}
+
+impl ShaderReflection {
+	/// Sorts everything in declaration order and binds the uniform blocks to their index.
+	pub(crate) fn new(mut meta: ShaderMeta, mut attributes: Vec<AttributeDesc>, vertex: &str, fragment: &str) -> ShaderReflection {
+		let source = format!("{}\n{}", vertex, fragment);
+		let position = |name: &str| find_word(&source, name).unwrap_or(usize::MAX);
+
+		meta.uniforms.uniforms.sort_by_key(|uniform| position(&uniform.name));
+		meta.images.sort_by_key(|image| position(&image.name));
+		meta.uniform_blocks.sort_by_key(|block| position(&block.name));
+		for (binding, block) in meta.uniform_blocks.iter_mut().enumerate() {
+			block.binding = binding as u32;
+		}
+		attributes.sort_by_key(|attribute| find_word(vertex, &attribute.name).unwrap_or(usize::MAX));
+
+		ShaderReflection { meta, attributes, source }
+	}
+
+	/// Check a handwritten `ShaderMeta` against the shader, failing with `ShaderError::MetaMismatch`.
+	///
+	/// Unused uniforms are optimized out by the driver, so a name missing from the program
+	/// is only reported when it is not declared in the source either.
+	///
+	/// ```
+	/// # use miniquad_wasm_bindgen::*;
+	/// # let mut ctx = NullContext::new();
+	/// let fragment = "#version 100
+	/// uniform lowp vec4 tint;
+	/// uniform sampler2D tex;
+	/// varying lowp vec2 uv;
+	/// void main() { gl_FragColor = texture2D(tex, uv) * tint; }";
+	/// # let vertex = "";
+	/// let reflection = ctx.reflect_shader(ShaderSource::new(vertex, fragment)).unwrap();
+	///
+	/// let meta = ShaderMeta {
+	///     uniforms: UniformBlockLayout { uniforms: vec![UniformDesc::new("tint", UniformType::Float3)] },
+	///     images: vec![ImageDesc::new("texture", ImageType::Float)],
+	///     uniform_blocks: vec![],
+	/// };
+	/// let Err(ShaderError::MetaMismatch(mismatches)) = reflection.validate(&meta) else { panic!() };
+	/// assert_eq!(
+	///     mismatches,
+	///     [
+	///         ShaderMismatch::UniformType { name: "tint".to_string(), meta: (UniformType::Float3, 1), shader: (UniformType::Float4, 1) },
+	///         ShaderMismatch::UnknownName("texture".to_string()),
+	///         ShaderMismatch::MissingImage(ImageDesc::new("tex", ImageType::Float)),
+	///     ]
+	/// );
+	/// assert!(reflection.validate(&reflection.meta).is_ok());
+	/// ```
+	pub fn validate(&self, meta: &ShaderMeta) -> Result<(), ShaderError> {
+		let shader = &self.meta;
+		let mut mismatches = vec![];
+		let declared_elsewhere = |name: &str| {
+			shader.uniforms.uniforms.iter().any(|uniform| uniform.name == name)
+				|| shader.images.iter().any(|image| image.name == name)
+				|| shader
+					.uniform_blocks
+					.iter()
+					.any(|block| block.name == name || block.layout.uniforms.iter().any(|uniform| uniform.name == name))
+		};
+		let unmatched = |name: &str, mismatches: &mut Vec<ShaderMismatch>| {
+			if declared_elsewhere(name) {
+				mismatches.push(ShaderMismatch::Misplaced(name.to_string()));
+			} else if find_word(&self.source, name).is_none() {
+				mismatches.push(ShaderMismatch::UnknownName(name.to_string()));
+			}
+		};
+
+		for uniform in &meta.uniforms.uniforms {
+			match shader.uniforms.uniforms.iter().find(|active| active.name == uniform.name) {
+				Some(active) if (active.uniform_type, active.array_count) != (uniform.uniform_type, uniform.array_count) => mismatches.push(ShaderMismatch::UniformType {
+					name: uniform.name.clone(),
+					meta: (uniform.uniform_type, uniform.array_count),
+					shader: (active.uniform_type, active.array_count),
+				}),
+				Some(_) => {}
+				None => unmatched(&uniform.name, &mut mismatches),
+			}
+		}
+		for image in &meta.images {
+			match shader.images.iter().find(|active| active.name == image.name) {
+				Some(active) if active.image_type != image.image_type => mismatches.push(ShaderMismatch::ImageType {
+					name: image.name.clone(),
+					meta: image.image_type,
+					shader: active.image_type,
+				}),
+				Some(_) => {}
+				None => unmatched(&image.name, &mut mismatches),
+			}
+		}
+		for block in &meta.uniform_blocks {
+			// member names are only documentation, the types make the layout
+			let types = |layout: &UniformBlockLayout| layout.uniforms.iter().map(|uniform| (uniform.uniform_type, uniform.array_count)).collect::<Vec<_>>();
+			match shader.uniform_blocks.iter().find(|active| active.name == block.name) {
+				Some(active) if types(&active.layout) != types(&block.layout) => mismatches.push(ShaderMismatch::UniformBlockLayout {
+					name: block.name.clone(),
+					meta: block.layout.clone(),
+					shader: active.layout.clone(),
+				}),
+				Some(_) => {}
+				None => unmatched(&block.name, &mut mismatches),
+			}
+		}
+
+		let listed =
+			|name: &str| meta.uniforms.uniforms.iter().any(|uniform| uniform.name == name) || meta.images.iter().any(|image| image.name == name) || meta.uniform_blocks.iter().any(|block| block.name == name);
+		for uniform in shader.uniforms.uniforms.iter().filter(|uniform| !listed(&uniform.name)) {
+			mismatches.push(ShaderMismatch::MissingUniform(uniform.clone()));
+		}
+		for image in shader.images.iter().filter(|image| !listed(&image.name)) {
+			mismatches.push(ShaderMismatch::MissingImage(image.clone()));
+		}
+		for block in shader.uniform_blocks.iter().filter(|block| !listed(&block.name)) {
+			mismatches.push(ShaderMismatch::MissingUniformBlock(block.name.clone()));
+		}
+
+		match mismatches.is_empty() {
+			true => Ok(()),
+			false => Err(ShaderError::MetaMismatch(mismatches)),
+		}
+	}
+}
+
+pub(crate) fn uniform_type(gl_type: GLenum) -> Option<UniformType> {
+	match gl_type {
+		GL_FLOAT => Some(UniformType::Float1),
+		GL_FLOAT_VEC2 => Some(UniformType::Float2),
+		GL_FLOAT_VEC3 => Some(UniformType::Float3),
+		GL_FLOAT_VEC4 => Some(UniformType::Float4),
+		GL_INT => Some(UniformType::Int1),
+		GL_INT_VEC2 => Some(UniformType::Int2),
+		GL_INT_VEC3 => Some(UniformType::Int3),
+		GL_INT_VEC4 => Some(UniformType::Int4),
+		GL_FLOAT_MAT4 => Some(UniformType::Mat4),
+		_ => None,
+	}
+}
+
+pub(crate) fn image_type(gl_type: GLenum) -> Option<ImageType> {
+	match gl_type {
+		GL_SAMPLER_2D | GL_SAMPLER_CUBE | GL_SAMPLER_2D_ARRAY | GL_SAMPLER_3D => Some(ImageType::Float),
+		GL_SAMPLER_2D_SHADOW | GL_SAMPLER_CUBE_SHADOW | GL_SAMPLER_2D_ARRAY_SHADOW => Some(ImageType::Shadow),
+		_ => None,
+	}
+}
+
+pub(crate) fn attribute_format(gl_type: GLenum) -> Option<VertexFormat> {
+	match uniform_type(gl_type)? {
+		UniformType::Float1 => Some(VertexFormat::Float1),
+		UniformType::Float2 => Some(VertexFormat::Float2),
+		UniformType::Float3 => Some(VertexFormat::Float3),
+		UniformType::Float4 => Some(VertexFormat::Float4),
+		UniformType::Int1 => Some(VertexFormat::Int1),
+		UniformType::Int2 => Some(VertexFormat::Int2),
+		UniformType::Int3 => Some(VertexFormat::Int3),
+		UniformType::Int4 => Some(VertexFormat::Int4),
+		UniformType::Mat4 => Some(VertexFormat::Mat4),
+	}
+}
+
+/// Byte offset of the first `word` in `source` that is not part of a longer identifier
+pub(crate) fn find_word(source: &str, word: &str) -> Option<usize> {
+	let is_ident = |c: char| c.is_ascii_alphanumeric() || c == '_';
+	source
+		.match_indices(word)
+		.map(|(i, _)| i)
+		.find(|&i| !source[..i].chars().next_back().is_some_and(is_ident) && !source[i + word.len()..].chars().next().is_some_and(is_ident))
+}
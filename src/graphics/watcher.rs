@@ -0,0 +1,258 @@
+//! Reloading shaders when their files change, see `ShaderWatcher`.
+
+#[cfg(target_os = "linux")]
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use super::*;
+
+/// Reloads shaders with `RenderingBackend::reload_shader` when their files change.
+///
+/// Watches the directories with inotify on Linux, other platforms and directories inotify
+/// can not watch fall back to comparing modification times on every `poll`.
+/// Nothing happens unless a watcher is made, it is meant for development builds.
+///
+/// ```no_run
+/// # use miniquad_wasm_bindgen::*;
+/// # let mut ctx = NullContext::new();
+/// # let meta = ShaderMeta::default();
+/// let vertex = std::fs::read_to_string("shaders/sprite.vert").unwrap();
+/// let fragment = std::fs::read_to_string("shaders/sprite.frag").unwrap();
+/// let shader = ctx.new_shader(ShaderSource::new(&vertex, &fragment), meta.clone()).unwrap();
+///
+/// let mut watcher = ShaderWatcher::new();
+/// watcher.watch(shader, "shaders/sprite.vert", "shaders/sprite.frag", meta);
+///
+/// // once per frame
+/// for (shader, result) in watcher.poll(&mut ctx) {
+///     if let Err(err) = result {
+///         eprintln!("{:?} kept its old program: {}", shader, err);
+///     }
+/// }
+/// ```
+pub struct ShaderWatcher {
+	shaders: Vec<WatchedShader>,
+	#[cfg(target_os = "linux")]
+	inotify: Option<Inotify>,
+}
+
+struct WatchedShader {
+	id: ShaderId,
+	vertex: WatchedFile,
+	fragment: WatchedFile,
+	meta: ShaderMeta,
+	/// Reload on any modification time change, inotify is not watching the files
+	polled: bool,
+}
+
+struct WatchedFile {
+	path: PathBuf,
+	modified: Option<SystemTime>,
+}
+
+impl WatchedFile {
+	fn new(path: &Path) -> WatchedFile {
+		WatchedFile {
+			path: path.to_path_buf(),
+			modified: modified(path),
+		}
+	}
+
+	/// Records the new modification time
+	fn changed(&mut self) -> bool {
+		let modified = modified(&self.path);
+		let changed = modified != self.modified;
+		self.modified = modified;
+		changed
+	}
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+	std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// The directory, editors often save by replacing the file, which ends watches on the file itself
+fn directory(path: &Path) -> &Path {
+	match path.parent() {
+		Some(parent) if !parent.as_os_str().is_empty() => parent,
+		_ => Path::new("."),
+	}
+}
+
+/// `path` the way inotify events name it
+fn event_path(path: &Path) -> PathBuf {
+	directory(path).join(path.file_name().unwrap_or_default())
+}
+
+impl Default for ShaderWatcher {
+	fn default() -> ShaderWatcher {
+		ShaderWatcher::new()
+	}
+}
+
+impl ShaderWatcher {
+	/// Uses inotify when available.
+	pub fn new() -> ShaderWatcher {
+		ShaderWatcher {
+			shaders: vec![],
+			#[cfg(target_os = "linux")]
+			inotify: Inotify::new(),
+		}
+	}
+
+	/// Only compares modification times, for file systems inotify does not report changes on, like network shares.
+	pub fn polling() -> ShaderWatcher {
+		ShaderWatcher {
+			shaders: vec![],
+			#[cfg(target_os = "linux")]
+			inotify: None,
+		}
+	}
+
+	/// Reload `id` from `vertex` and `fragment` when either changes, replacing an earlier `watch` of `id`.
+	pub fn watch(&mut self, id: ShaderId, vertex: impl AsRef<Path>, fragment: impl AsRef<Path>, meta: ShaderMeta) {
+		let (vertex, fragment) = (vertex.as_ref(), fragment.as_ref());
+		self.unwatch(id);
+
+		#[allow(unused_mut)]
+		let mut polled = true;
+		#[cfg(target_os = "linux")]
+		if let Some(inotify) = &mut self.inotify {
+			polled = !(inotify.add(directory(vertex)) && inotify.add(directory(fragment)));
+		}
+
+		self.shaders.push(WatchedShader {
+			id,
+			vertex: WatchedFile::new(vertex),
+			fragment: WatchedFile::new(fragment),
+			meta,
+			polled,
+		});
+	}
+
+	/// Stop reloading `id`, call it before deleting the shader.
+	pub fn unwatch(&mut self, id: ShaderId) {
+		let Some(index) = self.shaders.iter().position(|shader| shader.id == id) else {
+			return;
+		};
+		#[allow(unused_variables)]
+		let removed = self.shaders.remove(index);
+		#[cfg(target_os = "linux")]
+		if let Some(inotify) = &mut self.inotify {
+			for unwatched in [directory(&removed.vertex.path), directory(&removed.fragment.path)] {
+				if !self.shaders.iter().any(|shader| directory(&shader.vertex.path) == unwatched || directory(&shader.fragment.path) == unwatched) {
+					inotify.remove(unwatched);
+				}
+			}
+		}
+	}
+
+	/// Reload the shaders with changed files, returning the result of every reload.
+	/// A shader that fails to compile keeps its old program, until the next change.
+	/// Files that can not be read, like in the middle of a save, are tried again on their next change.
+	pub fn poll(&mut self, ctx: &mut dyn RenderingBackend) -> Vec<(ShaderId, Result<(), ShaderError>)> {
+		#[allow(unused_mut)]
+		let mut events = HashSet::new();
+		#[cfg(target_os = "linux")]
+		if let Some(inotify) = &mut self.inotify {
+			events = inotify.read();
+		}
+
+		let mut results = vec![];
+		for shader in &mut self.shaders {
+			let reported = events.contains(&event_path(&shader.vertex.path)) || events.contains(&event_path(&shader.fragment.path));
+			// both, `|` does not short-circuit
+			if !(reported || shader.polled && (shader.vertex.changed() | shader.fragment.changed())) {
+				continue;
+			}
+			let (Ok(vertex), Ok(fragment)) = (std::fs::read_to_string(&shader.vertex.path), std::fs::read_to_string(&shader.fragment.path)) else {
+				shader.vertex.modified = None;
+				shader.fragment.modified = None;
+				continue;
+			};
+			results.push((shader.id, ctx.reload_shader(shader.id, ShaderSource::new(&vertex, &fragment), shader.meta.clone())));
+		}
+		results
+	}
+}
+
+#[cfg(target_os = "linux")]
+struct Inotify {
+	fd: libc::c_int,
+	/// Watch descriptors of the directories, with every spelling of the directory that was added,
+	/// inotify gives the same descriptor to "shaders" and "./shaders"
+	directories: HashMap<libc::c_int, Vec<PathBuf>>,
+}
+
+#[cfg(target_os = "linux")]
+impl Inotify {
+	fn new() -> Option<Inotify> {
+		let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+		(fd >= 0).then(|| Inotify { fd, directories: HashMap::new() })
+	}
+
+	/// Watches `directory` for files written or moved into it, `false` if it can not be watched
+	fn add(&mut self, directory: &Path) -> bool {
+		use std::os::unix::ffi::OsStrExt;
+
+		let Ok(path) = std::ffi::CString::new(directory.as_os_str().as_bytes()) else {
+			return false;
+		};
+		let wd = unsafe { libc::inotify_add_watch(self.fd, path.as_ptr(), libc::IN_CLOSE_WRITE | libc::IN_MOVED_TO) };
+		if wd < 0 {
+			return false;
+		}
+		let spellings = self.directories.entry(wd).or_default();
+		if !spellings.iter().any(|spelling| spelling == directory) {
+			spellings.push(directory.to_path_buf());
+		}
+		true
+	}
+
+	/// Stops watching `directory` once no other spelling of it is watched
+	fn remove(&mut self, directory: &Path) {
+		let Some((&wd, spellings)) = self.directories.iter_mut().find(|(_, spellings)| spellings.iter().any(|spelling| spelling == directory)) else {
+			return;
+		};
+		spellings.retain(|spelling| spelling != directory);
+		if spellings.is_empty() {
+			self.directories.remove(&wd);
+			unsafe { libc::inotify_rm_watch(self.fd, wd) };
+		}
+	}
+
+	/// Paths of the files changed since the last read
+	fn read(&mut self) -> HashSet<PathBuf> {
+		use std::os::unix::ffi::OsStrExt;
+
+		let mut events = HashSet::new();
+		let mut buffer = [0u8; 4096];
+		loop {
+			let read = unsafe { libc::read(self.fd, buffer.as_mut_ptr() as *mut _, buffer.len()) };
+			if read <= 0 {
+				return events;
+			}
+			let mut offset = 0;
+			while offset + std::mem::size_of::<libc::inotify_event>() <= read as usize {
+				let event = unsafe { std::ptr::read_unaligned(buffer.as_ptr().add(offset) as *const libc::inotify_event) };
+				let name_start = offset + std::mem::size_of::<libc::inotify_event>();
+				let name = &buffer[name_start..name_start + event.len as usize];
+				// the name is padded with zeros
+				let name = &name[..name.iter().position(|&byte| byte == 0).unwrap_or(name.len())];
+				for directory in self.directories.get(&event.wd).into_iter().flatten() {
+					events.insert(directory.join(std::ffi::OsStr::from_bytes(name)));
+				}
+				offset = name_start + event.len as usize;
+			}
+		}
+	}
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for Inotify {
+	fn drop(&mut self) {
+		unsafe { libc::close(self.fd) };
+	}
+}
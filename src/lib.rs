@@ -7,7 +7,6 @@ pub mod fs;
 pub mod graphics;
 mod native;
 
-use std::collections::HashMap;
 use std::ops::{Index, IndexMut};
 
 #[cfg(feature = "log-impl")]
@@ -21,41 +20,94 @@ mod default_icon;
 
 pub use native::gl;
 
-#[derive(Clone)]
-pub(crate) struct ResourceManager<T> {
-	id: usize,
-	resources: HashMap<usize, T>,
+/// Resource handle wrapped by `BufferId`, `TextureId` and the other ids: the slot index, and the generation of the slot.
+///
+/// Slots are reused after deletion, but the generation is bumped every time,
+/// so a handle to a deleted resource never resolves to a newer one.
+/// Every context numbers its resources from 0, a handle of another context is not detected.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ResourceId {
+	pub(crate) index: u32,
+	pub(crate) generation: u32,
 }
 
-impl<T> Default for ResourceManager<T> {
-	fn default() -> Self {
-		Self { id: 0, resources: HashMap::new() }
+impl std::fmt::Debug for ResourceId {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}v{}", self.index, self.generation)
 	}
 }
 
+#[derive(Clone)]
+struct Slot<T> {
+	generation: u32,
+	resource: Option<T>,
+}
+
+#[derive(Clone)]
+pub(crate) struct ResourceManager<T> {
+	slots: Vec<Slot<T>>,
+	free: Vec<u32>,
+	/// Handle type name for the panic messages, like "BufferId"
+	kind: &'static str,
+}
+
 impl<T> ResourceManager<T> {
-	pub fn add(&mut self, resource: T) -> usize {
-		self.resources.insert(self.id, resource);
-		self.id += 1;
-		self.id - 1
+	pub fn new(kind: &'static str) -> Self {
+		Self { slots: vec![], free: vec![], kind }
+	}
+
+	pub fn add(&mut self, resource: T) -> ResourceId {
+		match self.free.pop() {
+			Some(index) => {
+				let slot = &mut self.slots[index as usize];
+				slot.resource = Some(resource);
+				ResourceId { index, generation: slot.generation }
+			}
+			None => {
+				self.slots.push(Slot {
+					generation: 0,
+					resource: Some(resource),
+				});
+				ResourceId {
+					index: self.slots.len() as u32 - 1,
+					generation: 0,
+				}
+			}
+		}
+	}
+
+	pub fn remove(&mut self, id: ResourceId) -> Option<T> {
+		let slot = self.slots.get_mut(id.index as usize).filter(|slot| slot.generation == id.generation)?;
+		let resource = slot.resource.take()?;
+
+		// a slot that went through all the generations is retired, old handles could resolve to it again
+		slot.generation = slot.generation.wrapping_add(1);
+		if slot.generation != 0 {
+			self.free.push(id.index);
+		}
+		Some(resource)
+	}
+
+	pub fn get(&self, id: ResourceId) -> Option<&T> {
+		self.slots.get(id.index as usize).filter(|slot| slot.generation == id.generation)?.resource.as_ref()
 	}
 
-	pub fn remove(&mut self, id: usize) -> T {
-		// Let it crash if the resource is not found
-		self.resources.remove(&id).unwrap()
+	pub fn get_mut(&mut self, id: ResourceId) -> Option<&mut T> {
+		self.slots.get_mut(id.index as usize).filter(|slot| slot.generation == id.generation)?.resource.as_mut()
 	}
 }
 
-impl<T> Index<usize> for ResourceManager<T> {
+impl<T> Index<ResourceId> for ResourceManager<T> {
 	type Output = T;
-	fn index(&self, index: usize) -> &Self::Output {
-		&self.resources[&index]
+	fn index(&self, id: ResourceId) -> &Self::Output {
+		self.get(id).unwrap_or_else(|| panic!("{} {:?} is deleted", self.kind, id))
 	}
 }
 
-impl<T> IndexMut<usize> for ResourceManager<T> {
-	fn index_mut(&mut self, index: usize) -> &mut Self::Output {
-		self.resources.get_mut(&index).unwrap()
+impl<T> IndexMut<ResourceId> for ResourceManager<T> {
+	fn index_mut(&mut self, id: ResourceId) -> &mut Self::Output {
+		let kind = self.kind;
+		self.get_mut(id).unwrap_or_else(|| panic!("{} {:?} is deleted", kind, id))
 	}
 }
 
@@ -248,6 +300,7 @@ where
 					native::linux_x11::run(&conf, f);
 				}
 			}
+			conf::LinuxBackend::Headless => native::linux_headless::run(&conf, f).expect("Headless backend failed"),
 		}
 	}
 
@@ -13,8 +13,12 @@ pub use core::ptr::null_mut;
 
 pub const EGL_SUCCESS: u32 = 12288;
 
+pub const EGL_PBUFFER_BIT: u32 = 1;
 pub const EGL_WINDOW_BIT: u32 = 4;
 
+pub const EGL_EXTENSIONS: u32 = 12373;
+pub const EGL_PLATFORM_SURFACELESS_MESA: u32 = 0x31DD;
+
 pub const EGL_ALPHA_SIZE: u32 = 12321;
 pub const EGL_BLUE_SIZE: u32 = 12322;
 pub const EGL_GREEN_SIZE: u32 = 12323;
@@ -66,6 +70,8 @@ pub type PFNEGLWAITNATIVEPROC = ::std::option::Option<unsafe extern "C" fn(engin
 pub type PFNEGLBINDTEXIMAGEPROC = ::std::option::Option<unsafe extern "C" fn(dpy: EGLDisplay, surface: EGLSurface, buffer: EGLint) -> EGLBoolean>;
 pub type PFNEGLRELEASETEXIMAGEPROC = ::std::option::Option<unsafe extern "C" fn(dpy: EGLDisplay, surface: EGLSurface, buffer: EGLint) -> EGLBoolean>;
 pub type PFNEGLSURFACEATTRIBPROC = ::std::option::Option<unsafe extern "C" fn(dpy: EGLDisplay, surface: EGLSurface, attribute: EGLint, value: EGLint) -> EGLBoolean>;
+#[allow(clippy::upper_case_acronyms)]
+pub type PFNEGLGETPLATFORMDISPLAYEXTPROC = ::std::option::Option<unsafe extern "C" fn(platform: u32, native_display: *mut ::std::os::raw::c_void, attrib_list: *const EGLint) -> EGLDisplay>;
 pub type PFNEGLSWAPINTERVALPROC = ::std::option::Option<unsafe extern "C" fn(dpy: EGLDisplay, interval: EGLint) -> EGLBoolean>;
 
 pub struct LibEgl {
@@ -144,6 +150,7 @@ pub enum EglError {
 	NoDisplay,
 	InitializeFailed,
 	CreateContextFailed,
+	CreateSurfaceFailed,
 }
 
 pub struct Egl {}
@@ -158,10 +165,48 @@ pub unsafe fn create_egl_context(egl: &mut LibEgl, display: *mut std::ffi::c_voi
 		return Err(EglError::InitializeFailed);
 	}
 
+	create_context_for_display(egl, display, EGL_WINDOW_BIT, alpha, sample_count)
+}
+
+/// Create a context rendering into an offscreen pbuffer, without any window system.
+/// Prefers Mesa's surfaceless platform and falls back to the default display.
+pub unsafe fn create_egl_pbuffer_context(egl: &mut LibEgl, width: i32, height: i32, alpha: bool, sample_count: i32) -> Result<(EGLContext, EGLSurface, EGLDisplay), EglError> {
+	let mut display = null_mut();
+
+	let extensions = (egl.eglQueryString.unwrap())(/* EGL_NO_DISPLAY */ null_mut(), EGL_EXTENSIONS as _);
+	if !extensions.is_null() && std::ffi::CStr::from_ptr(extensions).to_string_lossy().contains("EGL_MESA_platform_surfaceless") {
+		let get_platform_display: PFNEGLGETPLATFORMDISPLAYEXTPROC = std::mem::transmute((egl.eglGetProcAddress.unwrap())(c"eglGetPlatformDisplayEXT".as_ptr()));
+		if let Some(get_platform_display) = get_platform_display {
+			display = get_platform_display(EGL_PLATFORM_SURFACELESS_MESA, /* EGL_DEFAULT_DISPLAY */ null_mut(), null_mut());
+		}
+	}
+	if display.is_null() || (egl.eglInitialize.unwrap())(display, null_mut(), null_mut()) == 0 {
+		display = (egl.eglGetDisplay.unwrap())(/* EGL_DEFAULT_DISPLAY */ null_mut());
+		if display.is_null() {
+			return Err(EglError::NoDisplay);
+		}
+		if (egl.eglInitialize.unwrap())(display, null_mut(), null_mut()) == 0 {
+			return Err(EglError::InitializeFailed);
+		}
+	}
+
+	let (context, config, display) = create_context_for_display(egl, display, EGL_PBUFFER_BIT, alpha, sample_count)?;
+
+	let surface_attributes = [EGL_WIDTH, width as u32, EGL_HEIGHT, height as u32, EGL_NONE];
+	let surface = (egl.eglCreatePbufferSurface.unwrap())(display, config, surface_attributes.as_ptr() as _);
+	if surface.is_null() {
+		(egl.eglDestroyContext.unwrap())(display, context);
+		return Err(EglError::CreateSurfaceFailed);
+	}
+
+	Ok((context, surface, display))
+}
+
+unsafe fn create_context_for_display(egl: &mut LibEgl, display: EGLDisplay, surface_type: u32, alpha: bool, sample_count: i32) -> Result<(EGLContext, EGLConfig, EGLDisplay), EglError> {
 	let alpha_size = if alpha { 8 } else { 0 };
 	#[rustfmt::skip]
     let cfg_attributes = vec![
-        EGL_SURFACE_TYPE, EGL_WINDOW_BIT,
+        EGL_SURFACE_TYPE, surface_type,
         EGL_RED_SIZE, 8,
         EGL_GREEN_SIZE, 8,
         EGL_BLUE_SIZE, 8,
@@ -175,7 +220,9 @@ pub unsafe fn create_egl_context(egl: &mut LibEgl, display: *mut std::ffi::c_voi
 	let mut cfg_count = 0;
 
 	(egl.eglChooseConfig.unwrap())(display, cfg_attributes.as_ptr() as _, available_cfgs.as_ptr() as _, 32, &mut cfg_count as *mut _ as *mut _);
-	assert!(cfg_count > 0);
+	if cfg_count == 0 {
+		return Err(EglError::CreateContextFailed);
+	}
 	assert!(cfg_count <= 32);
 
 	// find config with 8-bit rgb buffer if available, ndk sample does not trust egl spec
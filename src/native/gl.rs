@@ -26,6 +26,13 @@ pub type GLclampf = f32;
 pub type GLdouble = f64;
 pub type GLclampd = f64;
 
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct __GLsync {
+	_unused: [u8; 0],
+}
+pub type GLsync = *mut __GLsync;
+
 pub const GL_INT_2_10_10_10_REV: u32 = 0x8D9F;
 pub const GL_PROGRAM_POINT_SIZE: u32 = 0x8642;
 pub const GL_STENCIL_ATTACHMENT: u32 = 0x8D20;
@@ -101,6 +108,7 @@ pub const GL_SRC_COLOR: u32 = 0x0300;
 pub const GL_BYTE: u32 = 0x1400;
 pub const GL_TEXTURE_CUBE_MAP_NEGATIVE_Z: u32 = 0x851A;
 pub const GL_LINE_STRIP: u32 = 0x0003;
+pub const GL_LINE_LOOP: u32 = 0x0002;
 pub const GL_TEXTURE_3D: u32 = 0x806F;
 pub const GL_CW: u32 = 0x0900;
 pub const GL_LINEAR: u32 = 0x2601;
@@ -117,12 +125,67 @@ pub const GL_TEXTURE_MIN_FILTER: u32 = 0x2801;
 pub const GL_LINEAR_MIPMAP_NEAREST: u32 = 0x2701;
 pub const GL_EXTENSIONS: u32 = 0x1F03;
 pub const GL_NO_ERROR: u32 = 0;
+pub const GL_INVALID_ENUM: u32 = 0x0500;
+pub const GL_INVALID_VALUE: u32 = 0x0501;
+pub const GL_INVALID_OPERATION: u32 = 0x0502;
+pub const GL_OUT_OF_MEMORY: u32 = 0x0505;
+pub const GL_UNIFORM_BUFFER: u32 = 0x8A11;
+pub const GL_UNIFORM_BLOCK_DATA_SIZE: u32 = 0x8A40;
+pub const GL_UNIFORM_BUFFER_OFFSET_ALIGNMENT: u32 = 0x8A34;
+pub const GL_MAX_UNIFORM_BUFFER_BINDINGS: u32 = 0x8A2F;
+pub const GL_INVALID_INDEX: u32 = 0xFFFFFFFF;
+pub const GL_ACTIVE_UNIFORMS: u32 = 0x8B86;
+pub const GL_ACTIVE_ATTRIBUTES: u32 = 0x8B89;
+pub const GL_ACTIVE_UNIFORM_BLOCKS: u32 = 0x8A36;
+pub const GL_UNIFORM_BLOCK_NAME_LENGTH: u32 = 0x8A41;
+pub const GL_UNIFORM_BLOCK_INDEX: u32 = 0x8A3A;
+pub const GL_UNIFORM_OFFSET: u32 = 0x8A3B;
+pub const GL_ACTIVE_UNIFORM_MAX_LENGTH: u32 = 0x8B87;
+pub const GL_ACTIVE_ATTRIBUTE_MAX_LENGTH: u32 = 0x8B8A;
+pub const GL_FLOAT_VEC2: u32 = 0x8B50;
+pub const GL_FLOAT_VEC3: u32 = 0x8B51;
+pub const GL_FLOAT_VEC4: u32 = 0x8B52;
+pub const GL_INT_VEC2: u32 = 0x8B53;
+pub const GL_INT_VEC3: u32 = 0x8B54;
+pub const GL_INT_VEC4: u32 = 0x8B55;
+pub const GL_FLOAT_MAT4: u32 = 0x8B5C;
+pub const GL_SAMPLER_2D: u32 = 0x8B5E;
+pub const GL_SAMPLER_3D: u32 = 0x8B5F;
+pub const GL_SAMPLER_CUBE: u32 = 0x8B60;
+pub const GL_SAMPLER_2D_SHADOW: u32 = 0x8B62;
+pub const GL_SAMPLER_2D_ARRAY: u32 = 0x8DC1;
+pub const GL_SAMPLER_2D_ARRAY_SHADOW: u32 = 0x8DC4;
+pub const GL_SAMPLER_CUBE_SHADOW: u32 = 0x8DC5;
+pub const GL_SRGB8_ALPHA8: u32 = 0x8C43;
+pub const GL_DEPTH_STENCIL: u32 = 0x84F9;
+pub const GL_DEPTH24_STENCIL8: u32 = 0x88F0;
+pub const GL_DEPTH_STENCIL_ATTACHMENT: u32 = 0x821A;
+pub const GL_UNSIGNED_INT_24_8: u32 = 0x84FA;
+pub const GL_HALF_FLOAT: u32 = 0x140B;
+pub const GL_COMPRESSED_RGB8_ETC2: u32 = 0x9274;
+pub const GL_COMPRESSED_RGBA8_ETC2_EAC: u32 = 0x9278;
+pub const GL_COMPRESSED_RGBA_ASTC_4x4_KHR: u32 = 0x93B0;
+pub const GL_COMPRESSED_RGBA_ASTC_6x6_KHR: u32 = 0x93B4;
+pub const GL_COMPRESSED_RGBA_ASTC_8x8_KHR: u32 = 0x93B7;
+pub const GL_COMPRESSED_RGBA_S3TC_DXT1_EXT: u32 = 0x83F1;
+pub const GL_COMPRESSED_RGBA_S3TC_DXT5_EXT: u32 = 0x83F3;
+pub const GL_PIXEL_PACK_BUFFER: u32 = 0x88EB;
+pub const GL_STREAM_READ: u32 = 0x88E1;
+pub const GL_PACK_ALIGNMENT: u32 = 0x0D05;
+pub const GL_MAP_READ_BIT: u32 = 0x0001;
+pub const GL_SYNC_GPU_COMMANDS_COMPLETE: u32 = 0x9117;
+pub const GL_SYNC_FLUSH_COMMANDS_BIT: u32 = 0x00000001;
+pub const GL_ALREADY_SIGNALED: u32 = 0x911A;
+pub const GL_TIMEOUT_EXPIRED: u32 = 0x911B;
+pub const GL_CONDITION_SATISFIED: u32 = 0x911C;
+pub const GL_WAIT_FAILED: u32 = 0x911D;
 pub const GL_REPLACE: u32 = 0x1E01;
 pub const GL_KEEP: u32 = 0x1E00;
 pub const GL_CCW: u32 = 0x0901;
 pub const GL_TEXTURE_CUBE_MAP_NEGATIVE_X: u32 = 0x8516;
 pub const GL_RGB: u32 = 0x1907;
 pub const GL_TRIANGLE_STRIP: u32 = 0x0005;
+pub const GL_TRIANGLE_FAN: u32 = 0x0006;
 pub const GL_FALSE: u32 = 0;
 pub const GL_ZERO: u32 = 0;
 pub const GL_CULL_FACE: u32 = 0x0B44;
@@ -232,6 +295,19 @@ pub const GL_MAX_CUBE_MAP_TEXTURE_SIZE: u32 = 0x851C;
 pub const GL_MAX_3D_TEXTURE_SIZE: u32 = 0x8073;
 pub const GL_MAX_ARRAY_TEXTURE_LAYERS: u32 = 0x88FF;
 pub const GL_MAX_VERTEX_ATTRIBS: u32 = 0x8869;
+pub const GL_MAX_TEXTURE_IMAGE_UNITS: u32 = 0x8872;
+pub const GL_MAX_VERTEX_UNIFORM_VECTORS: u32 = 0x8DFB;
+pub const GL_MAX_FRAGMENT_UNIFORM_VECTORS: u32 = 0x8DFD;
+pub const GL_MAX_VERTEX_UNIFORM_COMPONENTS: u32 = 0x8B4A;
+pub const GL_MAX_FRAGMENT_UNIFORM_COMPONENTS: u32 = 0x8B49;
+pub const GL_MAX_COLOR_ATTACHMENTS: u32 = 0x8CDF;
+pub const GL_MAX_DRAW_BUFFERS: u32 = 0x8824;
+pub const GL_MAX_SAMPLES: u32 = 0x8D57;
+pub const GL_MAX_TEXTURE_MAX_ANISOTROPY_EXT: u32 = 0x84FF;
+pub const GL_TEXTURE_MAX_ANISOTROPY_EXT: u32 = 0x84FE;
+pub const GL_TEXTURE_COMPARE_MODE: u32 = 0x884C;
+pub const GL_TEXTURE_COMPARE_FUNC: u32 = 0x884D;
+pub const GL_COMPARE_REF_TO_TEXTURE: u32 = 0x884E;
 pub const GL_CLAMP_TO_BORDER: u32 = 0x812D;
 pub const GL_TEXTURE_BORDER_COLOR: u32 = 0x1004;
 pub const GL_UNPACK_ALIGNMENT: u32 = 3317;
@@ -376,6 +452,10 @@ gl_loader!(
 	fn glLinkProgram(program: GLuint) -> (),
 	fn glPixelStorei(pname: GLenum, param: GLint) -> (),
 	fn glGetUniformLocation(program: GLuint, name: *const GLchar) -> GLint,
+	fn glGetUniformBlockIndex(program: GLuint, uniformBlockName: *const GLchar) -> GLuint,
+	fn glUniformBlockBinding(program: GLuint, uniformBlockIndex: GLuint, uniformBlockBinding: GLuint) -> (),
+	fn glGetActiveUniformBlockiv(program: GLuint, uniformBlockIndex: GLuint, pname: GLenum, params: *mut GLint) -> (),
+	fn glBindBufferRange(target: GLenum, index: GLuint, buffer: GLuint, offset: GLintptr, size: GLsizeiptr) -> (),
 	fn glGetShaderiv(shader: GLuint, pname: GLenum, params: *mut GLint) -> (),
 	fn glGetProgramInfoLog(
 		program: GLuint,
@@ -643,9 +723,47 @@ gl_loader!(
 	fn glGenQueries(n: GLsizei, ids: *mut GLuint) -> (),
 	fn glGetQueryObjectiv(id: GLuint, pname: GLenum, params: *mut GLint) -> (),
 	fn glGetQueryObjectui64v(id: GLuint, pname: GLenum, params: *mut GLuint64) -> (),
+	fn glFenceSync(condition: GLenum, flags: GLbitfield) -> GLsync,
+	fn glClientWaitSync(sync: GLsync, flags: GLbitfield, timeout: GLuint64) -> GLenum,
+	fn glDeleteSync(sync: GLsync) -> (),
+	fn glMapBufferRange(target: GLenum, offset: GLintptr, length: GLsizeiptr, access: GLbitfield) -> *mut ::std::os::raw::c_void,
+	fn glUnmapBuffer(target: GLenum) -> GLboolean,
 	fn glFlush() -> (),
 	fn glFinish() -> (),
-	fn glPolygonMode(face: GLenum, mode: GLenum) -> ()
+	fn glPolygonMode(face: GLenum, mode: GLenum) -> (),
+	fn glGenSamplers(count: GLsizei, samplers: *mut GLuint) -> (),
+	fn glDeleteSamplers(count: GLsizei, samplers: *const GLuint) -> (),
+	fn glBindSampler(unit: GLuint, sampler: GLuint) -> (),
+	fn glSamplerParameteri(sampler: GLuint, pname: GLenum, param: GLint) -> (),
+	fn glSamplerParameterf(sampler: GLuint, pname: GLenum, param: GLfloat) -> (),
+	fn glSamplerParameterfv(sampler: GLuint, pname: GLenum, params: *const GLfloat) -> (),
+	fn glEnablei(target: GLenum, index: GLuint) -> (),
+	fn glDisablei(target: GLenum, index: GLuint) -> (),
+	fn glColorMaski(index: GLuint, r: GLboolean, g: GLboolean, b: GLboolean, a: GLboolean) -> (),
+	fn glBlendFuncSeparatei(buf: GLuint, srcRGB: GLenum, dstRGB: GLenum, srcAlpha: GLenum, dstAlpha: GLenum) -> (),
+	fn glBlendEquationSeparatei(buf: GLuint, modeRGB: GLenum, modeAlpha: GLenum) -> (),
+	fn glInvalidateFramebuffer(target: GLenum, numAttachments: GLsizei, attachments: *const GLenum) -> (),
+	fn glCopyTexSubImage2D(target: GLenum, level: GLint, xoffset: GLint, yoffset: GLint, x: GLint, y: GLint, width: GLsizei, height: GLsizei) -> (),
+	fn glDrawElementsInstancedBaseVertex(
+		mode: GLenum,
+		count: GLsizei,
+		type_: GLenum,
+		indices: *const ::std::os::raw::c_void,
+		instancecount: GLsizei,
+		basevertex: GLint
+	) -> (),
+	fn glMultiDrawElements(
+		mode: GLenum,
+		count: *const GLsizei,
+		type_: GLenum,
+		indices: *const *const ::std::os::raw::c_void,
+		drawcount: GLsizei
+	) -> (),
+	fn glGetActiveUniform(program: GLuint, index: GLuint, bufSize: GLsizei, length: *mut GLsizei, size: *mut GLint, type_: *mut GLenum, name: *mut GLchar) -> (),
+	fn glGetActiveAttrib(program: GLuint, index: GLuint, bufSize: GLsizei, length: *mut GLsizei, size: *mut GLint, type_: *mut GLenum, name: *mut GLchar) -> (),
+	fn glGetActiveUniformsiv(program: GLuint, uniformCount: GLsizei, uniformIndices: *const GLuint, pname: GLenum, params: *mut GLint) -> (),
+	fn glGetActiveUniformBlockName(program: GLuint, uniformBlockIndex: GLuint, bufSize: GLsizei, length: *mut GLsizei, uniformBlockName: *mut GLchar) -> (),
+	fn glBindAttribLocation(program: GLuint, index: GLuint, name: *const GLchar) -> ()
 );
 
 // note that glGetString only works after first glSwapBuffer,
@@ -0,0 +1,73 @@
+//! Window-less backend: the GL context renders into an EGL pbuffer of
+//! `window_width`x`window_height`, no X11 or Wayland server required.
+//! Meant for CI and golden-image tests - read the frame back with
+//! `RenderingBackend::default_pass_read_pixels` and call `window::quit()`.
+
+use crate::{
+	event::EventHandler,
+	native::{egl, NativeDisplayData},
+};
+
+/// There is no system clipboard without a display server, keep the contents in memory.
+#[derive(Default)]
+struct HeadlessClipboard {
+	contents: Option<String>,
+}
+
+impl crate::native::Clipboard for HeadlessClipboard {
+	fn get(&mut self) -> Option<String> {
+		self.contents.clone()
+	}
+	fn set(&mut self, data: &str) {
+		self.contents = Some(data.to_owned());
+	}
+}
+
+pub fn run<F>(conf: &crate::conf::Conf, f: &mut Option<F>) -> Option<()>
+where
+	F: 'static + FnOnce() -> Box<dyn EventHandler>,
+{
+	unsafe {
+		let mut libegl = egl::LibEgl::try_load()?;
+		let (context, surface, egl_display) = egl::create_egl_pbuffer_context(&mut libegl, conf.window_width as _, conf.window_height as _, conf.platform.framebuffer_alpha, conf.sample_count).ok()?;
+
+		if (libegl.eglMakeCurrent.unwrap())(egl_display, surface, surface, context) == 0 {
+			return None;
+		}
+
+		crate::native::gl::load_gl_funcs(|proc| {
+			let name = std::ffi::CString::new(proc).unwrap();
+			libegl.eglGetProcAddress.expect("non-null function pointer")(name.as_ptr() as _)
+		});
+
+		let (tx, rx) = std::sync::mpsc::channel();
+		let clipboard = Box::<HeadlessClipboard>::default();
+		crate::set_display(NativeDisplayData {
+			high_dpi: conf.high_dpi,
+			..NativeDisplayData::new(conf.window_width, conf.window_height, tx, clipboard)
+		});
+
+		let mut event_handler = (f.take().unwrap())();
+
+		// No input and no resizes, so the blocking event loop would never wake up:
+		// frames are produced back to back until the application quits.
+		while !crate::native_display().try_lock().unwrap().quit {
+			// Cursor, size and fullscreen requests have no meaning without a window
+			while rx.try_recv().is_ok() {}
+
+			event_handler.update();
+			event_handler.draw();
+
+			(libegl.eglSwapBuffers.unwrap())(egl_display, surface);
+		}
+
+		drop(event_handler);
+
+		(libegl.eglMakeCurrent.unwrap())(egl_display, std::ptr::null_mut(), std::ptr::null_mut(), std::ptr::null_mut());
+		(libegl.eglDestroySurface.unwrap())(egl_display, surface);
+		(libegl.eglDestroyContext.unwrap())(egl_display, context);
+		(libegl.eglTerminate.unwrap())(egl_display);
+
+		Some(())
+	}
+}
@@ -449,7 +449,11 @@ where
 
 		let x11_display = (libx11.XOpenDisplay)(std::ptr::null());
 		if x11_display.is_null() {
-			panic!("XOpenDisplay() failed!");
+			// No X server to talk to (or DISPLAY is not set), let the caller fall back if it has one
+			if matches!(conf.platform.linux_backend, crate::conf::LinuxBackend::X11Only) {
+				panic!("XOpenDisplay() failed!");
+			}
+			return None;
 		}
 
 		// screen selection process. The place to do something about
@@ -62,6 +62,9 @@ pub mod linux_x11;
 #[cfg(target_os = "linux")]
 pub mod linux_wayland;
 
+#[cfg(target_os = "linux")]
+pub mod linux_headless;
+
 #[cfg(target_os = "windows")]
 pub mod windows;
 
@@ -111,6 +111,7 @@ pub const GL_SRC_COLOR: u32 = 0x0300;
 pub const GL_BYTE: u32 = 0x1400;
 pub const GL_TEXTURE_CUBE_MAP_NEGATIVE_Z: u32 = 0x851A;
 pub const GL_LINE_STRIP: u32 = 0x0003;
+pub const GL_LINE_LOOP: u32 = 0x0002;
 pub const GL_TEXTURE_3D: u32 = 0x806F;
 pub const GL_CW: u32 = 0x0900;
 pub const GL_LINEAR: u32 = 0x2601;
@@ -127,12 +128,65 @@ pub const GL_TEXTURE_MIN_FILTER: u32 = 0x2801;
 pub const GL_LINEAR_MIPMAP_NEAREST: u32 = 0x2701;
 pub const GL_EXTENSIONS: u32 = 0x1F03;
 pub const GL_NO_ERROR: u32 = 0;
+pub const GL_INVALID_ENUM: u32 = 0x0500;
+pub const GL_INVALID_VALUE: u32 = 0x0501;
+pub const GL_INVALID_OPERATION: u32 = 0x0502;
+pub const GL_OUT_OF_MEMORY: u32 = 0x0505;
+pub const GL_UNIFORM_BUFFER: u32 = 0x8A11;
+pub const GL_UNIFORM_BLOCK_DATA_SIZE: u32 = 0x8A40;
+pub const GL_UNIFORM_BUFFER_OFFSET_ALIGNMENT: u32 = 0x8A34;
+pub const GL_MAX_UNIFORM_BUFFER_BINDINGS: u32 = 0x8A2F;
+pub const GL_INVALID_INDEX: u32 = 0xFFFFFFFF;
+pub const GL_ACTIVE_UNIFORM_BLOCKS: u32 = 0x8A36;
+pub const GL_UNIFORM_BLOCK_NAME_LENGTH: u32 = 0x8A41;
+pub const GL_UNIFORM_BLOCK_INDEX: u32 = 0x8A3A;
+pub const GL_UNIFORM_OFFSET: u32 = 0x8A3B;
+pub const GL_ACTIVE_UNIFORM_MAX_LENGTH: u32 = 0x8B87;
+pub const GL_ACTIVE_ATTRIBUTE_MAX_LENGTH: u32 = 0x8B8A;
+pub const GL_FLOAT_VEC2: u32 = 0x8B50;
+pub const GL_FLOAT_VEC3: u32 = 0x8B51;
+pub const GL_FLOAT_VEC4: u32 = 0x8B52;
+pub const GL_INT_VEC2: u32 = 0x8B53;
+pub const GL_INT_VEC3: u32 = 0x8B54;
+pub const GL_INT_VEC4: u32 = 0x8B55;
+pub const GL_FLOAT_MAT4: u32 = 0x8B5C;
+pub const GL_SAMPLER_2D: u32 = 0x8B5E;
+pub const GL_SAMPLER_3D: u32 = 0x8B5F;
+pub const GL_SAMPLER_CUBE: u32 = 0x8B60;
+pub const GL_SAMPLER_2D_SHADOW: u32 = 0x8B62;
+pub const GL_SAMPLER_2D_ARRAY: u32 = 0x8DC1;
+pub const GL_SAMPLER_2D_ARRAY_SHADOW: u32 = 0x8DC4;
+pub const GL_SAMPLER_CUBE_SHADOW: u32 = 0x8DC5;
+pub const GL_SRGB8_ALPHA8: u32 = 0x8C43;
+pub const GL_DEPTH_STENCIL: u32 = 0x84F9;
+pub const GL_DEPTH24_STENCIL8: u32 = 0x88F0;
+pub const GL_DEPTH_STENCIL_ATTACHMENT: u32 = 0x821A;
+pub const GL_UNSIGNED_INT_24_8: u32 = 0x84FA;
+pub const GL_HALF_FLOAT: u32 = 0x140B;
+pub const GL_COMPRESSED_RGB8_ETC2: u32 = 0x9274;
+pub const GL_COMPRESSED_RGBA8_ETC2_EAC: u32 = 0x9278;
+pub const GL_COMPRESSED_RGBA_ASTC_4x4_KHR: u32 = 0x93B0;
+pub const GL_COMPRESSED_RGBA_ASTC_6x6_KHR: u32 = 0x93B4;
+pub const GL_COMPRESSED_RGBA_ASTC_8x8_KHR: u32 = 0x93B7;
+pub const GL_COMPRESSED_RGBA_S3TC_DXT1_EXT: u32 = 0x83F1;
+pub const GL_COMPRESSED_RGBA_S3TC_DXT5_EXT: u32 = 0x83F3;
+pub const GL_PIXEL_PACK_BUFFER: u32 = 0x88EB;
+pub const GL_STREAM_READ: u32 = 0x88E1;
+pub const GL_PACK_ALIGNMENT: u32 = 0x0D05;
+pub const GL_MAP_READ_BIT: u32 = 0x0001;
+pub const GL_SYNC_GPU_COMMANDS_COMPLETE: u32 = 0x9117;
+pub const GL_SYNC_FLUSH_COMMANDS_BIT: u32 = 0x00000001;
+pub const GL_ALREADY_SIGNALED: u32 = 0x911A;
+pub const GL_TIMEOUT_EXPIRED: u32 = 0x911B;
+pub const GL_CONDITION_SATISFIED: u32 = 0x911C;
+pub const GL_WAIT_FAILED: u32 = 0x911D;
 pub const GL_REPLACE: u32 = 0x1E01;
 pub const GL_KEEP: u32 = 0x1E00;
 pub const GL_CCW: u32 = 0x0901;
 pub const GL_TEXTURE_CUBE_MAP_NEGATIVE_X: u32 = 0x8516;
 pub const GL_RGB: u32 = 0x1907;
 pub const GL_TRIANGLE_STRIP: u32 = 0x0005;
+pub const GL_TRIANGLE_FAN: u32 = 0x0006;
 pub const GL_FALSE: u32 = 0;
 pub const GL_ZERO: u32 = 0;
 pub const GL_CULL_FACE: u32 = 0x0B44;
@@ -246,6 +300,19 @@ pub const GL_MAX_CUBE_MAP_TEXTURE_SIZE: u32 = 0x851C;
 pub const GL_MAX_3D_TEXTURE_SIZE: u32 = 0x8073;
 pub const GL_MAX_ARRAY_TEXTURE_LAYERS: u32 = 0x88FF;
 pub const GL_MAX_VERTEX_ATTRIBS: u32 = 0x8869;
+pub const GL_MAX_TEXTURE_IMAGE_UNITS: u32 = 0x8872;
+pub const GL_MAX_VERTEX_UNIFORM_VECTORS: u32 = 0x8DFB;
+pub const GL_MAX_FRAGMENT_UNIFORM_VECTORS: u32 = 0x8DFD;
+pub const GL_MAX_VERTEX_UNIFORM_COMPONENTS: u32 = 0x8B4A;
+pub const GL_MAX_FRAGMENT_UNIFORM_COMPONENTS: u32 = 0x8B49;
+pub const GL_MAX_COLOR_ATTACHMENTS: u32 = 0x8CDF;
+pub const GL_MAX_DRAW_BUFFERS: u32 = 0x8824;
+pub const GL_MAX_SAMPLES: u32 = 0x8D57;
+pub const GL_MAX_TEXTURE_MAX_ANISOTROPY_EXT: u32 = 0x84FF;
+pub const GL_TEXTURE_MAX_ANISOTROPY_EXT: u32 = 0x84FE;
+pub const GL_TEXTURE_COMPARE_MODE: u32 = 0x884C;
+pub const GL_TEXTURE_COMPARE_FUNC: u32 = 0x884D;
+pub const GL_COMPARE_REF_TO_TEXTURE: u32 = 0x884E;
 pub const GL_CLAMP_TO_BORDER: u32 = 0x812D;
 pub const GL_TEXTURE_BORDER_COLOR: u32 = 0x1004;
 pub const GL_UNPACK_ALIGNMENT: u32 = 3317;
@@ -349,8 +416,17 @@ pub fn glGetError() -> GLenum {
 }
 
 pub fn glGetString(name: GLenum) -> *const GLubyte {
-	let param = get_gl().get_parameter(name).unwrap();
-	let param = param.as_string().unwrap();
+	let param = if name == GL_EXTENSIONS {
+		// WebGL has no extension string, and extensions only work after being enabled
+		let gl = get_gl();
+		let names = gl.get_supported_extensions().map_or(vec![], |names| names.iter().filter_map(|name| name.as_string()).collect::<Vec<_>>());
+		for name in &names {
+			let _ = gl.get_extension(name);
+		}
+		names.join(" ")
+	} else {
+		get_gl().get_parameter(name).unwrap().as_string().unwrap()
+	};
 
 	let c_str = std::ffi::CString::new(param).unwrap();
 	let c_str = std::mem::ManuallyDrop::new(c_str);
@@ -361,12 +437,16 @@ pub fn glGetString(name: GLenum) -> *const GLubyte {
 
 // TODO: Correct implementation of glGetIntegerv
 #[inline(always)]
-pub(crate) unsafe fn glGetIntegerv(_: u32, data: *mut GLint) {
-	#[cfg(feature = "log-impl")]
-	crate::warn!("STUB: glGetIntegerv has an incomplete implementation on WebGL2");
-
+pub(crate) unsafe fn glGetIntegerv(pname: u32, data: *mut GLint) {
 	let data: &mut GLint = data.as_mut().unwrap();
-	*data = 0;
+	let value = get_gl().get_parameter(pname).unwrap_or(JsValue::NULL);
+
+	// framebuffer bindings are objects, map them back to their ids, null is the default framebuffer
+	*data = if let Some(framebuffer) = value.dyn_ref::<WebGlFramebuffer>() {
+		FRAME_BUFFERS.iter().find(|(_, f)| *f == framebuffer).map_or(0, |(id, _)| *id as GLint)
+	} else {
+		value.as_f64().unwrap_or(0.0) as GLint
+	};
 }
 
 // ==================== FRAME BUFFERS ====================
@@ -405,6 +485,12 @@ pub unsafe fn glFramebufferTexture2D(target: GLenum, attachment: GLenum, textarg
 	get_gl().framebuffer_texture_2d(target, attachment, textarget, texture, level)
 }
 
+pub unsafe fn glFramebufferTextureLayer(target: GLenum, attachment: GLenum, texture: GLuint, level: GLint, layer: GLint) {
+	debug_assert!(TEXTURES.contains_key(&texture));
+	let texture = TEXTURES.get(&texture);
+	get_gl().framebuffer_texture_layer(target, attachment, texture, level, layer)
+}
+
 pub unsafe fn glDeleteFramebuffers(n: GLsizei, framebuffers: *const GLuint) {
 	let framebuffers = unsafe { slice::from_raw_parts(framebuffers, n as usize) };
 
@@ -429,6 +515,17 @@ pub unsafe fn glDrawBuffers(n: GLsizei, bufs: *const GLenum) {
 	gl.draw_buffers(&array);
 }
 
+pub unsafe fn glInvalidateFramebuffer(target: GLenum, numAttachments: GLsizei, attachments: *const GLenum) {
+	let attachments = slice::from_raw_parts(attachments, numAttachments as usize);
+
+	let array = js_sys::Array::new_with_length(attachments.len() as _);
+	for (i, attachment) in attachments.iter().enumerate() {
+		array.set(i as u32, JsValue::from(*attachment));
+	}
+
+	get_gl().invalidate_framebuffer(target, &array).unwrap();
+}
+
 pub fn glDrawArrays(mode: GLenum, first: GLint, count: GLsizei) {
 	get_gl().draw_arrays(mode, first, count);
 }
@@ -652,6 +749,74 @@ pub unsafe fn glBindAttribLocation(program: GLuint, index: GLuint, name: *const
 	get_gl().bind_attrib_location(program, index, name);
 }
 
+pub unsafe fn glGetUniformBlockIndex(program: GLuint, name: *const GLchar) -> GLuint {
+	let program = PROGRAMS.get(&program).unwrap_throw();
+	let name = std::ffi::CStr::from_ptr(name).to_str().unwrap_throw();
+	get_gl().get_uniform_block_index(program, name)
+}
+
+pub unsafe fn glUniformBlockBinding(program: GLuint, block_index: GLuint, binding: GLuint) {
+	let program = PROGRAMS.get(&program).unwrap_throw();
+	get_gl().uniform_block_binding(program, block_index, binding);
+}
+
+pub unsafe fn glGetActiveUniformBlockiv(program: GLuint, block_index: GLuint, pname: GLenum, params: *mut GLint) {
+	let program = PROGRAMS.get(&program).unwrap_throw();
+	if pname == GL_UNIFORM_BLOCK_NAME_LENGTH {
+		// not queryable in WebGL2
+		*params = get_gl().get_active_uniform_block_name(program, block_index).map_or(0, |name| name.len() + 1) as GLint;
+		return;
+	}
+	let value = get_gl().get_active_uniform_block_parameter(program, block_index, pname).unwrap_throw();
+
+	// GL_UNIFORM_BLOCK_ACTIVE_UNIFORM_INDICES is an array, the rest are numbers or booleans
+	*params = value.as_f64().or_else(|| value.as_bool().map(|b| b as u8 as f64)).unwrap_or(0.0) as GLint;
+}
+
+pub unsafe fn glGetActiveUniformBlockName(program: GLuint, block_index: GLuint, bufSize: GLsizei, length: *mut GLsizei, name: *mut GLchar) {
+	let program = PROGRAMS.get(&program).unwrap_throw();
+	let block_name = get_gl().get_active_uniform_block_name(program, block_index).unwrap_throw();
+	write_name(&block_name, bufSize, length, name);
+}
+
+pub unsafe fn glGetActiveUniform(program: GLuint, index: GLuint, bufSize: GLsizei, length: *mut GLsizei, size: *mut GLint, type_: *mut GLenum, name: *mut GLchar) {
+	let program = PROGRAMS.get(&program).unwrap_throw();
+	let info = get_gl().get_active_uniform(program, index).unwrap_throw();
+	*size = info.size();
+	*type_ = info.type_();
+	write_name(&info.name(), bufSize, length, name);
+}
+
+pub unsafe fn glGetActiveAttrib(program: GLuint, index: GLuint, bufSize: GLsizei, length: *mut GLsizei, size: *mut GLint, type_: *mut GLenum, name: *mut GLchar) {
+	let program = PROGRAMS.get(&program).unwrap_throw();
+	let info = get_gl().get_active_attrib(program, index).unwrap_throw();
+	*size = info.size();
+	*type_ = info.type_();
+	write_name(&info.name(), bufSize, length, name);
+}
+
+pub unsafe fn glGetActiveUniformsiv(program: GLuint, uniformCount: GLsizei, uniformIndices: *const GLuint, pname: GLenum, params: *mut GLint) {
+	let program = PROGRAMS.get(&program).unwrap_throw();
+	let indices = js_sys::Uint32Array::from(slice::from_raw_parts(uniformIndices, uniformCount as usize));
+	let values: js_sys::Array = get_gl().get_active_uniforms(program, &indices, pname).unchecked_into();
+	for (i, value) in values.iter().enumerate() {
+		*params.add(i) = value.as_f64().or_else(|| value.as_bool().map(|b| b as u8 as f64)).unwrap_or(0.0) as GLint;
+	}
+}
+
+// null terminated and cut to bufSize, the way glGetActiveUniform and friends return names
+unsafe fn write_name(source: &str, bufSize: GLsizei, length: *mut GLsizei, name: *mut GLchar) {
+	if bufSize <= 0 {
+		return;
+	}
+	let len = source.len().min(bufSize as usize - 1);
+	std::ptr::copy_nonoverlapping(source.as_ptr(), name as *mut u8, len);
+	*name.add(len) = 0;
+	if !length.is_null() {
+		*length = len as GLsizei;
+	}
+}
+
 #[derive(Default)]
 struct ProgramInfo {
 	uniforms: BTreeMap<String, (WebGlActiveInfo, u32)>,
@@ -833,6 +998,19 @@ pub unsafe fn glGetProgramiv(program_idx: GLuint, pname: GLenum, params: *mut GL
 			let param = gl.get_program_parameter(program, p).as_bool().unwrap_throw();
 			*params = param as GLint;
 		}
+		p if p == GL_ACTIVE_UNIFORMS || p == GL_ACTIVE_ATTRIBUTES || p == GL_ACTIVE_UNIFORM_BLOCKS => {
+			*params = gl.get_program_parameter(program, p).as_f64().unwrap_throw() as GLint;
+		}
+		// not queryable in WebGL
+		p if p == GL_ACTIVE_UNIFORM_MAX_LENGTH || p == GL_ACTIVE_ATTRIBUTE_MAX_LENGTH => {
+			let uniforms = p == GL_ACTIVE_UNIFORM_MAX_LENGTH;
+			let count = gl.get_program_parameter(program, if uniforms { GL_ACTIVE_UNIFORMS } else { GL_ACTIVE_ATTRIBUTES }).as_f64().unwrap_throw() as u32;
+			*params = (0..count)
+				.filter_map(|i| if uniforms { gl.get_active_uniform(program, i) } else { gl.get_active_attrib(program, i) })
+				.map(|info| info.name().len() as GLint + 1)
+				.max()
+				.unwrap_or(0);
+		}
 		_p => {
 			#[cfg(feature = "log-impl")]
 			crate::error!("glGetProgramiv failed! Unsupported pname: {}", _p);
@@ -899,14 +1077,23 @@ pub fn glDisableVertexAttribArray(index: GLuint) {
 
 static mut TEXTURES: BTreeMap<u32, WebGlTexture> = BTreeMap::new();
 
-fn texture_size(internalformat: GLint, width: GLsizei, height: GLsizei) -> GLsizei {
-	match internalformat as u32 {
-		GL_ALPHA => width * height,
-		GL_RGB => width * height * 3,
-		GL_RGBA => width * height * 4,
-		// TextureFormat::RGB565 | TextureFormat::RGBA4 | TextureFormat::RGBA5551
-		_ => width * height * 3,
-	}
+/// Bytes of a `width`x`height` image with the given (unsized) format and pixel type
+fn texture_size(format: GLenum, type_: GLenum, width: GLsizei, height: GLsizei) -> GLsizei {
+	let components = match format {
+		GL_ALPHA | GL_LUMINANCE | GL_RED | GL_DEPTH_COMPONENT => 1,
+		GL_LUMINANCE_ALPHA | GL_RG => 2,
+		GL_RGB => 3,
+		_ => 4,
+	};
+	let pixel_size = match type_ {
+		// packed, all the components in one value
+		GL_UNSIGNED_SHORT_5_6_5 | GL_UNSIGNED_SHORT_4_4_4_4 | GL_UNSIGNED_SHORT_5_5_5_1 => 2,
+		GL_UNSIGNED_INT_24_8 => 4,
+		GL_UNSIGNED_BYTE => components,
+		GL_UNSIGNED_SHORT | GL_HALF_FLOAT => components * 2,
+		_ => components * 4,
+	};
+	width * height * pixel_size
 }
 
 pub unsafe fn glGenTextures(n: GLsizei, textures: *mut GLuint) {
@@ -940,7 +1127,13 @@ pub fn glPixelStorei(pname: GLenum, param: GLint) {
 }
 
 pub unsafe fn glReadPixels(x: GLint, y: GLint, width: GLsizei, height: GLsizei, format: GLenum, type_: GLenum, pixels: *mut ::std::os::raw::c_void) {
-	let size = texture_size(format as _, width, height) as _;
+	// with a pixel pack buffer bound, `pixels` is an offset into it
+	if PIXEL_PACK_BUFFER != 0 {
+		get_gl().read_pixels_with_i32(x, y, width, height, format, type_, pixels as i32).unwrap_throw();
+		return;
+	}
+
+	let size = texture_size(format, type_, width, height) as _;
 	let pixels = (pixels as *mut u8).as_mut().map(|p| slice::from_raw_parts_mut(p, size));
 	debug_assert!(pixels.as_ref().map(|p| p.len() >= size).unwrap_or(true));
 
@@ -954,7 +1147,7 @@ pub fn glTexParameteri(target: GLenum, pname: GLenum, param: GLint) {
 
 #[inline(never)]
 pub unsafe fn glTexImage2D(target: GLenum, level: GLint, internalformat: GLint, width: GLsizei, height: GLsizei, border: GLint, format: GLenum, type_: GLenum, pixels: *const ::std::os::raw::c_void) {
-	let length = texture_size(internalformat, width, height) as usize;
+	let length = texture_size(format, type_, width, height) as usize;
 	let pixels = (pixels as *const u8).as_ref().map(|p| slice::from_raw_parts(p, length));
 	get_gl()
 		.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(target, level, internalformat, width, height, border, format, type_, pixels)
@@ -963,13 +1156,52 @@ pub unsafe fn glTexImage2D(target: GLenum, level: GLint, internalformat: GLint,
 
 #[inline(never)]
 pub unsafe fn glTexSubImage2D(target: GLenum, level: GLint, xoffset: GLint, yoffset: GLint, width: GLsizei, height: GLsizei, format: GLenum, type_: GLenum, pixels: *const ::std::os::raw::c_void) {
-	let length = texture_size(format as _, width, height) as usize;
+	let length = texture_size(format, type_, width, height) as usize;
 	let pixels = (pixels as *const u8).as_ref().map(|p| slice::from_raw_parts(p, length));
 	get_gl()
 		.tex_sub_image_2d_with_i32_and_i32_and_u32_and_type_and_opt_u8_array(target, level, xoffset, yoffset, width, height, format, type_, pixels)
 		.unwrap_throw();
 }
 
+pub unsafe fn glTexImage3D(
+	target: GLenum,
+	level: GLint,
+	internalformat: GLint,
+	width: GLsizei,
+	height: GLsizei,
+	depth: GLsizei,
+	border: GLint,
+	format: GLenum,
+	type_: GLenum,
+	pixels: *const ::std::os::raw::c_void,
+) {
+	let length = (texture_size(format, type_, width, height) * depth) as usize;
+	let pixels = (pixels as *const u8).as_ref().map(|p| slice::from_raw_parts(p, length));
+	get_gl()
+		.tex_image_3d_with_opt_u8_array(target, level, internalformat, width, height, depth, border, format, type_, pixels)
+		.unwrap_throw();
+}
+
+pub unsafe fn glTexSubImage3D(
+	target: GLenum,
+	level: GLint,
+	xoffset: GLint,
+	yoffset: GLint,
+	zoffset: GLint,
+	width: GLsizei,
+	height: GLsizei,
+	depth: GLsizei,
+	format: GLenum,
+	type_: GLenum,
+	pixels: *const ::std::os::raw::c_void,
+) {
+	let length = (texture_size(format, type_, width, height) * depth) as usize;
+	let pixels = (pixels as *const u8).as_ref().map(|p| slice::from_raw_parts(p, length));
+	get_gl()
+		.tex_sub_image_3d_with_opt_u8_array(target, level, xoffset, yoffset, zoffset, width, height, depth, format, type_, pixels)
+		.unwrap_throw();
+}
+
 #[inline(always)]
 pub fn glCopyTexImage2D(target: GLenum, level: GLint, internalformat: GLenum, x: GLint, y: GLint, width: GLsizei, height: GLsizei, border: GLint) {
 	get_gl().copy_tex_image_2d(target, level, internalformat, x, y, width, height, border);
@@ -1063,6 +1295,43 @@ pub fn glColorMask(red: GLboolean, green: GLboolean, blue: GLboolean, alpha: GLb
 	get_gl().color_mask(red != 0, green != 0, blue != 0, alpha != 0)
 }
 
+// for extensions without web-sys bindings, look their functions up by name
+fn call_extension(extension: &str, function: &str, args: &[JsValue]) {
+	let Ok(Some(extension)) = get_gl().get_extension(extension) else {
+		return;
+	};
+	let function: js_sys::Function = js_sys::Reflect::get(&extension, &JsValue::from_str(function)).unwrap().unchecked_into();
+	function.apply(&extension, &args.iter().collect::<js_sys::Array>()).unwrap();
+}
+
+pub fn glEnablei(target: GLenum, index: GLuint) {
+	call_extension("OES_draw_buffers_indexed", "enableiOES", &[target.into(), index.into()])
+}
+
+pub fn glDisablei(target: GLenum, index: GLuint) {
+	call_extension("OES_draw_buffers_indexed", "disableiOES", &[target.into(), index.into()])
+}
+
+pub fn glColorMaski(index: GLuint, r: GLboolean, g: GLboolean, b: GLboolean, a: GLboolean) {
+	call_extension(
+		"OES_draw_buffers_indexed",
+		"colorMaskiOES",
+		&[index.into(), (r != 0).into(), (g != 0).into(), (b != 0).into(), (a != 0).into()],
+	)
+}
+
+pub fn glBlendFuncSeparatei(buf: GLuint, srcRGB: GLenum, dstRGB: GLenum, srcAlpha: GLenum, dstAlpha: GLenum) {
+	call_extension(
+		"OES_draw_buffers_indexed",
+		"blendFuncSeparateiOES",
+		&[buf.into(), srcRGB.into(), dstRGB.into(), srcAlpha.into(), dstAlpha.into()],
+	)
+}
+
+pub fn glBlendEquationSeparatei(buf: GLuint, modeRGB: GLenum, modeAlpha: GLenum) {
+	call_extension("OES_draw_buffers_indexed", "blendEquationSeparateiOES", &[buf.into(), modeRGB.into(), modeAlpha.into()])
+}
+
 #[inline(always)]
 pub fn glFrontFace(mode: GLenum) {
 	get_gl().front_face(mode)
@@ -1078,6 +1347,11 @@ pub fn glDepthRangef(n: GLfloat, f: GLfloat) {
 	get_gl().depth_range(n, f)
 }
 
+#[inline(always)]
+pub fn glPolygonOffset(factor: GLfloat, units: GLfloat) {
+	get_gl().polygon_offset(factor, units)
+}
+
 // ==================== STENCILS & CULLING====================
 
 #[inline(always)]
@@ -1177,8 +1451,76 @@ pub fn glGetQueryObjectui64v(id: GLuint, pname: GLenum, params: *mut GLuint64) {
 // 	pub fn glQueryCounter(id: GLenum, pname: GLenum);
 // 	pub fn glGetQueryObjectiv(id: GLuint, pname: GLenum, params: *mut GLint);
 
+// ============= SAMPLERS ================
+static mut SAMPLERS: BTreeMap<u32, WebGlSampler> = BTreeMap::new();
+
+pub unsafe fn glGenSamplers(n: GLsizei, samplers: *mut GLuint) {
+	for id in slice::from_raw_parts_mut(samplers, n as usize) {
+		if let Some(sampler) = get_gl().create_sampler() {
+			*id = counter::increment();
+			SAMPLERS.insert(*id, sampler);
+		}
+	}
+}
+
+pub unsafe fn glDeleteSamplers(n: GLsizei, samplers: *const GLuint) {
+	for id in slice::from_raw_parts(samplers, n as usize) {
+		let sampler = SAMPLERS.remove(id);
+		get_gl().delete_sampler(sampler.as_ref());
+	}
+}
+
+pub unsafe fn glBindSampler(unit: GLuint, sampler: GLuint) {
+	debug_assert!(SAMPLERS.contains_key(&sampler) || sampler == 0);
+	get_gl().bind_sampler(unit, SAMPLERS.get(&sampler));
+}
+
+pub unsafe fn glSamplerParameteri(sampler: GLuint, pname: GLenum, param: GLint) {
+	debug_assert!(SAMPLERS.contains_key(&sampler));
+	if let Some(sampler) = SAMPLERS.get(&sampler) {
+		get_gl().sampler_parameteri(sampler, pname, param);
+	}
+}
+
+pub unsafe fn glSamplerParameterf(sampler: GLuint, pname: GLenum, param: GLfloat) {
+	debug_assert!(SAMPLERS.contains_key(&sampler));
+	if let Some(sampler) = SAMPLERS.get(&sampler) {
+		get_gl().sampler_parameterf(sampler, pname, param);
+	}
+}
+
+// ============= SYNC ================
+static mut SYNCS: BTreeMap<u32, WebGlSync> = BTreeMap::new();
+
+pub unsafe fn glFenceSync(condition: GLenum, flags: GLbitfield) -> GLsync {
+	match get_gl().fence_sync(condition, flags) {
+		Some(sync) => {
+			let id = counter::increment();
+			SYNCS.insert(id, sync);
+			id as usize as GLsync
+		}
+		None => std::ptr::null_mut(),
+	}
+}
+
+pub unsafe fn glClientWaitSync(sync: GLsync, flags: GLbitfield, timeout: GLuint64) -> GLenum {
+	debug_assert!(SYNCS.contains_key(&(sync as usize as u32)));
+
+	match SYNCS.get(&(sync as usize as u32)) {
+		Some(sync) => get_gl().client_wait_sync_with_f64(sync, flags, timeout as f64),
+		None => GL_WAIT_FAILED,
+	}
+}
+
+pub unsafe fn glDeleteSync(sync: GLsync) {
+	let sync = SYNCS.remove(&(sync as usize as u32));
+	get_gl().delete_sync(sync.as_ref());
+}
+
 // ============= BUFFERS ================
 static mut BUFFERS: BTreeMap<u32, WebGlBuffer> = BTreeMap::new();
+// WebGL has no client memory readbacks, glReadPixels needs to know whether to write to a buffer
+static mut PIXEL_PACK_BUFFER: u32 = 0;
 
 pub unsafe fn glGenBuffers(n: GLsizei, buffers: *mut GLuint) {
 	debug_assert!(!buffers.is_null());
@@ -1209,13 +1551,30 @@ pub unsafe fn glBufferSubData(target: GLenum, offset: GLintptr, size: GLsizeiptr
 #[inline(always)]
 pub unsafe fn glBindBuffer(target: GLenum, buffer: GLuint) {
 	debug_assert!(BUFFERS.contains_key(&buffer) || buffer == 0);
+	if target == GL_PIXEL_PACK_BUFFER {
+		PIXEL_PACK_BUFFER = buffer;
+	}
 	get_gl().bind_buffer(target, BUFFERS.get(&buffer));
 }
 
+/// Desktop GL only, but WebGL has no `glMapBufferRange` to read buffers with.
+pub unsafe fn glGetBufferSubData(target: GLenum, offset: GLintptr, size: GLsizeiptr, data: *mut ::std::os::raw::c_void) {
+	let data = slice::from_raw_parts_mut(data as *mut u8, size as usize);
+	get_gl().get_buffer_sub_data_with_i32_and_u8_array(target, offset as _, data);
+}
+
+pub unsafe fn glBindBufferRange(target: GLenum, index: GLuint, buffer: GLuint, offset: GLintptr, size: GLsizeiptr) {
+	debug_assert!(BUFFERS.contains_key(&buffer) || buffer == 0);
+	get_gl().bind_buffer_range_with_i32_and_i32(target, index, BUFFERS.get(&buffer), offset as _, size as _);
+}
+
 pub unsafe fn glDeleteBuffers(n: GLsizei, buffers: *const GLuint) {
 	debug_assert!(!buffers.is_null());
 
 	for id in slice::from_raw_parts(buffers, n as _) {
+		if *id == PIXEL_PACK_BUFFER {
+			PIXEL_PACK_BUFFER = 0;
+		}
 		let buffer = BUFFERS.remove(id);
 		get_gl().delete_buffer(buffer.as_ref());
 	}
@@ -1244,6 +1603,22 @@ pub fn glDrawElementsInstanced(mode: GLenum, count: GLsizei, type_: GLenum, indi
 	get_gl().draw_elements_instanced_with_i32(mode, count, type_, indices as _, instance_count)
 }
 
+pub fn glDrawElementsInstancedBaseVertex(mode: GLenum, count: GLsizei, type_: GLenum, indices: *const ::std::os::raw::c_void, instance_count: GLsizei, base_vertex: GLint) {
+	let args: [JsValue; 7] = [mode.into(), count.into(), type_.into(), (indices as i32).into(), instance_count.into(), base_vertex.into(), 0.into()];
+	call_extension("WEBGL_draw_instanced_base_vertex_base_instance", "drawElementsInstancedBaseVertexBaseInstanceWEBGL", &args)
+}
+
+pub unsafe fn glMultiDrawElements(mode: GLenum, count: *const GLsizei, type_: GLenum, indices: *const *const ::std::os::raw::c_void, drawcount: GLsizei) {
+	let Ok(Some(extension)) = get_gl().get_extension("WEBGL_multi_draw") else {
+		return;
+	};
+	let mut counts = slice::from_raw_parts(count, drawcount as usize).to_vec();
+	let mut offsets: Vec<i32> = slice::from_raw_parts(indices, drawcount as usize).iter().map(|offset| *offset as i32).collect();
+	extension
+		.unchecked_into::<WebglMultiDraw>()
+		.multi_draw_elements_webgl_with_i32_array_and_i32_array(mode, &mut counts, 0, type_, &mut offsets, 0, drawcount);
+}
+
 // ================ CLEAR COLOUR ========
 
 #[inline(always)]
@@ -1261,6 +1636,12 @@ pub fn glClear(mask: GLbitfield) {
 	get_gl().clear(mask)
 }
 
+pub unsafe fn glClearBufferfv(buffer: GLenum, drawbuffer: GLint, value: *const GLfloat) {
+	// GL_COLOR takes 4 values, GL_DEPTH a single one
+	let len = if buffer == GL_COLOR { 4 } else { 1 };
+	get_gl().clear_bufferfv_with_f32_array(buffer, drawbuffer, slice::from_raw_parts(value, len));
+}
+
 #[inline(always)]
 pub fn glClearStencil(s: GLint) {
 	get_gl().clear_stencil(s)
@@ -1319,8 +1700,6 @@ pub unsafe fn glIsQuery(id: GLuint) -> bool {
 }
 
 // extern "C" {
-// 	pub fn glGetActiveAttrib(program: GLuint, index: GLuint, bufSize: GLsizei, length: *mut GLsizei, size: *mut GLint, type_: *mut GLenum, name: *mut GLchar);
-// 	pub fn glGetActiveUniform(program: GLuint, index: GLuint, bufSize: GLsizei, length: *mut GLsizei, size: *mut GLint, type_: *mut GLenum, name: *mut GLchar);
 // 	pub fn glGetAttachedShaders(program: GLuint, maxCount: GLsizei, count: *mut GLsizei, shaders: *mut GLuint);
 // 	pub fn glGetBooleanv(pname: GLenum, data: *mut GLboolean);
 // 	pub fn glGetBufferParameteriv(target: GLenum, pname: GLenum, params: *mut GLint);
@@ -1338,7 +1717,6 @@ pub unsafe fn glIsQuery(id: GLuint) -> bool {
 // 	pub fn glHint(target: GLenum, mode: GLenum);
 
 // 	pub fn glLineWidth(width: GLfloat);
-// 	pub fn glPolygonOffset(factor: GLfloat, units: GLfloat);
 // 	pub fn glReleaseShaderCompiler();
 // 	pub fn glRenderbufferStorage(target: GLenum, internalformat: GLenum, width: GLsizei, height: GLsizei);
 // 	pub fn glSampleCoverage(value: GLfloat, invert: GLboolean);
@@ -1445,15 +1823,12 @@ pub unsafe fn glIsQuery(id: GLuint) -> bool {
 // 	pub fn glUniform4uiv(location: GLint, count: GLsizei, value: *const GLuint);
 // 	pub fn glClearBufferiv(buffer: GLenum, drawbuffer: GLint, value: *const GLint);
 // 	pub fn glClearBufferuiv(buffer: GLenum, drawbuffer: GLint, value: *const GLuint);
-// 	pub fn glClearBufferfv(buffer: GLenum, drawbuffer: GLint, value: *const GLfloat);
 // 	pub fn glClearBufferfi(buffer: GLenum, drawbuffer: GLint, depth: GLfloat, stencil: GLint);
 // 	pub fn glGetStringi(name: GLenum, index: GLuint) -> *const GLubyte;
 // 	pub fn glCopyBufferSubData(readTarget: GLenum, writeTarget: GLenum, readOffset: GLintptr, writeOffset: GLintptr, size: GLsizeiptr);
 // 	pub fn glGetUniformIndices(program: GLuint, uniformCount: GLsizei, uniformNames: *const *const GLchar, uniformIndices: *mut GLuint);
-// 	pub fn glGetActiveUniformsiv(program: GLuint, uniformCount: GLsizei, uniformIndices: *const GLuint, pname: GLenum, params: *mut GLint);
 // 	pub fn glGetUniformBlockIndex(program: GLuint, uniformBlockName: *const GLchar) -> GLuint;
 // 	pub fn glGetActiveUniformBlockiv(program: GLuint, uniformBlockIndex: GLuint, pname: GLenum, params: *mut GLint);
-// 	pub fn glGetActiveUniformBlockName(program: GLuint, uniformBlockIndex: GLuint, bufSize: GLsizei, length: *mut GLsizei, uniformBlockName: *mut GLchar);
 // 	pub fn glUniformBlockBinding(program: GLuint, uniformBlockIndex: GLuint, uniformBlockBinding: GLuint);
 
 // 	pub fn glFenceSync(condition: GLenum, flags: GLbitfield) -> GLsync;
@@ -1465,13 +1840,8 @@ pub unsafe fn glIsQuery(id: GLuint) -> bool {
 // 	pub fn glGetSynciv(sync: GLsync, pname: GLenum, bufSize: GLsizei, length: *mut GLsizei, values: *mut GLint);
 // 	pub fn glGetInteger64i_v(target: GLenum, index: GLuint, data: *mut GLint64);
 // 	pub fn glGetBufferParameteri64v(target: GLenum, pname: GLenum, params: *mut GLint64);
-// 	pub fn glGenSamplers(count: GLsizei, samplers: *mut GLuint);
-// 	pub fn glDeleteSamplers(count: GLsizei, samplers: *const GLuint);
 // 	pub fn glIsSampler(sampler: GLuint) -> GLboolean;
-// 	pub fn glBindSampler(unit: GLuint, sampler: GLuint);
-// 	pub fn glSamplerParameteri(sampler: GLuint, pname: GLenum, param: GLint);
 // 	pub fn glSamplerParameteriv(sampler: GLuint, pname: GLenum, param: *const GLint);
-// 	pub fn glSamplerParameterf(sampler: GLuint, pname: GLenum, param: GLfloat);
 // 	pub fn glSamplerParameterfv(sampler: GLuint, pname: GLenum, param: *const GLfloat);
 // 	pub fn glGetSamplerParameteriv(sampler: GLuint, pname: GLenum, params: *mut GLint);
 // 	pub fn glGetSamplerParameterfv(sampler: GLuint, pname: GLenum, params: *mut GLfloat);
@@ -1484,7 +1854,6 @@ pub unsafe fn glIsQuery(id: GLuint) -> bool {
 // 	pub fn glGetProgramBinary(program: GLuint, bufSize: GLsizei, length: *mut GLsizei, binaryFormat: *mut GLenum, binary: *mut ::std::os::raw::c_void);
 // 	pub fn glProgramBinary(program: GLuint, binaryFormat: GLenum, binary: *const ::std::os::raw::c_void, length: GLsizei);
 // 	pub fn glProgramParameteri(program: GLuint, pname: GLenum, value: GLint);
-// 	pub fn glInvalidateFramebuffer(target: GLenum, numAttachments: GLsizei, attachments: *const GLenum);
 // 	pub fn glInvalidateSubFramebuffer(target: GLenum, numAttachments: GLsizei, attachments: *const GLenum, x: GLint, y: GLint, width: GLsizei, height: GLsizei);
 // 	pub fn glTexStorage2D(target: GLenum, levels: GLsizei, internalformat: GLenum, width: GLsizei, height: GLsizei);
 // 	pub fn glTexStorage3D(target: GLenum, levels: GLsizei, internalformat: GLenum, width: GLsizei, height: GLsizei, depth: GLsizei);
@@ -0,0 +1,485 @@
+//! `GlContext` checks on an offscreen EGL context (`LinuxBackend::Headless`),
+//! the doctests only run `NullContext`. Skipped when EGL can not be loaded.
+#![cfg(target_os = "linux")]
+
+use std::sync::{
+	atomic::{AtomicBool, Ordering},
+	Arc,
+};
+
+use miniquad_wasm_bindgen::*;
+
+const VERTEX: &str = "#version 300 es
+in vec2 in_pos;
+void main() { gl_Position = vec4(in_pos, 0.0, 1.0); }";
+
+const SOLID: &str = "#version 300 es
+precision mediump float;
+uniform vec4 color;
+out vec4 frag_color;
+void main() { frag_color = color; }";
+
+const RED: [u8; 4] = [255, 0, 0, 255];
+const GREEN: [u8; 4] = [0, 255, 0, 255];
+const BLUE: [u8; 4] = [0, 0, 255, 255];
+const BLACK: [u8; 4] = [0, 0, 0, 255];
+
+/// A triangle covering the whole viewport, and one outside of it
+const FULLSCREEN: [f32; 6] = [-1., -1., 3., -1., -1., 3.];
+const OFFSCREEN: [f32; 6] = [2., 2., 3., 2., 2., 3.];
+
+struct Quit;
+
+impl EventHandler for Quit {
+	fn update(&mut self) {}
+	fn draw(&mut self) {}
+}
+
+#[test]
+fn gl_context() {
+	let mut conf = conf::Conf {
+		window_width: 8,
+		window_height: 8,
+		sample_count: 0,
+		..Default::default()
+	};
+	conf.platform.linux_backend = conf::LinuxBackend::Headless;
+
+	// only one context per process, NATIVE_DISPLAY can not be set twice
+	let started = Arc::new(AtomicBool::new(false));
+	let started_in_context = started.clone();
+	let result = std::panic::catch_unwind(move || {
+		miniquad_wasm_bindgen::start(conf, move || {
+			started_in_context.store(true, Ordering::SeqCst);
+			let mut ctx = window::new_rendering_backend();
+			let ctx = &mut *ctx;
+			copy_texture_region(ctx);
+			blit_pass(ctx);
+			attachment_actions(ctx);
+			color_targets(ctx);
+			draws(ctx);
+			samplers(ctx);
+			shadow_sampler(ctx);
+			uniform_buffer(ctx);
+			reload_shader(ctx);
+			frame_stats(ctx);
+			reflect_shader(ctx);
+			window::quit();
+			Box::new(Quit)
+		})
+	});
+	match result {
+		Err(_) if !started.load(Ordering::SeqCst) => eprintln!("no EGL, GlContext checks skipped"),
+		Err(panic) => std::panic::resume_unwind(panic),
+		Ok(()) => {}
+	}
+}
+
+fn solid_pipeline(ctx: &mut dyn RenderingBackend, params: PipelineParams) -> Pipeline {
+	let meta = ShaderMeta {
+		uniforms: UniformBlockLayout {
+			uniforms: vec![UniformDesc::new("color", UniformType::Float4)],
+		},
+		..Default::default()
+	};
+	let shader = ctx.new_shader(ShaderSource::new(VERTEX, SOLID), meta).unwrap();
+	ctx.new_pipeline(&[BufferLayout::default()], &[VertexAttribute::new("in_pos", VertexFormat::Float2)], shader, params)
+}
+
+fn render_target(ctx: &mut dyn RenderingBackend, size: u32) -> (TextureId, RenderPass) {
+	let texture = ctx.new_render_texture(TextureParams {
+		width: size,
+		height: size,
+		..Default::default()
+	});
+	(texture, ctx.new_render_pass(texture, None))
+}
+
+/// Every pixel of the texture, when they are all the same
+fn read_color(ctx: &mut dyn RenderingBackend, texture: TextureId) -> [u8; 4] {
+	let params = ctx.texture_params(texture);
+	let mut bytes = vec![0; (params.width * params.height * 4) as usize];
+	ctx.texture_read_pixels(texture, &mut bytes);
+	let color: [u8; 4] = bytes[..4].try_into().unwrap();
+	assert!(bytes.chunks(4).all(|pixel| pixel == color), "{:?} is not uniformly {:?}", bytes, color);
+	color
+}
+
+fn copy_texture_region(ctx: &mut dyn RenderingBackend) {
+	let atlas = ctx.new_texture_from_rgba8(2, 1, &[RED, GREEN].concat());
+	let sprite = ctx.new_texture_from_rgba8(1, 1, &[0; 4]);
+	ctx.copy_texture_region(atlas, sprite, (1, 0, 1, 1), (0, 0));
+	assert_eq!(read_color(ctx, sprite), GREEN);
+
+	// into a part of a bigger texture
+	let (target, pass) = render_target(ctx, 2);
+	ctx.begin_pass(Some(pass), PassAction::clear_color(0.0, 0.0, 0.0, 1.0));
+	ctx.end_render_pass();
+	ctx.copy_texture_region(atlas, target, (0, 0, 1, 1), (1, 1));
+	let mut pixels = [0; 16];
+	ctx.texture_read_pixels(target, &mut pixels);
+	assert_eq!(pixels[..], [BLACK, BLACK, BLACK, RED].concat());
+}
+
+fn blit_pass(ctx: &mut dyn RenderingBackend) {
+	let (scene, scene_pass) = render_target(ctx, 4);
+	let (upscaled, upscaled_pass) = render_target(ctx, 8);
+	ctx.begin_pass(Some(scene_pass), PassAction::clear_color(1.0, 0.0, 0.0, 1.0));
+	ctx.end_render_pass();
+	ctx.begin_pass(Some(upscaled_pass), PassAction::clear_color(0.0, 0.0, 1.0, 1.0));
+	ctx.end_render_pass();
+
+	ctx.blit_pass(Some(scene_pass), Some(upscaled_pass), (0, 0, 4, 4), (0, 0, 8, 8), FilterMode::Nearest);
+	assert_eq!(read_color(ctx, upscaled), RED);
+	assert_eq!(read_color(ctx, scene), RED);
+
+	// the default framebuffer is 8x8 too
+	ctx.begin_default_pass(PassAction::clear_color(0.0, 0.0, 1.0, 1.0));
+	ctx.end_render_pass();
+	ctx.blit_pass(Some(scene_pass), None, (0, 0, 4, 4), (0, 0, 4, 8), FilterMode::Nearest);
+	let mut pixels = [0; 8 * 8 * 4];
+	ctx.default_pass_read_pixels(&mut pixels);
+	for (i, pixel) in pixels.chunks(4).enumerate() {
+		assert_eq!(pixel, if i % 8 < 4 { RED } else { BLUE }, "pixel {}", i);
+	}
+}
+
+/// Two 4x4 color attachments
+fn mrt_target(ctx: &mut dyn RenderingBackend) -> ([TextureId; 2], RenderPass) {
+	let params = TextureParams {
+		width: 4,
+		height: 4,
+		..Default::default()
+	};
+	let textures = [ctx.new_render_texture(params), ctx.new_render_texture(params)];
+	(textures, ctx.new_render_pass_mrt(&textures, &[], None))
+}
+
+fn attachment_actions(ctx: &mut dyn RenderingBackend) {
+	let ([first, second], pass) = mrt_target(ctx);
+	ctx.begin_pass(
+		Some(pass),
+		PassAction::ClearAttachments {
+			colors: vec![Some((1.0, 0.0, 0.0, 1.0)), Some((0.0, 0.0, 1.0, 1.0))],
+			depth: None,
+			stencil: None,
+		},
+	);
+	ctx.end_render_pass();
+	assert_eq!((read_color(ctx, first), read_color(ctx, second)), (RED, BLUE));
+
+	// the second attachment is loaded
+	ctx.begin_pass(
+		Some(pass),
+		PassAction::Attachments {
+			colors: vec![AttachmentAction {
+				load: LoadOp::Clear((0.0, 1.0, 0.0, 1.0)),
+				store: StoreOp::Store,
+			}],
+			depth: AttachmentAction {
+				load: LoadOp::DontCare,
+				store: StoreOp::Discard,
+			},
+			stencil: AttachmentAction {
+				load: LoadOp::DontCare,
+				store: StoreOp::Discard,
+			},
+		},
+	);
+	ctx.end_render_pass();
+	assert_eq!((read_color(ctx, first), read_color(ctx, second)), (GREEN, BLUE));
+}
+
+fn color_targets(ctx: &mut dyn RenderingBackend) {
+	if !ctx.info().features.independent_blend {
+		return;
+	}
+	let fragment = "#version 300 es
+precision mediump float;
+layout(location = 0) out vec4 first;
+layout(location = 1) out vec4 second;
+void main() { first = vec4(1.0); second = vec4(1.0); }";
+	let shader = ctx.new_shader(ShaderSource::new(VERTEX, fragment), ShaderMeta::default()).unwrap();
+	let mut color_targets = [None; MAX_COLOR_ATTACHMENTS];
+	color_targets[0] = Some(ColorTargetState {
+		color_write: (true, false, false, true),
+		..Default::default()
+	});
+	color_targets[1] = Some(ColorTargetState::default());
+	let params = PipelineParams { color_targets, ..Default::default() };
+	let pipeline = ctx.new_pipeline(&[BufferLayout::default()], &[VertexAttribute::new("in_pos", VertexFormat::Float2)], shader, params);
+	let vertex_buffer = ctx.new_buffer(BufferType::VertexBuffer, BufferUsage::Immutable, BufferSource::slice(&FULLSCREEN));
+
+	let ([first, second], pass) = mrt_target(ctx);
+	ctx.begin_pass(Some(pass), PassAction::clear_color(0.0, 0.0, 0.0, 1.0));
+	ctx.apply_pipeline(&pipeline);
+	ctx.apply_bindings(&Bindings {
+		vertex_buffers: vec![vertex_buffer],
+		index_buffer: None,
+		images: vec![],
+		samplers: vec![],
+	});
+	ctx.draw_arrays(0, 3, 1);
+	ctx.end_render_pass();
+	assert_eq!((read_color(ctx, first), read_color(ctx, second)), (RED, [255; 4]));
+}
+
+/// Draws red with `draw` into a black target and returns the result
+fn draw_red(ctx: &mut dyn RenderingBackend, pipeline: Pipeline, vertices: &[f32], indices: Option<&[u16]>, draw: impl FnOnce(&mut dyn RenderingBackend)) -> [u8; 4] {
+	let vertex_buffer = ctx.new_buffer(BufferType::VertexBuffer, BufferUsage::Immutable, BufferSource::slice(vertices));
+	let index_buffer = indices.map(|indices| ctx.new_buffer(BufferType::IndexBuffer, BufferUsage::Immutable, BufferSource::slice(indices)));
+	let (target, pass) = render_target(ctx, 4);
+	ctx.begin_pass(Some(pass), PassAction::clear_color(0.0, 0.0, 0.0, 1.0));
+	ctx.apply_pipeline(&pipeline);
+	ctx.apply_bindings(&Bindings {
+		vertex_buffers: vec![vertex_buffer],
+		index_buffer,
+		images: vec![],
+		samplers: vec![],
+	});
+	ctx.apply_uniforms(UniformsSource::table(&[1.0f32, 0.0, 0.0, 1.0]));
+	draw(ctx);
+	ctx.end_render_pass();
+	read_color(ctx, target)
+}
+
+fn draws(ctx: &mut dyn RenderingBackend) {
+	let pipeline = solid_pipeline(ctx, PipelineParams::default());
+	let both = [OFFSCREEN, FULLSCREEN].concat();
+
+	// without an index buffer the elements are the vertices
+	assert_eq!(draw_red(ctx, pipeline, &FULLSCREEN, None, |ctx| ctx.draw(0, 3, 1)), RED);
+	assert_eq!(draw_red(ctx, pipeline, &both, None, |ctx| ctx.draw_arrays(3, 3, 1)), RED);
+	assert_eq!(draw_red(ctx, pipeline, &both, None, |ctx| ctx.draw_arrays(0, 3, 1)), BLACK);
+	assert_eq!(draw_red(ctx, pipeline, &both, Some(&[0, 1, 2, 3, 4, 5]), |ctx| ctx.draw(3, 3, 1)), RED);
+
+	let ranges = [DrawRange { base_element: 0, num_elements: 3 }, DrawRange { base_element: 3, num_elements: 3 }];
+	assert_eq!(draw_red(ctx, pipeline, &both, Some(&[0, 1, 2, 3, 4, 5]), |ctx| ctx.draw_multi(&ranges)), RED);
+	assert_eq!(draw_red(ctx, pipeline, &both, Some(&[0, 1, 2, 3, 4, 5]), |ctx| ctx.draw_multi(&ranges[..1])), BLACK);
+
+	if ctx.info().features.base_vertex {
+		assert_eq!(draw_red(ctx, pipeline, &both, Some(&[0, 1, 2]), |ctx| ctx.draw_base_vertex(0, 3, 1, 3)), RED);
+	}
+
+	let strip = solid_pipeline(
+		ctx,
+		PipelineParams {
+			primitive_type: PrimitiveType::TriangleStrip,
+			..Default::default()
+		},
+	);
+	let quad = [-1f32, -1., 1., -1., -1., 1., 1., 1.];
+	assert_eq!(draw_red(ctx, strip, &quad, None, |ctx| ctx.draw_arrays(0, 4, 1)), RED);
+
+	let result = ctx.try_new_buffer(BufferType::IndexBuffer, BufferUsage::Immutable, BufferSource::slice(&[0u64, 1, 2]));
+	assert!(matches!(result, Err(GraphicsError::UnsupportedIndexSize(8))));
+}
+
+/// Renders `fragment` sampling `images` into a 1x1 target, with a float uniform if it has one
+fn sample(ctx: &mut dyn RenderingBackend, fragment: &str, meta: ShaderMeta, images: Vec<TextureId>, samplers: Vec<Option<SamplerId>>, uniform: Option<f32>) -> [u8; 4] {
+	let shader = ctx.new_shader(ShaderSource::new(VERTEX, fragment), meta).unwrap();
+	let pipeline = ctx.new_pipeline(&[BufferLayout::default()], &[VertexAttribute::new("in_pos", VertexFormat::Float2)], shader, PipelineParams::default());
+	let vertex_buffer = ctx.new_buffer(BufferType::VertexBuffer, BufferUsage::Immutable, BufferSource::slice(&FULLSCREEN));
+	let (target, pass) = render_target(ctx, 1);
+	ctx.begin_pass(Some(pass), PassAction::clear_color(0.0, 0.0, 0.0, 1.0));
+	ctx.apply_pipeline(&pipeline);
+	ctx.apply_bindings(&Bindings {
+		vertex_buffers: vec![vertex_buffer],
+		index_buffer: None,
+		images,
+		samplers,
+	});
+	if let Some(uniform) = uniform {
+		ctx.apply_uniforms(UniformsSource::table(&uniform));
+	}
+	ctx.draw(0, 3, 1);
+	ctx.end_render_pass();
+	read_color(ctx, target)
+}
+
+fn samplers(ctx: &mut dyn RenderingBackend) {
+	if !ctx.info().features.samplers {
+		return;
+	}
+	// between the texel centers, 70% of the way to the white one
+	let fragment = "#version 300 es
+precision mediump float;
+uniform sampler2D tex;
+out vec4 frag_color;
+void main() { frag_color = texture(tex, vec2(0.6, 0.5)); }";
+	let meta = ShaderMeta {
+		images: vec![ImageDesc::new("tex", ImageType::Float)],
+		..Default::default()
+	};
+	let texture = ctx.new_texture_from_rgba8(2, 1, &[BLACK, [255; 4]].concat());
+	ctx.texture_set_filter(texture, FilterMode::Linear, MipmapFilterMode::None);
+	let nearest = ctx.new_sampler(SamplerParams {
+		min_filter: FilterMode::Nearest,
+		mag_filter: FilterMode::Nearest,
+		..Default::default()
+	});
+
+	assert_eq!(sample(ctx, fragment, meta.clone(), vec![texture], vec![Some(nearest)], None), [255; 4]);
+	// the texture's own filtering without a sampler
+	let [linear, ..] = sample(ctx, fragment, meta, vec![texture], vec![None], None);
+	assert!((160..200).contains(&linear), "{}", linear);
+}
+
+fn shadow_sampler(ctx: &mut dyn RenderingBackend) {
+	let shadow_map = ctx.new_render_texture(TextureParams {
+		width: 4,
+		height: 4,
+		format: TextureFormat::Depth32,
+		compare: Some(Comparison::LessOrEqual),
+		..Default::default()
+	});
+	let pass = ctx.new_render_pass_mrt(&[], &[], Some(shadow_map));
+	ctx.begin_pass(
+		Some(pass),
+		PassAction::Clear {
+			color: None,
+			depth: Some(0.5),
+			stencil: None,
+		},
+	);
+	ctx.end_render_pass();
+
+	let fragment = "#version 300 es
+precision mediump float;
+uniform highp sampler2DShadow shadow_map;
+uniform float depth;
+out vec4 frag_color;
+void main() { frag_color = vec4(vec3(texture(shadow_map, vec3(0.5, 0.5, depth))), 1.0); }";
+	let meta = ShaderMeta {
+		uniforms: UniformBlockLayout {
+			uniforms: vec![UniformDesc::new("depth", UniformType::Float1)],
+		},
+		images: vec![ImageDesc::new("shadow_map", ImageType::Shadow)],
+		..Default::default()
+	};
+	assert_eq!(sample(ctx, fragment, meta.clone(), vec![shadow_map], vec![], Some(0.25)), [255; 4]);
+	assert_eq!(sample(ctx, fragment, meta, vec![shadow_map], vec![], Some(0.75)), BLACK);
+}
+
+fn uniform_buffer(ctx: &mut dyn RenderingBackend) {
+	if ctx.info().uniform_buffer_offset_alignment == 0 {
+		return;
+	}
+	let fragment = "#version 300 es
+precision mediump float;
+layout(std140) uniform Tint { vec4 unused; vec4 color; };
+out vec4 frag_color;
+void main() { frag_color = color; }";
+	let meta = ShaderMeta {
+		uniform_blocks: vec![UniformBlockDesc::new(
+			"Tint",
+			0,
+			vec![UniformDesc::new("unused", UniformType::Float4), UniformDesc::new("color", UniformType::Float4)],
+		)],
+		..Default::default()
+	};
+	let shader = ctx.new_shader(ShaderSource::new(VERTEX, fragment), meta).unwrap();
+	let pipeline = ctx.new_pipeline(&[BufferLayout::default()], &[VertexAttribute::new("in_pos", VertexFormat::Float2)], shader, PipelineParams::default());
+
+	// the block of the draw is at an aligned offset
+	let offset = ctx.info().uniform_buffer_offset_alignment.max(32);
+	let mut uniforms = vec![0f32; (offset + 32) / 4];
+	uniforms[offset / 4 + 4..].copy_from_slice(&[0.0, 1.0, 0.0, 1.0]);
+	let buffer = ctx.new_buffer(BufferType::UniformBuffer, BufferUsage::Immutable, BufferSource::slice(&uniforms));
+	let vertex_buffer = ctx.new_buffer(BufferType::VertexBuffer, BufferUsage::Immutable, BufferSource::slice(&FULLSCREEN));
+
+	let (target, pass) = render_target(ctx, 1);
+	ctx.begin_pass(Some(pass), PassAction::clear_color(0.0, 0.0, 0.0, 1.0));
+	ctx.apply_pipeline(&pipeline);
+	ctx.apply_bindings(&Bindings {
+		vertex_buffers: vec![vertex_buffer],
+		index_buffer: None,
+		images: vec![],
+		samplers: vec![],
+	});
+	ctx.apply_uniform_buffer(0, buffer, offset, 32);
+	ctx.draw(0, 3, 1);
+	ctx.end_render_pass();
+	assert_eq!(read_color(ctx, target), GREEN);
+}
+
+fn reload_shader(ctx: &mut dyn RenderingBackend) {
+	let meta = ShaderMeta {
+		uniforms: UniformBlockLayout {
+			uniforms: vec![UniformDesc::new("color", UniformType::Float4)],
+		},
+		..Default::default()
+	};
+	let shader = ctx.new_shader(ShaderSource::new(VERTEX, SOLID), meta.clone()).unwrap();
+	let pipeline = ctx.new_pipeline(&[BufferLayout::default()], &[VertexAttribute::new("in_pos", VertexFormat::Float2)], shader, PipelineParams::default());
+	assert_eq!(draw_red(ctx, pipeline, &FULLSCREEN, None, |ctx| ctx.draw(0, 3, 1)), RED);
+
+	let swapped = SOLID.replace("frag_color = color", "frag_color = color.bgra");
+	ctx.reload_shader(shader, ShaderSource::new(VERTEX, &swapped), meta.clone()).unwrap();
+	assert_eq!(draw_red(ctx, pipeline, &FULLSCREEN, None, |ctx| ctx.draw(0, 3, 1)), BLUE);
+
+	// a typo keeps the last program
+	assert!(ctx.reload_shader(shader, ShaderSource::new(VERTEX, "#version 300 es\nvoid main() { oops }"), meta).is_err());
+	assert_eq!(draw_red(ctx, pipeline, &FULLSCREEN, None, |ctx| ctx.draw(0, 3, 1)), BLUE);
+}
+
+fn frame_stats(ctx: &mut dyn RenderingBackend) {
+	let pipeline = solid_pipeline(ctx, PipelineParams::default());
+	let vertex_buffer = ctx.new_buffer(BufferType::VertexBuffer, BufferUsage::Immutable, BufferSource::slice(&FULLSCREEN));
+	ctx.commit_frame();
+
+	let bindings = Bindings {
+		vertex_buffers: vec![vertex_buffer],
+		index_buffer: None,
+		images: vec![],
+		samplers: vec![],
+	};
+	ctx.begin_default_pass(PassAction::Nothing);
+	for _ in 0..2 {
+		ctx.apply_pipeline(&pipeline);
+		ctx.apply_bindings(&bindings);
+		ctx.apply_uniforms(UniformsSource::table(&[1.0f32, 0.0, 0.0, 1.0]));
+		ctx.draw_arrays(0, 3, 1);
+	}
+	ctx.end_render_pass();
+	ctx.commit_frame();
+
+	let stats = ctx.frame_stats();
+	assert_eq!((stats.draw_calls, stats.pipeline_switches, stats.bytes_uploaded), (2, 1, 0));
+}
+
+fn reflect_shader(ctx: &mut dyn RenderingBackend) {
+	let vertex = "#version 300 es
+in vec2 in_pos;
+in vec4 in_color;
+layout(std140) uniform Camera { mat4 view; vec2 offsets[2]; } camera;
+uniform vec4 tints[3];
+out vec4 color;
+void main() {
+	gl_Position = camera.view * vec4(in_pos + camera.offsets[1], 0.0, 1.0);
+	color = in_color * tints[2];
+}";
+	let fragment = "#version 300 es
+precision mediump float;
+uniform sampler2D tex;
+uniform highp sampler2DShadow shadow;
+in vec4 color;
+out vec4 frag_color;
+void main() { frag_color = color * texture(tex, vec2(0.5)) * texture(shadow, vec3(0.5)); }";
+	let reflection = ctx.reflect_shader(ShaderSource::new(vertex, fragment)).unwrap();
+
+	// GL reports "tints[0]", "Camera.view" and "Camera.offsets[0]"
+	assert_eq!(reflection.meta.uniforms.uniforms, [UniformDesc::new("tints", UniformType::Float4).array(3)]);
+	assert_eq!(reflection.meta.images, [ImageDesc::new("tex", ImageType::Float), ImageDesc::new("shadow", ImageType::Shadow)]);
+	assert_eq!(
+		reflection.meta.uniform_blocks,
+		[UniformBlockDesc::new(
+			"Camera",
+			0,
+			vec![UniformDesc::new("view", UniformType::Mat4), UniformDesc::new("offsets", UniformType::Float2).array(2)]
+		)]
+	);
+	let attributes: Vec<_> = reflection.attributes.iter().map(|attribute| (attribute.name.as_str(), attribute.format)).collect();
+	assert_eq!(attributes, [("in_pos", VertexFormat::Float2), ("in_color", VertexFormat::Float4)]);
+	assert!(reflection.validate(&reflection.meta).is_ok());
+}